@@ -108,12 +108,12 @@ function Main(props) {
             </Form.Field>
             <Form.Field style={{ textAlign: 'center' }}>
               <TxButton
-                label="Register Bank Account"
+                label="Request IBAN Link"
                 type="SIGNED-TX"
                 setStatus={setStatus}
                 attrs={{
                   palletRpc: 'fiatRamps',
-                  callable: 'createAccount',
+                  callable: 'requestIbanLink',
                   inputParams: [formIban],
                   paramFields: [true],
                 }}
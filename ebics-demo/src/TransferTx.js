@@ -5,11 +5,11 @@ import { useSubstrateState } from './substrate-lib'
 import { TxButton } from './substrate-lib/components'
 
 // Derive destination type from destination options
-const deriveDest = (dest, addressTo, ibanTo) => {
+const deriveDest = (dest, addressTo, ibanTo, beneficiary) => {
   switch (dest) {
     case "IBAN":
       return {
-          "Iban": ibanTo
+          "Iban": [ibanTo, beneficiary]
       }
     case "Address":
       return {
@@ -156,8 +156,8 @@ export default function Main(props) {
             attrs={{
               palletRpc: 'fiatRamps',
               callable: 'transfer',
-              inputParams: [base.mul(new BN(amount)), deriveDest(destination, addressTo, ibanTo)],
-              paramFields: [true, true],
+              inputParams: [base.mul(new BN(amount)), 'EUR', deriveDest(destination, addressTo, ibanTo, recipient.beneficiary)],
+              paramFields: [true, true, true],
             }}
           />
         </Form.Field>
@@ -28,6 +28,17 @@ const initialState = {
     name: "Jack",
     address: "5Hg6mE6QCiqDFH21yjDGe2JSezEZSTn9mBsZa6JsC3wo438c",
     iban: "CH2108307000289537313",
+    // Holder of the IBAN, required by the bank for the payment
+    beneficiary: {
+      name: "Jack",
+      street: "Bahnhofstrasse",
+      street_nr: "1",
+      zip: "6340",
+      city: "Baar",
+      country: "CH",
+      bic: "",
+      national: true,
+    },
     donations: 0,
   }
 }
@@ -1,7 +1,7 @@
 use hex_literal::hex;
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, Decode, FiatRampsConfig, GrandpaConfig,
-	RuntimeGenesisConfig, Signature, SudoConfig, SystemConfig, WASM_BINARY,
+	AccountId, AssetsConfig, AuraConfig, BalancesConfig, Decode, FiatRampsConfig, GrandpaConfig,
+	RuntimeGenesisConfig, Signature, SudoConfig, SystemConfig, CHF_ASSET_ID, WASM_BINARY,
 };
 use sc_service::{ChainType, Properties};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -77,6 +77,9 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				],
 				true,
 				demo_account_id.clone(),
+				ChainType::Development,
+				// Accept fake receipts
+				true,
 			)
 		},
 		// Bootnodes
@@ -126,6 +129,9 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				],
 				true,
 				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				ChainType::Local,
+				// Fake receipts can be allowed by sudo
+				false,
 			)
 		},
 		// Bootnodes
@@ -150,6 +156,8 @@ fn testnet_genesis(
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool,
 	ocw_account: AccountId,
+	chain_type: ChainType,
+	fake_receipts_allowed: bool,
 ) -> RuntimeGenesisConfig {
 	RuntimeGenesisConfig {
 		system: SystemConfig {
@@ -170,11 +178,25 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: Some(root_key.clone()),
 		},
 		transaction_payment: Default::default(),
 		fiat_ramps: FiatRampsConfig {
-			accounts: vec![(ocw_account, b"CH2108307000289537313".to_vec())],
+			accounts: vec![(ocw_account.clone(), b"CH2108307000289537313".to_vec())],
+			// Development and local chains only, where receipts do not need to be proofs
+			fake_receipts_permitted: matches!(
+				chain_type,
+				ChainType::Development | ChainType::Local
+			),
+			fake_receipts_allowed,
+			// More authorities and a higher threshold are set by governance
+			ocw_authorities: vec![ocw_account],
+			attestation_threshold: 1,
+		},
+		assets: AssetsConfig {
+			// Swiss francs, minted by the fiat-ramps pallet
+			assets: vec![(CHF_ASSET_ID, root_key, true, 1)],
+			..Default::default()
 		},
 	}
 }
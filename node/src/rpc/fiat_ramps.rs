@@ -0,0 +1,251 @@
+//! RPC methods to query the state of the fiat-ramps pallet.
+//!
+//! IBANs, references and currencies are returned as strings, so that clients do not have to
+//! decode the raw storage of the pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_runtime::{
+	fiat_ramps::types::BurnRequestStatus, AccountId, BlockNumber,
+	FiatRampsApi as FiatRampsRuntimeApi, FiatRampsBurnRequest, FiatRampsQueuedStatements,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+
+/// Burn request with its funds held in escrow
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnRequestInfo {
+	/// Id of the request
+	pub id: u64,
+	/// IBAN of the burner
+	pub burner: String,
+	/// Account that made the request
+	pub burner_account: AccountId,
+	/// IBAN the funds are sent to
+	pub dest_iban: String,
+	/// Name of the holder of `dest_iban`
+	pub beneficiary: String,
+	/// Amount of the request
+	pub amount: NumberOrHex,
+	/// ISO 4217 code of the currency
+	pub currency: String,
+	/// Status of the request, i.e `pending`, `in_flight`, `unknown`, `submitted`, `failed` or
+	/// `refund_failed`
+	pub status: String,
+	/// Number of failed attempts to send the unpeg request
+	pub attempts: u32,
+	/// Block of the last status change
+	pub updated_at: BlockNumber,
+}
+
+impl From<FiatRampsBurnRequest> for BurnRequestInfo {
+	fn from(request: FiatRampsBurnRequest) -> Self {
+		let (status, attempts) = match request.status {
+			BurnRequestStatus::Pending => ("pending", 0),
+			BurnRequestStatus::Submitted => ("submitted", 0),
+			BurnRequestStatus::Confirmed => ("confirmed", 0),
+			BurnRequestStatus::Failed { attempts } => ("failed", attempts),
+			BurnRequestStatus::Refunded => ("refunded", 0),
+			BurnRequestStatus::InFlight { attempts } => ("in_flight", attempts),
+			BurnRequestStatus::Unknown { attempts } => ("unknown", attempts),
+			BurnRequestStatus::RefundFailed => ("refund_failed", 0),
+		};
+
+		Self {
+			id: request.id,
+			burner: to_string(&request.burner),
+			burner_account: request.burner_account,
+			dest_iban: to_string(&request.dest_iban),
+			beneficiary: to_string(&request.beneficiary.name),
+			amount: request.amount.into(),
+			currency: to_string(&request.currency),
+			status: status.into(),
+			attempts,
+			updated_at: request.updated_at,
+		}
+	}
+}
+
+/// Statement batch waiting for verification
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedStatementsInfo {
+	/// Id of the batch
+	pub batch_id: u64,
+	/// URL for the receipt of the statements
+	pub receipt_url: String,
+	/// Number of statements in the batch
+	pub statements: u32,
+	/// Number of transactions in all statements of the batch
+	pub transactions: u32,
+	/// Block number when the statements were queued
+	pub queued_at: BlockNumber,
+	/// Last block in which the batch can be verified
+	pub expires_at: BlockNumber,
+}
+
+impl From<FiatRampsQueuedStatements> for QueuedStatementsInfo {
+	fn from(status: FiatRampsQueuedStatements) -> Self {
+		Self {
+			batch_id: status.batch_id,
+			receipt_url: to_string(&status.receipt_url),
+			statements: status.statements,
+			transactions: status.transactions,
+			queued_at: status.queued_at,
+			expires_at: status.expires_at,
+		}
+	}
+}
+
+/// Lossy conversion of bytes stored in the pallet to a string
+fn to_string(bytes: &[u8]) -> String {
+	String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Fiat-ramps RPC methods
+#[rpc(client, server)]
+pub trait FiatRampsApi<BlockHash> {
+	/// Account linked to the IBAN
+	#[method(name = "fiatRamps_accountByIban")]
+	fn account_by_iban(&self, iban: String, at: Option<BlockHash>) -> RpcResult<Option<AccountId>>;
+
+	/// IBAN linked to the account
+	#[method(name = "fiatRamps_ibanByAccount")]
+	fn iban_by_account(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<String>>;
+
+	/// Burn requests of the account whose funds are still held in escrow
+	#[method(name = "fiatRamps_pendingBurnRequests")]
+	fn pending_burn_requests(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<BurnRequestInfo>>;
+
+	/// Statement batches that are waiting for verification
+	#[method(name = "fiatRamps_queuedStatements")]
+	fn queued_statements(&self, at: Option<BlockHash>) -> RpcResult<Vec<QueuedStatementsInfo>>;
+
+	/// Image id of the risc0 module that verifies the statements
+	#[method(name = "fiatRamps_risc0ImageId")]
+	fn risc0_image_id(&self, at: Option<BlockHash>) -> RpcResult<Option<H256>>;
+}
+
+/// Provides RPC methods to query the state of the fiat-ramps pallet
+pub struct FiatRamps<C, Block> {
+	/// Shared reference to the client
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> FiatRamps<C, Block> {
+	/// Creates a new instance of the fiat-ramps RPC handler
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error code of failed runtime API calls
+const RUNTIME_ERROR: i32 = 1;
+
+/// Converts a runtime API error into an RPC error
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		RUNTIME_ERROR,
+		"Runtime error",
+		Some(format!("{:?}", err)),
+	))
+	.into()
+}
+
+impl<C, Block> FiatRampsApiServer<<Block as BlockT>::Hash> for FiatRamps<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: FiatRampsRuntimeApi<Block>,
+{
+	fn account_by_iban(
+		&self,
+		iban: String,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<AccountId>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		self.client
+			.runtime_api()
+			.account_by_iban(at, iban.into_bytes())
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn iban_by_account(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<String>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let iban = self
+			.client
+			.runtime_api()
+			.iban_by_account(at, account)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(iban.as_deref().map(to_string))
+	}
+
+	fn pending_burn_requests(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<BurnRequestInfo>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let requests = self
+			.client
+			.runtime_api()
+			.pending_burn_requests(at, account)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(requests.into_iter().map(Into::into).collect())
+	}
+
+	fn queued_statements(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<QueuedStatementsInfo>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let batches = self
+			.client
+			.runtime_api()
+			.queued_statements(at)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(batches.into_iter().map(Into::into).collect())
+	}
+
+	fn risc0_image_id(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Option<H256>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let image_id = self
+			.client
+			.runtime_api()
+			.risc0_image_id(at)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(image_id.map(H256::from))
+	}
+}
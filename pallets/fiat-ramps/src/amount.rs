@@ -0,0 +1,107 @@
+//! Fixed-point conversion of fiat amounts
+//!
+//! On-chain amounts are integers with `Config::FiatDecimals` decimal places, e.g with
+//! 4 decimals `12.34` is represented as `123_400`. The same conversion is used for amounts
+//! received from the EBICS service and for amounts sent to it.
+use crate::types::AmountError;
+use lite_json::NumberValue;
+
+/// Returns `10^exponent`
+fn pow10(exponent: u32) -> Result<u128, AmountError> {
+	10u128.checked_pow(exponent).ok_or(AmountError::Overflow)
+}
+
+/// Converts a JSON number to an amount with `decimals` decimal places
+///
+/// Numbers in scientific notation are supported. Fails if the amount does not fit into
+/// `i128`, or if the number has more decimal places than `decimals`.
+pub fn from_json_number(number: &NumberValue, decimals: u32) -> Result<i128, AmountError> {
+	// Digits of the number without the decimal point
+	let digits = (number.integer as u128)
+		.checked_mul(pow10(number.fraction_length)?)
+		.and_then(|digits| digits.checked_add(number.fraction as u128))
+		.ok_or(AmountError::Overflow)?;
+
+	if digits == 0 {
+		return Ok(0)
+	}
+
+	// Number of zeros to append to `digits` to get the fixed-point amount, if negative
+	// that many digits have to be removed instead
+	let scale =
+		i64::from(decimals) + i64::from(number.exponent) - i64::from(number.fraction_length);
+
+	let magnitude = if scale >= 0 {
+		let factor = u32::try_from(scale).map_err(|_| AmountError::Overflow).and_then(pow10)?;
+		digits.checked_mul(factor).ok_or(AmountError::Overflow)?
+	} else {
+		match u32::try_from(-scale).ok().and_then(|scale| 10u128.checked_pow(scale)) {
+			Some(divisor) if digits % divisor == 0 => digits / divisor,
+			_ => return Err(AmountError::TooPrecise),
+		}
+	};
+
+	let amount = i128::try_from(magnitude).map_err(|_| AmountError::Overflow)?;
+
+	Ok(if number.negative { -amount } else { amount })
+}
+
+/// Converts a decimal number, e.g `1234.56`, to an amount with `decimals` decimal places
+///
+/// Used for amounts of ISO 20022 messages, which are unsigned and never use scientific
+/// notation.
+pub fn from_decimal(text: &[u8], decimals: u32) -> Result<i128, AmountError> {
+	let (integer, fraction) = match text.iter().position(|c| *c == b'.') {
+		Some(point) => (&text[..point], &text[point + 1..]),
+		None => (text, &[][..]),
+	};
+
+	if integer.is_empty() || !integer.iter().chain(fraction).all(u8::is_ascii_digit) {
+		return Err(AmountError::NotANumber)
+	}
+
+	let number = |digits: &[u8]| {
+		digits
+			.iter()
+			.try_fold(0u64, |n, c| n.checked_mul(10)?.checked_add(u64::from(c - b'0')))
+			.ok_or(AmountError::Overflow)
+	};
+
+	from_json_number(
+		&NumberValue {
+			integer: number(integer)?,
+			fraction: number(fraction)?,
+			fraction_length: fraction.len() as u32,
+			exponent: 0,
+			negative: false,
+		},
+		decimals,
+	)
+}
+
+/// Converts an amount with `decimals` decimal places to a JSON number
+///
+/// Trailing zeros of the fraction are omitted. Fails if the integer or the fraction part
+/// does not fit into a JSON number.
+pub fn to_json_number(amount: i128, decimals: u32) -> Result<NumberValue, AmountError> {
+	let unit = pow10(decimals)?;
+	let magnitude = amount.unsigned_abs();
+
+	let integer = u64::try_from(magnitude / unit).map_err(|_| AmountError::Overflow)?;
+
+	let mut fraction = magnitude % unit;
+	let mut fraction_length = decimals;
+
+	while fraction_length > 0 && fraction % 10 == 0 {
+		fraction /= 10;
+		fraction_length -= 1;
+	}
+
+	Ok(NumberValue {
+		integer,
+		fraction: u64::try_from(fraction).map_err(|_| AmountError::Overflow)?,
+		fraction_length,
+		exponent: 0,
+		negative: amount < 0,
+	})
+}
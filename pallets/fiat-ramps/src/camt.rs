@@ -0,0 +1,264 @@
+//! ISO 20022 camt.053 and camt.054 messages
+//!
+//! EBICS delivers end of day statements as camt.053 and intraday debit and credit
+//! notifications as camt.054 messages. Both are parsed to the same bank accounts and
+//! transactions as the JSON statements of the EBICS service, so that they are queued and
+//! processed the same way.
+use crate::{
+	amount::from_decimal,
+	types::*,
+	utils::parse_date,
+	xml::{self, Element},
+	Config,
+};
+use frame_support::{traits::Get, BoundedVec};
+use sp_std::{vec, vec::Vec};
+
+/// Statement of a camt message, or the type and index of the entry and the reason the
+/// statement could not be parsed
+pub type CamtStatement<T> =
+	Result<(BankAccountOf<T>, Vec<TransactionOf<T>>), (Option<(TransactionType, u32)>, ParseError)>;
+
+/// Parses a camt.053 or camt.054 message
+///
+/// Returns the message id and the statements, or notifications, of the message. Receipts of
+/// camt messages are published under their message id.
+pub fn parse_message<T: Config>(
+	message: &[u8],
+) -> Result<(StringOf<T>, Vec<CamtStatement<T>>), ParseError> {
+	let document = xml::parse(message).map_err(ParseError::InvalidXml)?;
+
+	// Notifications do not report balances
+	let (report, statements, has_balances) = if let Some(report) = document.child("BkToCstmrStmt") {
+		(report, report.all("Stmt"), true)
+	} else if let Some(report) = document.child("BkToCstmrDbtCdtNtfctn") {
+		(report, report.all("Ntfctn"), false)
+	} else {
+		return Err(ParseError::MissingField("BkToCstmrStmt"))
+	};
+
+	let message_id = bounded(report.path(&["GrpHdr", "MsgId"]), "MsgId")?;
+
+	Ok((
+		message_id,
+		statements
+			.map(|statement| parse_statement::<T>(statement, has_balances))
+			.collect(),
+	))
+}
+
+/// Parses a statement (`Stmt`) or notification (`Ntfctn`)
+fn parse_statement<T: Config>(statement: &Element, has_balances: bool) -> CamtStatement<T> {
+	let decimals = T::FiatDecimals::get();
+
+	let iban = bounded(statement.path(&["Acct", "Id", "IBAN"]), "IBAN").map_err(|e| (None, e))?;
+
+	let (balance, last_updated) = match find_balance(statement, &["CLBD"], decimals) {
+		Ok(Some(balance)) => balance,
+		Ok(None) if !has_balances => (0, 0),
+		Ok(None) => return Err((None, ParseError::MissingField("Bal"))),
+		Err(e) => return Err((None, e)),
+	};
+	let (opening_balance, _) = find_balance(statement, &["OPBD", "PRCD"], decimals)
+		.map_err(|e| (None, e))?
+		.unwrap_or_default();
+
+	// Currency of the account, or of its balances if the account does not report it
+	let currency = statement
+		.path(&["Acct", "Ccy"])
+		.map(|currency| &currency.text[..])
+		.or_else(|| statement.path(&["Bal", "Amt"]).and_then(|amount| amount.attribute("Ccy")))
+		.map(|currency| {
+			CurrencyCode::try_from(currency).map_err(|_| (None, ParseError::InvalidField("Ccy")))
+		})
+		.transpose()?;
+
+	let mut transactions = Vec::new();
+	for (index, entry) in statement.all("Ntry").enumerate() {
+		transactions.extend(
+			parse_entry::<T>(entry, decimals)
+				.map_err(|(tx_type, e)| (Some((tx_type, index as u32)), e))?,
+		);
+	}
+
+	Ok((
+		BankAccount {
+			iban,
+			// Overdrawn account is reported with zero balance
+			opening_balance: opening_balance.max(0) as u128,
+			balance: balance.max(0) as u128,
+			currency,
+			last_updated,
+			beneficiary: None,
+		},
+		transactions,
+	))
+}
+
+/// Parses the transactions of an entry (`Ntry`)
+///
+/// Batch entries consist of multiple transactions, each with its own amount and reference.
+/// Entries that are not booked yet are skipped, they are reported again once booked.
+fn parse_entry<T: Config>(
+	entry: &Element,
+	decimals: u32,
+) -> Result<Vec<TransactionOf<T>>, (TransactionType, ParseError)> {
+	// Reversals are booked in the opposite direction of the original entry, so the indicator
+	// is already the direction of the funds
+	let tx_type = match entry.child("CdtDbtInd").map(|indicator| &indicator.text[..]) {
+		Some(b"CRDT") => TransactionType::Incoming,
+		Some(b"DBIT") => TransactionType::Outgoing,
+		Some(_) => return Err((TransactionType::None, ParseError::InvalidField("CdtDbtInd"))),
+		None => return Err((TransactionType::None, ParseError::MissingField("CdtDbtInd"))),
+	};
+
+	// Status is a code since camt.053.001.08
+	let status = entry.child("Sts").map(|status| status.child("Cd").unwrap_or(status));
+	if status.map_or(false, |status| status.text != b"BOOK") {
+		return Ok(Vec::new())
+	}
+
+	let details = entry
+		.all("NtryDtls")
+		.flat_map(|details| details.all("TxDtls"))
+		.collect::<Vec<_>>();
+
+	let transactions = match &details[..] {
+		[] | [_] => {
+			let entry_reference = entry.child("AcctSvcrRef").or_else(|| entry.child("NtryRef"));
+			parse_transaction::<T>(
+				entry,
+				details.first().copied(),
+				entry_reference,
+				tx_type,
+				decimals,
+			)
+			.map(|transaction| vec![transaction])
+		},
+		// Transactions of a batch are identified by their own references
+		_ => details
+			.iter()
+			.map(|&details| {
+				let entry_reference = details.path(&["Refs", "AcctSvcrRef"]);
+				parse_transaction::<T>(entry, Some(details), entry_reference, tx_type, decimals)
+			})
+			.collect(),
+	};
+
+	transactions.map_err(|e| (tx_type, e))
+}
+
+/// Parses a transaction of an entry from its details (`TxDtls`)
+fn parse_transaction<T: Config>(
+	entry: &Element,
+	details: Option<&Element>,
+	entry_reference: Option<&Element>,
+	tx_type: TransactionType,
+	decimals: u32,
+) -> Result<TransactionOf<T>, ParseError> {
+	let (party, account) = match tx_type {
+		TransactionType::Incoming => ("Dbtr", "DbtrAcct"),
+		_ => ("Cdtr", "CdtrAcct"),
+	};
+	let parties = details.and_then(|details| details.child("RltdPties"));
+	let remittance = details.and_then(|details| details.child("RmtInf"));
+
+	// Amount of the transaction in the currency of the account, or the amount of the entry
+	let amount = details
+		.and_then(|details| {
+			details.child("Amt").or_else(|| details.path(&["AmtDtls", "TxAmt", "Amt"]))
+		})
+		.or_else(|| entry.child("Amt"))
+		.ok_or(ParseError::MissingField("Amt"))?;
+
+	// Name is only informative, it is cut instead of dropping the statement
+	let name = parties
+		.and_then(|parties| parties.child(party)?.find("Nm"))
+		.map(|name| name.text.clone())
+		.unwrap_or_default();
+
+	Ok(Transaction {
+		// Fees and card payments have no counterparty account
+		iban: bounded_or_empty(
+			parties.and_then(|parties| parties.child(account)?.find("IBAN")),
+			"IBAN",
+		)?,
+		name: BoundedVec::truncate_from(name),
+		currency: amount
+			.attribute("Ccy")
+			.ok_or(ParseError::MissingField("Ccy"))?
+			.to_vec()
+			.try_into()
+			.map_err(|_| ParseError::FieldTooLong("Ccy"))?,
+		amount: from_decimal(&amount.text, decimals)
+			.map_err(|e| ParseError::InvalidAmount("Amt", e))?
+			.unsigned_abs(),
+		// Unstructured remittance information or the structured creditor reference
+		reference: bounded_or_empty(
+			remittance.and_then(|remittance| {
+				remittance.child("Ustrd").or_else(|| remittance.find("Ref"))
+			}),
+			"Ustrd",
+		)?,
+		tx_type,
+		entry_reference: entry_reference
+			.map(|entry_reference| bounded(Some(entry_reference), "AcctSvcrRef"))
+			.transpose()?,
+		booking_date: entry.child("BookgDt").and_then(date).unwrap_or_default(),
+		validation_date: entry.child("ValDt").and_then(date).unwrap_or_default(),
+	})
+}
+
+/// Amount and date of the first balance (`Bal`) with one of the type codes
+///
+/// Debit balances, i.e of overdrawn accounts, are negative
+fn find_balance(
+	statement: &Element,
+	codes: &[&str],
+	decimals: u32,
+) -> Result<Option<(i128, u64)>, ParseError> {
+	let balance = statement.all("Bal").find(|balance| {
+		balance
+			.path(&["Tp", "CdOrPrtry", "Cd"])
+			.map_or(false, |code| codes.iter().any(|c| code.text == c.as_bytes()))
+	});
+
+	let balance = match balance {
+		Some(balance) => balance,
+		None => return Ok(None),
+	};
+
+	let amount = balance.child("Amt").ok_or(ParseError::MissingField("Amt"))?;
+	let amount =
+		from_decimal(&amount.text, decimals).map_err(|e| ParseError::InvalidAmount("Bal", e))?;
+	let amount = match balance.child("CdtDbtInd").map(|indicator| &indicator.text[..]) {
+		Some(b"DBIT") => -amount,
+		_ => amount,
+	};
+
+	Ok(Some((amount, balance.child("Dt").and_then(date).unwrap_or_default())))
+}
+
+/// Unix timestamp in milliseconds of a date (`Dt`) or date and time (`DtTm`)
+fn date(element: &Element) -> Option<u64> {
+	let date = element.child("Dt").or_else(|| element.child("DtTm"))?;
+	parse_date(&date.text.iter().map(|c| *c as char).collect::<Vec<_>>())
+}
+
+/// Text of the element, fails if the element is missing or the text does not fit into
+/// `MaxLength` bytes
+fn bounded<MaxLength: Get<u32>>(
+	element: Option<&Element>,
+	field: &'static str,
+) -> Result<BoundedVec<u8, MaxLength>, ParseError> {
+	let element = element.ok_or(ParseError::MissingField(field))?;
+	element.text.clone().try_into().map_err(|_| ParseError::FieldTooLong(field))
+}
+
+/// Text of the element, or empty if the element is missing
+fn bounded_or_empty<MaxLength: Get<u32>>(
+	element: Option<&Element>,
+	field: &'static str,
+) -> Result<BoundedVec<u8, MaxLength>, ParseError> {
+	element.map_or(Ok(BoundedVec::default()), |element| bounded(Some(element), field))
+}
@@ -0,0 +1,283 @@
+//! HTTP client of the offchain worker
+//!
+//! Requests are sent to endpoints of `ApiUrl` with a deadline of `Config::HttpTimeout`.
+//! Timeouts and server errors of GET requests are retried up to `Config::HttpRetries` times,
+//! waiting twice as long before every retry. POST requests instruct payments and are sent once,
+//! since a request that timed out might still have been executed by the EBICS service. The
+//! caller decides what to do with such an unknown outcome. Several POST requests can be sent at
+//! once with `post_all`, they share a single deadline, so that waiting for them takes no longer
+//! than a single request. The last error of an endpoint is kept
+//! in the persistent offchain storage under `fiat_ramps::http_error::<endpoint>`, so that
+//! operators can inspect it.
+//!
+//! If the node has a secret under `fiat_ramps::api_secret` in its persistent offchain storage,
+//! requests are signed with HMAC-SHA256 of the method, path, timestamp and hash of the body.
+//! The secret is shared with the EBICS service only and never leaves the node. Requests are not
+//! signed with the `ramp` key: the EBICS service does not follow the chain to learn the
+//! authority set, and the secret of a node can be revoked on the service without a runtime
+//! call. Nodes without a secret send unsigned requests, which is only meant for development,
+//! every such request is logged as an error.
+use crate::{types::OcwError, ApiUrl, Config};
+use alloc::{format, string::String};
+use frame_support::traits::Get;
+use lite_json::{parse_json, JsonValue};
+use sp_core::hexdisplay::HexDisplay;
+use sp_io::hashing::sha2_256;
+use sp_runtime::offchain::{
+	http::{Error as HttpError, HttpResult, Method, PendingRequest, Request},
+	storage::StorageValueRef,
+	Duration, StorageKind, Timestamp,
+};
+use sp_std::{vec, vec::Vec};
+
+/// Milliseconds to wait before the first retry
+const RETRY_BACKOFF: u64 = 500;
+
+/// Prefix of the offchain storage keys of the last error per endpoint
+const LAST_ERROR_PREFIX: &[u8] = b"fiat_ramps::http_error::";
+
+/// Offchain storage key of the secret that requests are signed with
+pub const API_SECRET_KEY: &[u8] = b"fiat_ramps::api_secret";
+
+/// Block size of SHA-256
+const HMAC_BLOCK_SIZE: usize = 64;
+
+/// Sends a GET request to the endpoint and returns the response body
+pub fn get<T: Config>(endpoint: &str) -> Result<Vec<u8>, OcwError> {
+	send::<T>(Method::Get, endpoint, None).map_err(|e| record_error(endpoint, e))
+}
+
+/// Sends a GET request to the endpoint and parses the response as JSON
+pub fn get_json<T: Config>(endpoint: &str) -> Result<JsonValue, OcwError> {
+	send::<T>(Method::Get, endpoint, None)
+		.and_then(|body| parse(&body))
+		.map_err(|e| record_error(endpoint, e))
+}
+
+/// Sends a POST request with a body of the content type to the endpoint and returns the
+/// response body
+pub fn post<T: Config>(
+	endpoint: &str,
+	content_type: &str,
+	body: Vec<u8>,
+) -> Result<Vec<u8>, OcwError> {
+	send::<T>(Method::Post, endpoint, Some((content_type, body)))
+		.map_err(|e| record_error(endpoint, e))
+}
+
+/// Sends POST requests with the bodies of the content type to the endpoint at once and waits
+/// for their responses until a single deadline
+///
+/// Returns the response body of every request, in the order of the bodies
+pub fn post_all<T: Config>(
+	endpoint: &str,
+	content_type: &str,
+	bodies: Vec<Vec<u8>>,
+) -> Vec<Result<Vec<u8>, OcwError>> {
+	let url = match url::<T>(endpoint) {
+		Ok(url) => url,
+		Err(e) => return bodies.iter().map(|_| Err(record_error(endpoint, e))).collect(),
+	};
+	let deadline = deadline::<T>();
+
+	let mut results = Vec::with_capacity(bodies.len());
+	let mut pending = Vec::new();
+
+	for body in bodies {
+		match start::<T>(Method::Post, &url, &Some((content_type, body)), deadline) {
+			Ok(request) => {
+				pending.push((results.len(), request));
+				results.push(Err(OcwError::Timeout));
+			},
+			Err(e) => results.push(Err(e)),
+		}
+	}
+
+	let (indices, requests): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+	let responses = PendingRequest::try_wait_all(requests, deadline);
+	for (index, response) in indices.into_iter().zip(responses) {
+		results[index] = read_response(response);
+	}
+
+	results
+		.into_iter()
+		.map(|result| result.map_err(|e| record_error(endpoint, e)))
+		.collect()
+}
+
+/// Parses a response body as JSON
+pub fn parse(body: &[u8]) -> Result<JsonValue, OcwError> {
+	let json = core::str::from_utf8(body).map_err(|_| OcwError::InvalidUtf8)?;
+
+	log::info!("[OCW] JSON received: {}", json);
+
+	parse_json(json).map_err(|_| OcwError::InvalidJson)
+}
+
+/// Writes the error as the last error of the endpoint and returns it
+pub fn record_error(endpoint: &str, error: OcwError) -> OcwError {
+	log::error!("[OCW] Request to {} failed: {:?}", endpoint, error);
+
+	let key = [LAST_ERROR_PREFIX, endpoint.as_bytes()].concat();
+	StorageValueRef::persistent(&key).set(&(sp_io::offchain::timestamp().unix_millis(), error));
+
+	error
+}
+
+/// Last error of the endpoint and the unix timestamp in milliseconds when it occurred
+pub fn last_error(endpoint: &str) -> Option<(u64, OcwError)> {
+	let key = [LAST_ERROR_PREFIX, endpoint.as_bytes()].concat();
+	StorageValueRef::persistent(&key).get().ok().flatten()
+}
+
+/// Number of times a failed request of the method is sent again
+///
+/// Only GET requests are idempotent and retried, a POST might have been executed even if no
+/// response arrived.
+pub fn max_retries<T: Config>(method: &Method) -> u32 {
+	match method {
+		Method::Get => T::HttpRetries::get(),
+		_ => 0,
+	}
+}
+
+/// Sends the request, retrying timeouts and server errors of idempotent requests with
+/// exponential backoff
+fn send<T: Config>(
+	method: Method,
+	endpoint: &str,
+	body: Option<(&str, Vec<u8>)>,
+) -> Result<Vec<u8>, OcwError> {
+	let url = url::<T>(endpoint)?;
+
+	let max_retries = max_retries::<T>(&method);
+	let mut retries = 0;
+
+	loop {
+		match send_once::<T>(method.clone(), &url, &body) {
+			Err(e) if e.is_retryable() && retries < max_retries => {
+				let backoff = RETRY_BACKOFF.saturating_mul(2u64.saturating_pow(retries));
+				log::warn!("[OCW] Request to {} failed: {:?}, retry in {}ms", url, e, backoff);
+
+				sp_io::offchain::sleep_until(
+					sp_io::offchain::timestamp().add(Duration::from_millis(backoff)),
+				);
+				retries += 1;
+			},
+			result => return result,
+		}
+	}
+}
+
+/// Url of the endpoint of the EBICS service
+fn url<T: Config>(endpoint: &str) -> Result<String, OcwError> {
+	let api_url = ApiUrl::<T>::get();
+	let api_url = core::str::from_utf8(&api_url).map_err(|_| OcwError::InvalidUtf8)?;
+
+	Ok(format!("{}/{}", api_url, endpoint))
+}
+
+/// Deadline of requests sent now
+fn deadline<T: Config>() -> Timestamp {
+	sp_io::offchain::timestamp().add(Duration::from_millis(T::HttpTimeout::get()))
+}
+
+/// Sends the request once and waits for the response until the deadline
+fn send_once<T: Config>(
+	method: Method,
+	url: &str,
+	body: &Option<(&str, Vec<u8>)>,
+) -> Result<Vec<u8>, OcwError> {
+	let deadline = deadline::<T>();
+	let pending = start::<T>(method, url, body, deadline)?;
+
+	read_response(pending.try_wait(deadline))
+}
+
+/// Starts the request, the response has to arrive before the deadline
+fn start<T: Config>(
+	method: Method,
+	url: &str,
+	body: &Option<(&str, Vec<u8>)>,
+	deadline: Timestamp,
+) -> Result<PendingRequest, OcwError> {
+	// Signed again on every retry, since the timestamp changes
+	let signature = api_secret().map(|secret| {
+		let timestamp = sp_io::offchain::timestamp().unix_millis();
+		let body = body.as_ref().map_or(&[][..], |(_, body)| &body[..]);
+		let message = signing_message(method.as_ref(), path(url), timestamp, body);
+
+		(format!("{}", timestamp), format!("{}", HexDisplay::from(&hmac_sha256(&secret, &message))))
+	});
+	if signature.is_none() {
+		log::error!("[OCW] No API secret set, sending unsigned request to {}", url);
+	}
+
+	let mut request = Request::new(url)
+		.method(method)
+		.body(body.as_ref().map(|(_, body)| vec![body.clone()]).unwrap_or_default())
+		.deadline(deadline);
+
+	if let Some((content_type, _)) = body {
+		request = request.add_header("Content-Type", content_type).add_header("accept", "*/*");
+	}
+	if let Some((timestamp, signature)) = &signature {
+		request = request
+			.add_header("X-Ramp-Timestamp", timestamp)
+			.add_header("X-Ramp-Signature", signature);
+	}
+
+	request.send().map_err(|_| OcwError::Transport)
+}
+
+/// Body of a successful response of a request that was waited for
+fn read_response(response: Result<HttpResult, PendingRequest>) -> Result<Vec<u8>, OcwError> {
+	let response = match response {
+		Ok(Ok(response)) => response,
+		Ok(Err(HttpError::DeadlineReached)) | Err(_) => return Err(OcwError::Timeout),
+		Ok(Err(_)) => return Err(OcwError::Transport),
+	};
+
+	if response.code != 200 {
+		return Err(OcwError::HttpStatus(response.code))
+	}
+
+	Ok(response.body().collect())
+}
+
+/// Secret of the node to sign requests with, if any
+fn api_secret() -> Option<Vec<u8>> {
+	sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, API_SECRET_KEY)
+		.filter(|secret| !secret.is_empty())
+}
+
+/// Path and query of the url
+fn path(url: &str) -> &str {
+	let rest = url.find("://").map_or(url, |start| &url[start + 3..]);
+	rest.find('/').map_or("/", |start| &rest[start..])
+}
+
+/// Message that is signed for a request
+///
+/// Lines of the method, the path, the unix timestamp in milliseconds and the hex encoded
+/// SHA-256 hash of the body. The body of a GET request is empty.
+pub fn signing_message(method: &str, path: &str, timestamp: u64, body: &[u8]) -> Vec<u8> {
+	format!("{}\n{}\n{}\n{}", method, path, timestamp, HexDisplay::from(&sha2_256(body)))
+		.into_bytes()
+}
+
+/// HMAC-SHA256 (RFC 2104) of the message
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+	// Keys longer than the block size are hashed first
+	let mut block = [0u8; HMAC_BLOCK_SIZE];
+	if key.len() > HMAC_BLOCK_SIZE {
+		block[..32].copy_from_slice(&sha2_256(key));
+	} else {
+		block[..key.len()].copy_from_slice(key);
+	}
+
+	let pad = |byte: u8| block.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+	let inner = sha2_256(&[pad(0x36), message.to_vec()].concat());
+
+	sha2_256(&[pad(0x5c), inner.to_vec()].concat())
+}
@@ -4,7 +4,7 @@ use core::convert::TryInto;
 use crate::{types::*, *};
 use sp_std::default::Default;
 
-use self::utils::{extract_value, parse_object};
+use self::utils::{extract_amount, extract_bounded, extract_date, extract_value, parse_object};
 
 impl<T: SigningTypes> SignedPayload<T> for Payload<T::Public> {
 	fn public(&self) -> T::Public {
@@ -35,14 +35,77 @@ impl Deserialize<Vec<u8>> for Vec<u8> {
 	}
 }
 
-impl Deserialize<u128> for u128 {
-	fn deserialize(json: &JsonValue) -> Option<u128> {
-		json.clone().to_number().map(|num| {
-			let value_1 = num.integer as u128 * 10_u128.pow(num.exponent as u32 + 10);
-			let value_2 =
-				num.fraction as u128 * 10_u128.pow(num.exponent as u32 + 10 - num.fraction_length);
-			value_1 + value_2
-		})
+impl TransactionType {
+	/// Returns the opposite direction of the transaction
+	pub fn reversed(&self) -> Self {
+		match self {
+			Self::Incoming => Self::Outgoing,
+			Self::Outgoing => Self::Incoming,
+			Self::None => Self::None,
+		}
+	}
+}
+
+impl StatementFormat {
+	/// Endpoint of the EBICS service that serves statements in this format
+	pub fn endpoint(&self) -> &'static str {
+		match self {
+			Self::Json => "api-v1/bankstatements",
+			Self::Camt053 => "api-v1/camt053",
+			Self::Camt054 => "api-v1/camt054",
+		}
+	}
+
+	/// Whether statements in this format report the balances of the account
+	pub fn reports_balances(&self) -> bool {
+		!matches!(self, Self::Camt054)
+	}
+}
+
+impl OcwError {
+	/// Whether the request might succeed when it is sent again
+	pub fn is_retryable(&self) -> bool {
+		matches!(self, Self::Timeout | Self::HttpStatus(500..=599))
+	}
+}
+
+impl From<OcwError> for &'static str {
+	fn from(error: OcwError) -> Self {
+		match error {
+			OcwError::Transport => "Error in sending http request",
+			OcwError::Timeout => "Http request deadline reached",
+			OcwError::HttpStatus(_) => "Unexpected http response status",
+			OcwError::InvalidUtf8 => "Error in converting response to string",
+			OcwError::InvalidJson => "Error in parsing json",
+			OcwError::Schema => "Unexpected structure of the response",
+		}
+	}
+}
+
+impl PaymentFormat {
+	/// Endpoint to which payment initiations in this format are posted
+	pub fn endpoint(&self) -> &'static str {
+		match self {
+			Self::Json => "api-v1/unpeg",
+			Self::Pain001 => "api-v1/pain001",
+			Self::JsonBatch => "api-v1/unpeg/batch",
+		}
+	}
+
+	/// Content type of payment initiations in this format
+	pub fn content_type(&self) -> &'static str {
+		match self {
+			Self::Json | Self::JsonBatch => "application/json",
+			Self::Pain001 => "application/xml",
+		}
+	}
+}
+
+impl<BlockNumber: PartialOrd, Label> ImageIdInfo<BlockNumber, Label> {
+	/// Whether receipts of the image are accepted at the block
+	pub fn is_active(&self, block_number: &BlockNumber) -> bool {
+		&self.activation <= block_number &&
+			self.sunset.as_ref().map_or(true, |sunset| block_number < sunset)
 	}
 }
 
@@ -51,69 +114,310 @@ impl<MaxIbanLength: Get<u32>, MaxStringLength: Get<u32>>
 	Transaction<MaxIbanLength, MaxStringLength>
 {
 	// Get single transaction instance from json
-	pub fn from_json_statement(json: &JsonValue, tx_type: &TransactionType) -> Option<Self> {
-		if let Some(obj) = json.as_object() {
-			let iban = extract_value::<Vec<u8>>("iban", obj).try_into().expect("Invalid IBAN");
-			let name = extract_value::<Vec<u8>>("name", obj).try_into().expect("Invalid name");
-			let currency =
-				extract_value::<Vec<u8>>("currency", obj).try_into().expect("Invalid currency");
-			let amount = extract_value::<u128>("amount", obj);
-			let reference = extract_value::<Vec<u8>>("reference", obj)
-				.try_into()
-				.expect("Invalid reference");
-
-			Some(Self { iban, name, currency, amount, reference, tx_type: *tx_type })
-		} else {
-			None
+	//
+	// `decimals` is the number of decimal places of the fixed-point amount
+	pub fn from_json_statement(
+		json: &JsonValue,
+		tx_type: &TransactionType,
+		decimals: u32,
+	) -> Result<Self, ParseError> {
+		let obj = json.as_object().ok_or(ParseError::NotAnObject)?;
+
+		let iban = extract_bounded("iban", obj)?;
+		let currency = extract_bounded("currency", obj)?;
+		let amount = extract_amount("amount", obj, decimals)
+			.map_err(|e| ParseError::InvalidAmount("amount", e))?;
+		let reference = extract_bounded("reference", obj)?;
+
+		// Name is only informative, it is cut instead of dropping the statement
+		let name = extract_value::<Vec<u8>>("name", obj)?;
+		if name.len() > MaxStringLength::get() as usize {
+			log::warn!("[OCW] Name {:?} is truncated to {} bytes", name, MaxStringLength::get());
+		}
+		let name = BoundedVec::truncate_from(name);
+
+		let entry_reference = ["entryReference", "acctSvcrRef"]
+			.iter()
+			.find_map(|key| {
+				Vec::<u8>::deserialize(&parse_object(key, obj))
+					.filter(|entry_reference| !entry_reference.is_empty())
+			})
+			.and_then(|entry_reference| {
+				// Transaction is identified by its contents instead
+				let bounded = BoundedVec::try_from(entry_reference.clone()).ok();
+				if bounded.is_none() {
+					log::warn!("[OCW] Entry reference {:?} is too long", entry_reference);
+				}
+				bounded
+			});
+		let booking_date = extract_date("bookingDate", obj).unwrap_or_default();
+		let validation_date = extract_date("validationDate", obj).unwrap_or_default();
+
+		// Negative amount is a reversal, funds move in the opposite direction
+		let tx_type = if amount < 0 { tx_type.reversed() } else { *tx_type };
+
+		Ok(Self {
+			iban,
+			name,
+			currency,
+			amount: amount.unsigned_abs(),
+			reference,
+			tx_type,
+			entry_reference,
+			booking_date,
+			validation_date,
+		})
+	}
+
+	/// Stable identifier of the transaction
+	///
+	/// Hash of the bank's entry reference. If the bank does not provide one, hash of the
+	/// counterparty IBAN, booking date, currency, amount, reference and type of the transaction.
+	pub fn id(&self) -> [u8; 32] {
+		match &self.entry_reference {
+			Some(entry_reference) => sp_io::hashing::blake2_256(entry_reference),
+			None => (
+				&self.iban,
+				self.booking_date,
+				&self.currency,
+				self.amount,
+				&self.reference,
+				self.tx_type,
+			)
+				.using_encoded(sp_io::hashing::blake2_256),
 		}
 	}
 
 	/// Parse multiple transactions from `JsonValue`
+	///
+	/// Fails with the index of the first transaction that could not be parsed, or `None` if
+	/// the list of transactions itself is invalid
 	pub fn parse_transactions(
 		json: &JsonValue,
 		transaction_type: TransactionType,
-	) -> Option<Vec<Self>> {
+		decimals: u32,
+	) -> Result<Vec<Self>, (Option<u32>, ParseError)> {
 		// Get the key string for the transaction type
 		let key_string = match transaction_type {
 			TransactionType::Incoming => "incomingTransactions",
 			TransactionType::Outgoing => "outgoingTransactions",
-			_ => return None,
+			_ => return Ok(Vec::new()),
 		};
 
-		let mut transactions = Vec::new();
+		let obj = json.as_object().ok_or((None, ParseError::NotAnObject))?;
 
-		if let JsonValue::Object(obj) = json {
-			if let JsonValue::Array(txs) = parse_object(key_string, obj) {
-				for json_tx in txs {
-					if let Some(tx) = Self::from_json_statement(&json_tx, &transaction_type) {
-						transactions.push(tx);
-					}
-				}
-			};
+		// Dates of the statement apply to transactions without their own dates
+		let booking_date = extract_date("bookingDate", obj).unwrap_or_default();
+		let validation_date = extract_date("validationDate", obj).unwrap_or_default();
+
+		let txs = match parse_object(key_string, obj) {
+			JsonValue::Array(txs) => txs,
+			// Statement without transactions of this type
+			JsonValue::Null => return Ok(Vec::new()),
+			_ => return Err((None, ParseError::InvalidField(key_string))),
 		};
 
-		Some(transactions)
+		let mut transactions = Vec::with_capacity(txs.len());
+
+		for (index, json_tx) in txs.iter().enumerate() {
+			let mut tx = Self::from_json_statement(json_tx, &transaction_type, decimals)
+				.map_err(|e| (Some(index as u32), e))?;
+
+			if tx.booking_date == 0 {
+				tx.booking_date = booking_date;
+			}
+			if tx.validation_date == 0 {
+				tx.validation_date = validation_date;
+			}
+			transactions.push(tx);
+		}
+
+		Ok(transactions)
 	}
 }
 
-impl<MaxLength: Get<u32>> From<&Iban<MaxLength>> for BankAccount<MaxLength> {
-	fn from(iban: &Iban<MaxLength>) -> Self {
-		Self { iban: iban.clone(), balance: 0, last_updated: 0 }
+impl<AccountId> Default for Reference<AccountId> {
+	fn default() -> Self {
+		Self { dest: None, burn_request_id: None }
+	}
+}
+
+impl<AccountId: Decode> TryFrom<&[u8]> for Reference<AccountId> {
+	type Error = ReferenceError;
+
+	fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+		let reference =
+			core::str::from_utf8(bytes).map_err(|_| ReferenceError::InvalidUtf8)?.trim();
+
+		// ISO 20022 structured creditor reference
+		if is_creditor_reference(reference) {
+			return Ok(Self { dest: None, burn_request_id: parse_creditor_reference(reference)? })
+		}
+
+		// Plain SS58 address
+		if let Some(dest) = decode_address(reference) {
+			return Ok(Self { dest: Some(dest), burn_request_id: None })
+		}
+
+		// Format of the reference is the following:
+		// Purpose:AccountId; ourReference:nonce(of burn request)
+		// E.g, "Purp:5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty; ourRef:12",
+		let mut parsed = Self::default();
+
+		for field in reference.split(';') {
+			let (key, value) = match field.split_once(':') {
+				Some((key, value)) => (key.trim(), value.trim()),
+				None => continue,
+			};
+
+			if key.eq_ignore_ascii_case("purp") {
+				if !value.eq_ignore_ascii_case("none") {
+					parsed.dest =
+						Some(decode_address(value).ok_or(ReferenceError::InvalidAddress)?);
+				}
+			} else if key.eq_ignore_ascii_case("ourref") {
+				parsed.burn_request_id = if value.eq_ignore_ascii_case("none") {
+					None
+				} else if is_creditor_reference(value) {
+					parse_creditor_reference(value)?
+				} else {
+					Some(value.parse::<u64>().map_err(|_| ReferenceError::InvalidRequestId)?)
+				};
+			}
+		}
+
+		Ok(parsed)
 	}
 }
 
-impl<MaxLength: Get<u32>> TryFrom<&JsonValue> for BankAccount<MaxLength> {
-	type Error = ();
+/// Decodes `AccountId` from SS58 address
+fn decode_address<AccountId: Decode>(address: &str) -> Option<AccountId> {
+	AccountId32::from_ss58check(address)
+		.ok()
+		.and_then(|account| AccountId::decode(&mut &account.encode()[..]).ok())
+}
 
-	fn try_from(json: &JsonValue) -> Result<Self, Self::Error> {
-		let raw_object = json.as_object();
-		if let Some(obj) = raw_object {
-			let iban = extract_value::<Vec<u8>>("iban", obj).try_into().unwrap_or_default();
-			let balance = extract_value::<u128>("balanceCL", obj);
+/// Checks if the value looks like a structured creditor reference, i.e `RF` and check digits
+fn is_creditor_reference(value: &str) -> bool {
+	let bytes = value.as_bytes();
+
+	bytes.len() >= 4 &&
+		bytes[..2].eq_ignore_ascii_case(b"RF") &&
+		bytes[2].is_ascii_digit() &&
+		bytes[3].is_ascii_digit()
+}
+
+/// Validates structured creditor reference (ISO 11649)
+///
+/// Returns the burn request id if the payload of the reference is numeric
+fn parse_creditor_reference(value: &str) -> Result<Option<u64>, ReferenceError> {
+	// Printed format groups the reference in blocks of four characters
+	let reference = value.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>();
+
+	if reference.len() < 5 ||
+		reference.len() > 25 ||
+		!reference.iter().all(char::is_ascii_alphanumeric)
+	{
+		return Err(ReferenceError::InvalidCreditorReference)
+	}
 
-			Ok(Self { iban, balance, last_updated: 0 })
+	// Check digits are valid if the reference, with `RF` and check digits moved to the end,
+	// is 1 modulo 97 when letters are replaced by numbers (A = 10, ..., Z = 35)
+	let remainder = reference[4..].iter().chain(reference[..4].iter()).fold(0u32, |rem, c| {
+		let value = c.to_digit(36).unwrap_or_default();
+		if value < 10 {
+			(rem * 10 + value) % 97
 		} else {
-			Err(())
+			(rem * 100 + value) % 97
 		}
+	});
+
+	if remainder != 1 {
+		return Err(ReferenceError::InvalidCreditorReference)
+	}
+
+	// Numeric payload is the burn request id
+	Ok(reference[4..].iter().try_fold(0u64, |id, c| {
+		c.to_digit(10).and_then(|digit| id.checked_mul(10)?.checked_add(digit as u64))
+	}))
+}
+
+impl<MaxLength: Get<u32>, StringMaxLength: Get<u32>> From<&Iban<MaxLength>>
+	for BankAccount<MaxLength, StringMaxLength>
+{
+	fn from(iban: &Iban<MaxLength>) -> Self {
+		Self {
+			iban: iban.clone(),
+			opening_balance: 0,
+			balance: 0,
+			currency: None,
+			last_updated: 0,
+			beneficiary: None,
+		}
+	}
+}
+
+/// Functions of `BankAccount<T>` type
+impl<MaxLength: Get<u32>, StringMaxLength: Get<u32>> BankAccount<MaxLength, StringMaxLength> {
+	/// Get bank account of a statement from json
+	///
+	/// `decimals` is the number of decimal places of the fixed-point balance
+	pub fn from_json(json: &JsonValue, decimals: u32) -> Result<Self, ParseError> {
+		let obj = json.as_object().ok_or(ParseError::NotAnObject)?;
+		let iban = extract_bounded("iban", obj)?;
+		let balance = match extract_amount("balanceCL", obj, decimals) {
+			// Overdrawn account is reported with zero balance
+			Ok(balance) => balance.max(0) as u128,
+			Err(e) => return Err(ParseError::InvalidAmount("balanceCL", e)),
+		};
+		let opening_balance = match extract_amount("balanceOP", obj, decimals) {
+			Ok(balance) => balance.max(0) as u128,
+			// Opening balance is not reported by every bank
+			Err(AmountError::NotANumber) => 0,
+			Err(e) => return Err(ParseError::InvalidAmount("balanceOP", e)),
+		};
+		let currency = match extract_value::<Vec<u8>>("currency", obj) {
+			Ok(currency) => Some(
+				CurrencyCode::try_from(&currency[..])
+					.map_err(|_| ParseError::InvalidField("currency"))?,
+			),
+			// Currency is not reported by every bank
+			Err(ParseError::MissingField(_)) => None,
+			Err(e) => return Err(e),
+		};
+		let last_updated = extract_date("balanceCLDate", obj).unwrap_or_default();
+
+		Ok(Self { iban, opening_balance, balance, currency, last_updated, beneficiary: None })
+	}
+}
+
+impl<StringMaxLength: Get<u32>> Beneficiary<StringMaxLength> {
+	/// Checks that the beneficiary can be used in a payment
+	///
+	/// Name is required and country must be an ISO 3166-1 alpha-2 code. BIC must consist of
+	/// 8 or 11 alphanumeric characters, it can only be omitted for national payments. Text
+	/// fields are sent to the EBICS service as JSON strings, so they must be valid UTF-8.
+	pub fn is_valid(&self) -> bool {
+		let bic_is_valid = if self.bic.is_empty() {
+			self.national
+		} else {
+			matches!(self.bic.len(), 8 | 11) && self.bic.iter().all(u8::is_ascii_alphanumeric)
+		};
+
+		let text_is_utf8 = [
+			&self.name,
+			&self.street,
+			&self.street_nr,
+			&self.zip,
+			&self.city,
+			&self.bank_name,
+		]
+		.iter()
+		.all(|text| core::str::from_utf8(text).is_ok());
+
+		!self.name.is_empty() &&
+			self.country.iter().all(u8::is_ascii_uppercase) &&
+			bic_is_valid &&
+			text_is_utf8
 	}
 }
@@ -0,0 +1,179 @@
+//! Journal of hyperfridge receipts
+//!
+//! The hyperfridge guest program commits a JSON document to the journal of its receipt,
+//! serialized as a string with the risc0 serde format, i.e the length as a little endian
+//! `u32` followed by the UTF-8 bytes. Besides the keys used to check the signatures of the
+//! bank, the document lists the statements it proved, in the order they are queued:
+//!
+//! ```json
+//! {"stmts": [{"iban": "CH4308307000289537312", "closing_balance": "1234.56",
+//!   "transactions_hash": "<hex encoded SHA-256 of the canonical form of the transactions>"}]}
+//! ```
+//!
+//! The canonical form of version 1 is the line `fiat-ramps/transactions/v1` followed by the
+//! transactions of the statement in order. Every transaction is written as the following
+//! fields, each as its length in bytes in decimal, `:`, its bytes and `,`:
+//!
+//! 1. IBAN of the counterparty
+//! 2. name of the counterparty
+//! 3. currency
+//! 4. amount as decimal with `FiatDecimals` decimal places, e.g `12.50`
+//! 5. reference
+//! 6. `CRDT` for incoming, `DBIT` for outgoing transactions, empty otherwise
+//! 7. entry reference of the bank, empty if the bank provides none
+//! 8. booking date as `YYYY-MM-DDThh:mm:ss` in UTC
+//! 9. value date as `YYYY-MM-DDThh:mm:ss` in UTC
+//!
+//! E.g `fiat-ramps/transactions/v1\n4:CH93,5:Alice,3:CHF,5:12.50,0:,4:CRDT,...`. Any change
+//! of the form increases `TRANSACTIONS_HASH_VERSION`, so that hashes of different versions never
+//! match.
+use crate::{
+	amount::from_decimal,
+	types::{JournalError, ParseError, StatementsOf, TransactionOf, TransactionType},
+	utils::{extract_value, format_date_time, parse_object},
+	Config,
+};
+use alloc::format;
+use frame_support::traits::Get;
+use lite_json::{parse_json, JsonValue};
+use sp_core::hexdisplay::HexDisplay;
+use sp_io::hashing::sha2_256;
+use sp_std::vec::Vec;
+
+/// Version of the canonical form of transactions that `transactions_hash` hashes
+pub const TRANSACTIONS_HASH_VERSION: u32 = 1;
+
+/// Statement the receipt commits to
+#[derive(Clone, PartialEq, Eq)]
+struct JournalStatement {
+	iban: Vec<u8>,
+	closing_balance: Vec<u8>,
+	transactions_hash: Vec<u8>,
+}
+
+/// Checks that the journal commits to exactly the queued statements
+///
+/// Statements are compared in order, field by field. Closing balances are only compared if
+/// the statement format reports balances. Fails with the first field that does not match.
+pub fn check<T: Config>(journal: &[u8], statements: &StatementsOf<T>) -> Result<(), JournalError> {
+	let committed = parse(journal)?;
+
+	if committed.len() != statements.len() {
+		return Err(JournalError::StatementCount)
+	}
+
+	let decimals = T::FiatDecimals::get();
+	let reports_balances = T::StatementFormat::get().reports_balances();
+
+	for (index, ((bank_account, transactions), statement)) in
+		statements.iter().zip(committed).enumerate()
+	{
+		let index = index as u32;
+
+		if statement.iban != bank_account.iban.to_vec() {
+			return Err(JournalError::Iban(index))
+		}
+
+		// Overdrawn account is queued with zero balance
+		let closing_balance = from_decimal(&statement.closing_balance, decimals)
+			.map_err(|_| JournalError::Malformed)?
+			.max(0) as u128;
+		if reports_balances && closing_balance != bank_account.balance {
+			return Err(JournalError::ClosingBalance(index))
+		}
+
+		if statement.transactions_hash != transactions_hash::<T>(transactions) {
+			return Err(JournalError::Transactions(index))
+		}
+	}
+
+	Ok(())
+}
+
+/// Hex encoded SHA-256 hash of the canonical form of the transactions of a statement
+pub fn transactions_hash<T: Config>(transactions: &[TransactionOf<T>]) -> Vec<u8> {
+	let canonical = canonical_transactions::<T>(transactions);
+	format!("{}", HexDisplay::from(&sha2_256(&canonical))).into_bytes()
+}
+
+/// Canonical form of the transactions of a statement, as defined in the module documentation
+pub fn canonical_transactions<T: Config>(transactions: &[TransactionOf<T>]) -> Vec<u8> {
+	let decimals = T::FiatDecimals::get();
+	let unit = 10u128.saturating_pow(decimals);
+
+	let mut canonical =
+		format!("fiat-ramps/transactions/v{}\n", TRANSACTIONS_HASH_VERSION).into_bytes();
+
+	for transaction in transactions {
+		let amount = match decimals {
+			0 => format!("{}", transaction.amount),
+			_ => format!(
+				"{}.{:0width$}",
+				transaction.amount / unit,
+				transaction.amount % unit,
+				width = decimals as usize
+			),
+		};
+		let credit_debit = match transaction.tx_type {
+			TransactionType::Incoming => "CRDT",
+			TransactionType::Outgoing => "DBIT",
+			TransactionType::None => "",
+		};
+		let entry_reference = transaction.entry_reference.as_deref().map_or(&[][..], |r| &r[..]);
+		let booking_date = format_date_time(transaction.booking_date);
+		let validation_date = format_date_time(transaction.validation_date);
+
+		let fields: [&[u8]; 9] = [
+			&transaction.iban,
+			&transaction.name,
+			&transaction.currency,
+			amount.as_bytes(),
+			&transaction.reference,
+			credit_debit.as_bytes(),
+			entry_reference,
+			booking_date.as_bytes(),
+			validation_date.as_bytes(),
+		];
+		for field in fields {
+			canonical.extend(format!("{}:", field.len()).into_bytes());
+			canonical.extend_from_slice(field);
+			canonical.push(b',');
+		}
+	}
+
+	canonical
+}
+
+/// Decodes the statements of the journal
+fn parse(journal: &[u8]) -> Result<Vec<JournalStatement>, JournalError> {
+	let length = journal
+		.get(..4)
+		.map(|length| u32::from_le_bytes([length[0], length[1], length[2], length[3]]))
+		.ok_or(JournalError::Malformed)?;
+	let document = journal
+		.get(4..)
+		.and_then(|rest| rest.get(..length as usize))
+		.and_then(|document| core::str::from_utf8(document).ok())
+		.ok_or(JournalError::Malformed)?;
+
+	let statements = match parse_json(document) {
+		Ok(JsonValue::Object(obj)) => match parse_object("stmts", &obj) {
+			JsonValue::Array(statements) => statements,
+			_ => return Err(JournalError::Malformed),
+		},
+		_ => return Err(JournalError::Malformed),
+	};
+
+	statements
+		.iter()
+		.map(|statement| match statement {
+			JsonValue::Object(obj) => Ok(JournalStatement {
+				iban: extract_value("iban", obj)?,
+				closing_balance: extract_value("closing_balance", obj)?,
+				transactions_hash: extract_value("transactions_hash", obj)?,
+			}),
+			_ => Err(ParseError::NotAnObject),
+		})
+		.collect::<Result<_, _>>()
+		.map_err(|_| JournalError::Malformed)
+}
@@ -13,6 +13,8 @@ use frame_support::{
 	ensure,
 	pallet_prelude::*,
 	traits::{
+		fungibles,
+		tokens::{Fortitude, Precision, Preservation},
 		BuildGenesisConfig, Currency, ExistenceRequirement, Get, LockableCurrency, UnixTime,
 		WithdrawReasons,
 	},
@@ -20,21 +22,19 @@ use frame_support::{
 use frame_system::{
 	ensure_signed,
 	offchain::{
-		AppCrypto, CreateSignedTransaction, SendSignedTransaction, SignedPayload, Signer,
+		AppCrypto, CreateSignedTransaction, ForAll, SendSignedTransaction, SignedPayload, Signer,
 		SigningTypes,
 	},
 	pallet_prelude::*,
 };
-use lite_json::{json::JsonValue, parse_json, Serialize};
+use lite_json::{json::JsonValue, Serialize};
 use risc0_zkvm::{InnerReceipt, Receipt};
 use scale_info::{prelude::format, TypeInfo};
-use sp_core::hexdisplay::AsBytesRef;
 use sp_runtime::{
-	offchain as rt_offchain,
 	offchain::storage::{MutateStorageError, StorageRetrievalError, StorageValueRef},
-	traits::{AccountIdConversion, Zero},
+	traits::{AccountIdConversion, Convert, IdentifyAccount, Saturating, Zero},
 	transaction_validity::{InvalidTransaction, TransactionValidity},
-	AccountId32, SaturatedConversion,
+	AccountId32, RuntimeAppPublic, SaturatedConversion,
 };
 use sp_std::{
 	convert::{TryFrom, TryInto},
@@ -45,10 +45,16 @@ use sp_std::{
 
 use sp_core::crypto::Ss58Codec;
 
+pub mod amount;
+pub mod camt;
 pub mod crypto;
+mod http;
 mod impls;
+pub mod journal;
+pub mod migrations;
 pub mod types;
 mod utils;
+mod xml;
 
 #[cfg(test)]
 mod helpers;
@@ -57,7 +63,7 @@ pub mod mock;
 #[cfg(test)]
 mod tests;
 
-use crate::types::*;
+use crate::{amount::to_json_number, types::*};
 use utils::*;
 
 pub use pallet::*;
@@ -67,7 +73,11 @@ pub mod pallet {
 	use super::*;
 	use types::StringOf;
 
+	/// The current storage version
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(_);
 
@@ -88,6 +98,13 @@ pub mod pallet {
 		/// Currency type
 		type Currency: Currency<Self::AccountId> + LockableCurrency<Self::AccountId>;
 
+		/// Assets of the fiat currencies that are not represented by `Currency`
+		type Assets: fungibles::Mutate<Self::AccountId>
+			+ fungibles::Inspect<Self::AccountId, Balance = BalanceOf<Self>>;
+
+		/// Maps ISO 4217 currency code to the on-chain currency, `None` if it is not supported
+		type CurrencyFor: Convert<CurrencyCode, Option<CurrencyId<AssetIdOf<Self>>>>;
+
 		/// Maximum number of characters in IBAN
 		#[pallet::constant]
 		type MaxIbanLength: Get<u32> + PartialEq + Eq + MaxEncodedLen + TypeInfo + Clone + Debug;
@@ -107,16 +124,100 @@ pub mod pallet {
 		#[pallet::constant]
 		type UnsignedPriority: Get<u64>;
 
-		/// OCW account
-		type OcwAccount: Get<Self::AccountId>;
+		/// Maximum number of offchain worker authorities
+		#[pallet::constant]
+		type MaxOcwAuthorities: Get<u32>;
+
+		/// Maximum number of subjects an authority can attest before they reach the threshold
+		/// or expire
+		#[pallet::constant]
+		type MaxPendingAttestations: Get<u32>;
 
 		/// Max number of statements to process
 		#[pallet::constant]
 		type MaxStatements: Get<u32> + PartialEq + Eq + MaxEncodedLen + TypeInfo + Clone;
+
+		/// Number of blocks a queued statement batch is kept waiting for its receipt
+		#[pallet::constant]
+		type StatementsExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Number of blocks a pending IBAN link waits for its micro-deposit
+		#[pallet::constant]
+		type LinkExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of pending links to an IBAN, every deposit to the IBAN is checked
+		/// against all of them
+		#[pallet::constant]
+		type MaxPendingLinks: Get<u32>;
+
+		/// Number of failed unpeg attempts after which a burn request is refunded
+		#[pallet::constant]
+		type MaxUnpegAttempts: Get<u32>;
+
+		/// Number of blocks to wait before retrying a failed unpeg request, doubled after
+		/// every failed attempt
+		#[pallet::constant]
+		type UnpegRetryDelay: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of burn requests sent in one unpeg batch per offchain worker run
+		#[pallet::constant]
+		type MaxUnpegBatch: Get<u32>;
+
+		/// Milliseconds the offchain worker waits for the response to a request to the EBICS
+		/// service
+		#[pallet::constant]
+		type HttpTimeout: Get<u64>;
+
+		/// Number of times a GET request to the EBICS service is retried after a timeout or a
+		/// server error. Payment instructions (POST) are never retried
+		#[pallet::constant]
+		type HttpRetries: Get<u32>;
+
+		/// Number of decimal places of fiat amounts, i.e amount `1` is `10^FiatDecimals`
+		#[pallet::constant]
+		type FiatDecimals: Get<u32>;
+
+		/// Whether minting for an IBAN is paused when its closing balance does not match
+		/// the funds on chain
+		#[pallet::constant]
+		type PauseMintingOnMismatch: Get<bool>;
+
+		/// Number of blocks a processed transaction is remembered, should cover the overlap
+		/// of statements returned by the EBICS service
+		#[pallet::constant]
+		type ProcessedTransactionsExpiry: Get<BlockNumberFor<Self>>;
+
+		/// Format of the bank statements fetched by the offchain worker
+		#[pallet::constant]
+		type StatementFormat: Get<StatementFormat>;
+
+		/// Maximum size in bytes of a receipt submitted with `submit_receipt`
+		#[pallet::constant]
+		type MaxReceiptLength: Get<u32>;
+
+		/// Weight of verifying the seal of a succinct receipt, which does not depend on its
+		/// size, and of reading the image ids and the queued batch
+		#[pallet::constant]
+		type ReceiptVerificationWeight: Get<Weight>;
+
+		/// Weight of decoding and hashing a byte of a JSON encoded receipt
+		#[pallet::constant]
+		type ReceiptByteWeight: Get<Weight>;
+
+		/// Maximum number of registered image ids of the risc0 module
+		#[pallet::constant]
+		type MaxImageIds: Get<u32>;
 	}
 
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
+			Self::prune_expired_statements(block_number)
+				.saturating_add(Self::prune_expired_attestations(block_number))
+				.saturating_add(Self::prune_expired_links(block_number))
+				.saturating_add(Self::prune_processed_transactions(block_number))
+		}
+
 		fn offchain_worker(block_number: BlockNumberFor<T>) {
 			log::info!("[OCW] Instantiating offchain worker");
 
@@ -174,32 +275,185 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type ApiUrl<T: Config> = StorageValue<_, StringOf<T>, ValueQuery, DefaultApi<T>>;
 
+	/// Format of the payment initiations sent to the API endpoint for burn requests
+	#[pallet::storage]
+	#[pallet::getter(fn unpeg_format)]
+	pub(super) type UnpegFormat<T: Config> = StorageValue<_, PaymentFormat, ValueQuery>;
+
 	/// Mapping from `AccountId` to `BankAccount`
 	#[pallet::storage]
 	#[pallet::getter(fn account_of)]
 	pub(super) type Accounts<T: Config> =
 		StorageMap<_, Blake2_128Concat, AccountIdOf<T>, BankAccountOf<T>>;
 
+	/// Reverse index of `Accounts`, mapping IBAN to the `AccountId` it is linked to
+	#[pallet::storage]
+	#[pallet::getter(fn iban_to_account)]
+	pub(super) type IbanToAccount<T: Config> =
+		StorageMap<_, Blake2_128Concat, IbanOf<T>, AccountIdOf<T>>;
+
+	/// IBAN links waiting for the micro-deposit that proves ownership of the IBAN
+	///
+	/// Keyed by the IBAN and the account that requested the link. Several accounts can request
+	/// a link to the same IBAN, the deposit decides which of them owns it
+	#[pallet::storage]
+	#[pallet::getter(fn pending_links)]
+	pub(super) type PendingLinks<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		IbanOf<T>,
+		Blake2_128Concat,
+		AccountIdOf<T>,
+		PendingLinkOf<T>,
+	>;
+
+	/// Number of `PendingLinks` to an IBAN, at most `MaxPendingLinks`
+	#[pallet::storage]
+	pub(super) type PendingLinkCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, IbanOf<T>, u32, ValueQuery>;
+
+	/// Keys of `PendingLinks` by the block in which they are removed
+	#[pallet::storage]
+	pub(super) type PendingLinksExpiring<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		BlockNumberFor<T>,
+		Vec<(IbanOf<T>, AccountIdOf<T>)>,
+		ValueQuery,
+	>;
+
 	/// Stores burn requests
-	/// until they are confirmed by the bank as outgoing transaction
+	/// until they are confirmed by the bank as outgoing transaction or refunded
 	/// transaction_id -> burn_request
 	#[pallet::storage]
 	#[pallet::getter(fn burn_requests)]
 	pub(super) type BurnRequests<T: Config> =
-		StorageMap<_, Blake2_128Concat, u64, BurnRequest<T::MaxIbanLength, BalanceOf<T>>>;
+		StorageMap<_, Blake2_128Concat, u64, BurnRequestOf<T>>;
+
+	/// Funds of the burn requests of an account that are held in escrow, by currency
+	#[pallet::storage]
+	pub(super) type EscrowedFunds<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		CurrencyCode,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// Ids of the burn requests in flight
+	#[pallet::storage]
+	pub(super) type InFlightBurnRequests<T: Config> =
+		StorageValue<_, BoundedVec<u64, T::MaxUnpegBatch>, ValueQuery>;
+
+	/// Counts the number of queued statement batches, used as the id of the next batch
+	#[pallet::storage]
+	#[pallet::getter(fn statement_batch_count)]
+	pub(super) type StatementBatchCount<T: Config> = StorageValue<_, u64, ValueQuery>;
 
-	/// Stores latest statements waiting for verification
+	/// Stores statement batches waiting for verification
 	///
-	/// Maps `receipt_url` to `Statements`
+	/// Maps batch id to `QueuedStatementsInfo`, a batch is kept until its receipt
+	/// is verified, rejected or the batch expires
 	#[pallet::storage]
 	#[pallet::getter(fn queued_statements)]
 	pub(super) type QueuedStatements<T: Config> =
-		StorageValue<_, QueuedStatementsInfoOf<T>, OptionQuery>;
+		StorageMap<_, Blake2_128Concat, u64, QueuedStatementsInfoOf<T>>;
+
+	/// Ids of `QueuedStatements` by the block in which they expire
+	#[pallet::storage]
+	pub(super) type StatementsExpiring<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<u64>, ValueQuery>;
+
+	/// Ids of `QueuedStatements` by the URL of their receipt
+	#[pallet::storage]
+	pub(super) type QueuedReceipts<T: Config> = StorageMap<_, Blake2_128Concat, StringOf<T>, u64>;
+
+	/// IBANs whose minting is paused after a reconciliation mismatch
+	///
+	/// Maps IBAN to the block number of the mismatch, minting stays paused until
+	/// `resume_minting` is called
+	#[pallet::storage]
+	#[pallet::getter(fn minting_paused_at)]
+	pub(super) type PausedMinting<T: Config> =
+		StorageMap<_, Blake2_128Concat, IbanOf<T>, BlockNumberFor<T>>;
+
+	/// Transactions that have been applied, keyed by the IBAN of their statement and the id
+	/// of the transaction
+	///
+	/// Used to skip transactions of overlapping statements, maps to the block in which the
+	/// entry is removed
+	#[pallet::storage]
+	#[pallet::getter(fn processed_transactions)]
+	pub(super) type ProcessedTransactions<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		IbanOf<T>,
+		Blake2_128Concat,
+		[u8; 32],
+		BlockNumberFor<T>,
+	>;
+
+	/// Entries of `ProcessedTransactions` by the block in which they are removed
+	#[pallet::storage]
+	pub(super) type ProcessedTransactionsExpiring<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<(IbanOf<T>, [u8; 32])>, ValueQuery>;
+
+	/// Image IDs of the risc0 module that receipts are verified against
+	///
+	/// Several images can be active at once, so that receipts of the previous guest program
+	/// keep verifying until it is sunset
+	#[pallet::storage]
+	pub(super) type Risc0ImageIds<T: Config> =
+		CountedStorageMap<_, Blake2_128Concat, [u8; 32], ImageIdInfoOf<T>, OptionQuery>;
+
+	/// Whether fake receipts may ever be accepted on this chain, set in the genesis config
+	/// depending on the chain type
+	#[pallet::storage]
+	#[pallet::getter(fn fake_receipts_permitted)]
+	pub(super) type FakeReceiptsPermitted<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Whether fake receipts are currently accepted in place of proofs
+	#[pallet::storage]
+	#[pallet::getter(fn fake_receipts_allowed)]
+	pub(super) type FakeReceiptsAllowed<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Accounts of the offchain workers that attest statements and their verification
+	#[pallet::storage]
+	#[pallet::getter(fn ocw_authorities)]
+	pub(super) type OcwAuthorities<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, T::MaxOcwAuthorities>, ValueQuery>;
+
+	/// Number of authorities that have to attest identical content before it is applied
+	#[pallet::storage]
+	#[pallet::getter(fn attestation_threshold)]
+	pub(super) type AttestationThreshold<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// Attestations of the authorities that have not reached the threshold yet
+	#[pallet::storage]
+	pub(super) type Attestations<T: Config> =
+		StorageMap<_, Blake2_128Concat, AttestationSubjectOf<T>, AttestationsInfoOf<T>>;
+
+	/// Number of subjects in `Attestations` each authority has attested
+	#[pallet::storage]
+	pub(super) type PendingAttestations<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
 
-	/// Image ID of the risc0 module
+	/// Subjects of `Attestations` by the block in which they expire
+	#[pallet::storage]
+	pub(super) type AttestationsExpiring<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		BlockNumberFor<T>,
+		Vec<AttestationSubjectOf<T>>,
+		ValueQuery,
+	>;
+
+	/// Authority that claims burn requests, sends their unpeg requests and reports the outcomes
 	#[pallet::storage]
-	#[pallet::getter(fn risc0_image_id)]
-	pub(super) type Risc0ImageId<T: Config> = StorageValue<_, [u8; 32], OptionQuery>;
+	#[pallet::getter(fn unpeg_authority)]
+	pub(super) type UnpegAuthority<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
@@ -213,38 +467,44 @@ pub mod pallet {
 			Ok(().into())
 		}
 
-		/// Create new bank account instance
+		/// Create new bank account instance without proof of IBAN ownership
+		///
+		/// Users link their IBAN with `request_iban_link`, this call is reserved for root
+		///
+		/// Fails if the IBAN is already linked to an account, or if the account
+		/// already has an IBAN linked, which must be unmapped first
 		///
 		/// # Arguments
 		///
 		/// * `origin` - The origin of the call
+		/// * `who` - Account to link the IBAN to
 		/// * `iban` - IBAN of the account
-		#[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 2))]
 		#[pallet::call_index(1)]
-		pub fn create_account(origin: OriginFor<T>, iban: IbanOf<T>) -> DispatchResultWithPostInfo {
-			let who = ensure_signed(origin)?;
+		pub fn create_account(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			iban: IbanOf<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
 
-			// TO-DO: need to check if account owner really owns this IBAN
-			Accounts::<T>::insert(
-				&who,
-				BankAccount::<T::MaxIbanLength> {
-					iban: iban.clone(),
-					balance: 0u128,
-					last_updated: T::TimeProvider::now().as_millis() as u64,
-				},
-			);
+			ensure!(!IbanToAccount::<T>::contains_key(&iban), Error::<T>::IbanAlreadyMapped);
+			ensure!(!Accounts::<T>::contains_key(&who), Error::<T>::AccountAlreadyMapped);
 
-			Self::deposit_event(Event::AccountCreated(who, iban));
+			Self::link_account(who, iban);
 
 			Ok(().into())
 		}
 
 		/// Remove `IbanAccount` from the store
 		///
+		/// Unmapping is refused while burn requests of the caller have funds held in escrow in
+		/// the pallet's account
+		///
 		/// # Arguments
 		///
-		/// `iban`: IbanAccount struct
-		#[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+		/// `iban`: IBAN that is linked to the caller
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 2))]
 		#[pallet::call_index(2)]
 		pub fn unmap_iban_account(
 			origin: OriginFor<T>,
@@ -252,7 +512,13 @@ pub mod pallet {
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
+			let bank_account = Accounts::<T>::get(&who).ok_or(Error::<T>::AccountNotMapped)?;
+
+			ensure!(bank_account.iban == iban, Error::<T>::IbanMismatch);
+			ensure!(!Self::has_escrowed_funds(&who), Error::<T>::BurnRequestsPending);
+
 			Accounts::<T>::remove(&who);
+			IbanToAccount::<T>::remove(&iban);
 
 			Self::deposit_event(Event::AccountDestroyed(who, iban));
 
@@ -269,18 +535,26 @@ pub mod pallet {
 		/// # Arguments
 		///
 		/// `amount`: Amount of tokens to burn
+		/// `currency`: ISO 4217 code of the currency to burn
 		/// `iban`: IbanOf<T> account of the receiver
 		/// `dest`: `TransferDestination` enum which can be either `Iban`, `AccountId` or withdrawal
-		#[pallet::weight(T::DbWeight::get().reads_writes(10, 2))]
+		///
+		/// Transfers to an IBAN carry the beneficiary details, withdrawals and transfers to an
+		/// on-chain address use the default beneficiary of the receiving bank account
+		#[pallet::weight(T::DbWeight::get().reads_writes(11, 3))]
 		#[pallet::call_index(3)]
 		pub fn transfer(
 			origin: OriginFor<T>,
 			amount: BalanceOf<T>,
+			currency: CurrencyCode,
 			dest: TransferDestinationOf<T>,
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
-			ensure!(T::Currency::free_balance(&who) >= amount, Error::<T>::InsufficientBalance,);
+			ensure!(
+				Self::free_balance(&currency, &who)? >= amount,
+				Error::<T>::InsufficientBalance,
+			);
 
 			ensure!(!amount.is_zero(), Error::<T>::AmountIsZero);
 
@@ -290,22 +564,34 @@ pub mod pallet {
 			// Get bank account associated with the sender
 			let source_account = Accounts::<T>::get(&who).ok_or(Error::<T>::AccountNotFound)?;
 
-			let dest_iban = match dest.clone() {
-				TransferDestination::Iban(iban) => Ok(iban),
-				TransferDestination::Address(dest_account) => Accounts::<T>::get(&dest_account)
-					.ok_or(Error::<T>::AccountNotFound)
-					.map(|account| account.iban),
-				TransferDestination::Withdraw => Ok(source_account.iban.clone()),
-			}?;
+			let (dest_iban, beneficiary) = match dest.clone() {
+				TransferDestination::Iban(iban, beneficiary) => (iban, Some(beneficiary)),
+				TransferDestination::Address(dest_account) => {
+					let account =
+						Accounts::<T>::get(&dest_account).ok_or(Error::<T>::AccountNotFound)?;
+					(account.iban, account.beneficiary)
+				},
+				TransferDestination::Withdraw =>
+					(source_account.iban.clone(), source_account.beneficiary.clone()),
+			};
+
+			let beneficiary = beneficiary.ok_or(Error::<T>::BeneficiaryNotSet)?;
+			ensure!(beneficiary.is_valid(), Error::<T>::InvalidBeneficiary);
 
 			let burn_request = BurnRequest {
 				id: request_id,
 				burner: source_account.iban,
+				burner_account: who.clone(),
 				dest_iban: dest_iban.clone(),
+				beneficiary,
 				amount,
+				currency,
+				status: BurnRequestStatus::Pending,
+				updated_at: frame_system::Pallet::<T>::block_number(),
 			};
 
 			// Create new burn request in the storage
+			Self::hold_in_escrow(&burn_request);
 			<BurnRequests<T>>::insert(request_id, burn_request);
 
 			// Increase burn request count
@@ -318,12 +604,7 @@ pub mod pallet {
 			};
 
 			// transfer amount to this pallet's account
-			T::Currency::transfer(
-				&who,
-				&Self::account_id(),
-				amount,
-				ExistenceRequirement::AllowDeath,
-			)?;
+			Self::transfer_funds(&currency, &who, &Self::account_id(), amount)?;
 
 			// create burn request event
 			Self::deposit_event(Event::BurnRequest {
@@ -332,6 +613,7 @@ pub mod pallet {
 				dest: dest_address,
 				dest_iban,
 				amount,
+				currency,
 			});
 
 			Ok(().into())
@@ -341,38 +623,68 @@ pub mod pallet {
 		///
 		/// This is used to process transactions in the bank statement
 		///
-		/// NOTE: This call can be called only by the offchain worker
+		/// NOTE: This call can be called only by the offchain worker authorities. The batch is
+		/// processed once `AttestationThreshold` authorities attested the same outcome.
 		/// Params:
 		///
-		/// `statements`: list of statements to process
-		/// 	`iban_account`: IBAN account connected to the statement
-		/// 	`Vec<Transaction>`: List of transactions to process
-		#[pallet::weight(T::DbWeight::get().reads_writes(T::MaxStatements::get() as u64, 1))]
+		/// `batch_id`: id of the queued statement batch to process
+		/// `image_id`: active image id that the receipt of the batch verified against
+		/// `journal`: journal of the verified receipt of the batch
+		/// `fake_receipt`: whether the receipt is a fake receipt instead of a proof
+		///
+		/// The journal has to commit to exactly the queued statements, otherwise the batch is
+		/// dropped and `JournalMismatch` is emitted. Fake receipts are refused unless
+		/// `FakeReceiptsAllowed` is set.
+		#[pallet::weight(Pallet::<T>::process_statements_weight(
+			T::MaxStatements::get() as u64,
+			T::MaxStatements::get() as u64,
+		).saturating_add(Pallet::<T>::attest_weight()))]
 		#[pallet::call_index(4)]
-		pub fn process_statements(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
-			// this can be called only by the ocw account
+		pub fn process_statements(
+			origin: OriginFor<T>,
+			batch_id: u64,
+			image_id: [u8; 32],
+			journal: Vec<u8>,
+			fake_receipt: bool,
+		) -> DispatchResultWithPostInfo {
+			// this can be called only by the ocw authorities
 			let who = ensure_signed(origin)?;
-			ensure!(who == T::OcwAccount::get(), Error::<T>::UnauthorizedCall);
-
-			let statements_info = QueuedStatements::<T>::take().ok_or(Error::<T>::IbanNotMapped)?;
+			Self::ensure_ocw_authority(&who)?;
+			ensure!(Self::is_image_id_active(&image_id), Error::<T>::ImageIdNotActive);
+			ensure!(
+				!fake_receipt || Self::fake_receipts_allowed(),
+				Error::<T>::FakeReceiptsNotAllowed
+			);
+			ensure!(QueuedStatements::<T>::contains_key(batch_id), Error::<T>::StatementsNotQueued);
 
-			log::info!("[OCW] Processing statements");
+			let hash = Self::verification_hash(Some((image_id, &journal, fake_receipt)));
+			if !Self::attest(AttestationSubject::Verification(batch_id), who, hash)? {
+				return Ok(().into())
+			}
 
-			for (iban_account, transactions) in statements_info.clone().statements {
-				let should_process = Self::should_process_transactions(&iban_account);
+			let statements_info =
+				Self::take_queued_statements(batch_id).ok_or(Error::<T>::StatementsNotQueued)?;
 
-				if should_process {
-					Self::process_transactions(&iban_account, &transactions)?;
-				}
+			// A valid proof of other statements must not authorize processing the batch
+			if let Err(error) = journal::check::<T>(&journal, &statements_info.statements) {
+				log::error!("[OCW] Journal does not match batch {}: {:?}", batch_id, error);
+				Self::deposit_event(Event::JournalMismatch { batch_id, error });
+				return Ok(().into())
 			}
 
-			Self::deposit_event(Event::ProofVerified { statements_info });
+			let (statements_count, transactions_count) =
+				Self::apply_statements(batch_id, image_id, fake_receipt, statements_info)?;
 
-			Ok(().into())
+			Ok(Some(Self::process_statements_weight(statements_count, transactions_count)).into())
 		}
 
 		/// Queue new statements for processing
-		#[pallet::weight(T::DbWeight::get().writes(1))]
+		///
+		/// Every batch is identified by an increasing batch id. The batch is queued once
+		/// `AttestationThreshold` offchain worker authorities attested the same statements.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 5).saturating_add(Pallet::<T>::attest_weight())
+		)]
 		#[pallet::call_index(5)]
 		pub fn queue_statements(
 			origin: OriginFor<T>,
@@ -382,134 +694,908 @@ pub mod pallet {
 			let who = ensure_signed(origin)?;
 			log::info!("who {:?}", who);
 
-			ensure!(who == T::OcwAccount::get(), Error::<T>::UnauthorizedCall);
+			Self::ensure_ocw_authority(&who)?;
+			ensure!(!Self::is_receipt_queued(&receipt_url), Error::<T>::StatementsAlreadyQueued);
 
-			QueuedStatements::<T>::put(QueuedStatementsInfo {
-				block_number: frame_system::Pallet::<T>::block_number(),
-				receipt_url: receipt_url.clone(),
-				statements,
-			});
+			let hash = (&receipt_url, &statements).using_encoded(sp_io::hashing::blake2_256);
+			if !Self::attest(AttestationSubject::Queue(receipt_url.clone()), who, hash)? {
+				return Ok(().into())
+			}
+
+			let batch_id = Self::statement_batch_count();
+			let block_number = frame_system::Pallet::<T>::block_number();
+
+			QueuedStatements::<T>::insert(
+				batch_id,
+				QueuedStatementsInfo { block_number, receipt_url: receipt_url.clone(), statements },
+			);
+			QueuedReceipts::<T>::insert(&receipt_url, batch_id);
+
+			let expires_at = block_number.saturating_add(T::StatementsExpiry::get());
+			StatementsExpiring::<T>::append(expires_at.saturating_add(1u32.into()), batch_id);
+
+			<StatementBatchCount<T>>::put(batch_id + 1);
 
 			log::info!("[OCW] Queued statements for processing");
 
+			Self::deposit_event(Event::StatementsQueued { batch_id, receipt_url });
+
 			Ok(().into())
 		}
 
-		/// Set image id of the risc0 module
-		#[pallet::weight(T::DbWeight::get().writes(1))]
-		#[pallet::call_index(6)]
-		pub fn set_risc0_image_id(
+		/// Request to link an IBAN to the caller's account
+		///
+		/// Records a pending link with a reference code and a small amount. The link is
+		/// activated once a bank statement contains a transaction of exactly that amount
+		/// from the IBAN, with the code in its reference. Pending links expire after
+		/// `LinkExpiry` blocks, requesting the link again renews it.
+		///
+		/// Several accounts can request the same IBAN, only the owner of the IBAN can make the
+		/// deposit that activates one of the links. At most `MaxPendingLinks` links to an IBAN
+		/// can be pending at once.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin of the call
+		/// * `iban` - IBAN to link
+		#[pallet::weight(T::DbWeight::get().reads_writes(5, 4))]
+		#[pallet::call_index(8)]
+		pub fn request_iban_link(
 			origin: OriginFor<T>,
-			image_id: [u8; 32],
+			iban: IbanOf<T>,
 		) -> DispatchResultWithPostInfo {
-			ensure_root(origin)?;
+			let who = ensure_signed(origin)?;
+
+			ensure!(!IbanToAccount::<T>::contains_key(&iban), Error::<T>::IbanAlreadyMapped);
+			ensure!(!Accounts::<T>::contains_key(&who), Error::<T>::AccountAlreadyMapped);
+
+			let now = frame_system::Pallet::<T>::block_number();
+
+			let (code, amount) = Self::generate_link_code(&who, &iban)?;
+
+			// Renewing a link does not add another one
+			if !PendingLinks::<T>::contains_key(&iban, &who) {
+				PendingLinkCount::<T>::try_mutate(&iban, |count| {
+					ensure!(*count < T::MaxPendingLinks::get(), Error::<T>::TooManyPendingLinks);
+					*count += 1;
+					Ok::<_, Error<T>>(())
+				})?;
+			}
+
+			let expires_at = now.saturating_add(T::LinkExpiry::get());
+
+			PendingLinks::<T>::insert(
+				&iban,
+				&who,
+				PendingLink { who: who.clone(), code, amount, expires_at },
+			);
+			PendingLinksExpiring::<T>::append(
+				expires_at.saturating_add(1u32.into()),
+				(iban.clone(), who.clone()),
+			);
 
-			Risc0ImageId::<T>::put(image_id);
+			Self::deposit_event(Event::IbanLinkRequested { who, iban, code, amount, expires_at });
 
 			Ok(().into())
 		}
-	}
-
-	#[pallet::event]
-	#[pallet::generate_deposit(pub(super) fn deposit_event)]
-	pub enum Event<T: Config> {
-		/// New IBAN has been mapped to an account
-		AccountCreated(T::AccountId, IbanOf<T>),
-		/// IBAN has been un-mapped from an account
-		AccountDestroyed(T::AccountId, IbanOf<T>),
-		/// New minted tokens to an account
-		Minted { who: T::AccountId, iban: IbanOf<T>, amount: BalanceOf<T> },
-		/// New burned tokens from an account
-		Burned { who: T::AccountId, iban: IbanOf<T>, amount: BalanceOf<T> },
-		/// New Burn request has been made
-		BurnRequest {
-			request_id: u64,
-			burner: T::AccountId,
-			dest: Option<T::AccountId>,
-			dest_iban: IbanOf<T>,
-			amount: BalanceOf<T>,
-		},
-		/// Transfer event with IBAN numbers
-		Transfer { from: IbanOf<T>, to: IbanOf<T>, amount: BalanceOf<T> },
-		/// Statement processed
-		StatementProcessed {
-			holder: AccountIdOf<T>,
-			iban: IbanOf<T>,
-			failed_transactions: Vec<u32>,
-		},
-		/// ZK proof verified
-		ProofVerified {
-			statements_info: QueuedStatementsInfo<BlockNumberFor<T>, StatementsOf<T>, StringOf<T>>,
-		},
-	}
 
-	#[pallet::error]
-	pub enum Error<T> {
-		/// Account is not mapped to an IBAN
-		AccountNotMapped,
-		/// IBAN is not mapped to an account
-		IbanNotMapped,
-		/// Account not found when trying to map IBAN
-		AccountNotFound,
-		/// Amount is zero
-		AmountIsZero,
-		/// Insufficient funds
-		InsufficientBalance,
-		/// Unauthorized call for `process_statements`. Only ocw account can call this
-		UnauthorizedCall,
-	}
+		/// Reject a queued statement batch whose receipt failed verification
+		///
+		/// NOTE: This call can be called only by the offchain worker authorities. The batch is
+		/// rejected once `AttestationThreshold` authorities attested the rejection.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(2, 3).saturating_add(Pallet::<T>::attest_weight())
+		)]
+		#[pallet::call_index(7)]
+		pub fn reject_statements(
+			origin: OriginFor<T>,
+			batch_id: u64,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_ocw_authority(&who)?;
+			ensure!(QueuedStatements::<T>::contains_key(batch_id), Error::<T>::StatementsNotQueued);
 
-	#[pallet::validate_unsigned]
-	impl<T: Config> ValidateUnsigned for Pallet<T> {
-		type Call = Call<T>;
-		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
-			if let Call::process_statements {} = call {
-				Self::validate_tx_parameters()
-			} else {
-				InvalidTransaction::Call.into()
+			let hash = Self::verification_hash(None);
+			if !Self::attest(AttestationSubject::Verification(batch_id), who, hash)? {
+				return Ok(().into())
 			}
-		}
-	}
 
-	/// Genesis configuration
-	#[pallet::genesis_config]
-	pub struct GenesisConfig<T: Config> {
-		pub accounts: Vec<(T::AccountId, Vec<u8>)>,
-	}
-	impl<T: Config> Default for GenesisConfig<T> {
-		fn default() -> Self {
-			GenesisConfig { accounts: vec![] }
+			Self::take_queued_statements(batch_id);
+
+			Self::deposit_event(Event::StatementsRejected { batch_id });
+
+			Ok(().into())
 		}
-	}
 
-	#[pallet::genesis_build]
-	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
-		fn build(&self) {
-			for (account, iban) in &self.accounts {
-				Accounts::<T>::insert(
-					account,
-					BankAccount {
-						iban: iban.clone().try_into().expect("IBAN should be valid"),
-						balance: 0u128,
-						last_updated: T::TimeProvider::now().as_millis() as u64,
-					},
-				);
+		/// Record the outcome of unpeg requests sent by the offchain worker
+		///
+		/// Only requests in flight are updated. Accepted requests are marked as submitted and
+		/// wait for their outgoing transaction. Rejected requests are retried with backoff, after
+		/// `MaxUnpegAttempts` rejections the escrowed funds are returned to the burner. Requests
+		/// with an unknown outcome might have been executed, they are not retried until the
+		/// outcome is resolved with `resolve_burn_request`.
+		///
+		/// NOTE: This call can be called only by the `UnpegAuthority`. Unlike statements, the
+		/// results are not attested by `AttestationThreshold` authorities: only the node that
+		/// sent the unpeg requests sees the responses of the EBICS service. Accepted requests
+		/// are confirmed independently once their outgoing transaction is in an attested
+		/// statement.
+		///
+		/// # Arguments
+		///
+		/// `results`: list of burn request ids and the outcomes of their unpeg requests
+		#[pallet::weight(T::DbWeight::get().reads_writes(
+			1 + 3 * results.len() as u64,
+			4 * results.len() as u64,
+		))]
+		#[pallet::call_index(9)]
+		pub fn report_unpeg_results(
+			origin: OriginFor<T>,
+			results: Vec<(u64, UnpegOutcome)>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::is_unpeg_authority(&who), Error::<T>::UnauthorizedCall);
+
+			for (request_id, outcome) in results {
+				Self::record_unpeg_result(request_id, outcome);
 			}
+
+			Ok(().into())
 		}
-	}
-}
 
-/// Utility functions
-impl<T: Config> Pallet<T> {
-	/// AccountId associated with Pallet
-	fn account_id() -> T::AccountId {
-		PALLET_ID.try_into_account().expect("Should never fail")
-	}
+		/// Cancel a burn request and return the escrowed funds
+		///
+		/// Only the account that made the request can cancel it, and only while its unpeg
+		/// request is not in flight and has not been accepted by the EBICS service. Cancelling
+		/// a request whose refund failed retries the refund.
+		///
+		/// # Arguments
+		///
+		/// `request_id`: id of the burn request to cancel
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 3))]
+		#[pallet::call_index(10)]
+		pub fn cancel_transfer(
+			origin: OriginFor<T>,
+			request_id: u64,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
 
-	/// Returns the action to take
-	fn choose_ocw_activity() -> OcwActivity {
-		// Get last activity recorded
-		let last_activity_ref = StorageValueRef::persistent(b"fiat_ramps:last_activity");
+			let request =
+				BurnRequests::<T>::get(request_id).ok_or(Error::<T>::BurnRequestNotFound)?;
+
+			ensure!(request.burner_account == who, Error::<T>::NotBurnRequestOwner);
+			ensure!(
+				!matches!(
+					request.status,
+					BurnRequestStatus::InFlight { .. } | BurnRequestStatus::Unknown { .. }
+				),
+				Error::<T>::BurnRequestInFlight
+			);
+			ensure!(
+				matches!(
+					request.status,
+					BurnRequestStatus::Pending |
+						BurnRequestStatus::Failed { .. } |
+						BurnRequestStatus::RefundFailed
+				),
+				Error::<T>::BurnRequestAlreadySubmitted
+			);
+
+			Self::transfer_funds(&request.currency, &Self::account_id(), &who, request.amount)?;
+
+			Self::release_escrow(&request);
+			BurnRequests::<T>::remove(request_id);
+
+			Self::deposit_event(Event::BurnRequestCancelled {
+				request_id,
+				burner: who,
+				amount: request.amount,
+			});
+
+			Ok(().into())
+		}
+
+		/// Set the default beneficiary of the caller's bank account
+		///
+		/// It is used for withdrawals and transfers to the caller's on-chain address
+		///
+		/// # Arguments
+		///
+		/// `beneficiary`: holder of the linked IBAN, `None` removes the default beneficiary
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		#[pallet::call_index(11)]
+		pub fn set_beneficiary(
+			origin: OriginFor<T>,
+			beneficiary: Option<BeneficiaryOf<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			if let Some(beneficiary) = &beneficiary {
+				ensure!(beneficiary.is_valid(), Error::<T>::InvalidBeneficiary);
+			}
+
+			Accounts::<T>::try_mutate(&who, |account| -> DispatchResult {
+				let account = account.as_mut().ok_or(Error::<T>::AccountNotMapped)?;
+				account.beneficiary = beneficiary;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::BeneficiaryUpdated { who });
+
+			Ok(().into())
+		}
+
+		/// Resume minting for an IBAN that was paused after a reconciliation mismatch
+		///
+		/// Should be called once the balances of the IBAN have been reconciled
+		///
+		/// # Arguments
+		///
+		/// `iban`: IBAN to resume minting for
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		#[pallet::call_index(12)]
+		pub fn resume_minting(origin: OriginFor<T>, iban: IbanOf<T>) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			ensure!(PausedMinting::<T>::take(&iban).is_some(), Error::<T>::MintingNotPaused);
+
+			Self::deposit_event(Event::MintingResumed { iban });
+
+			Ok(().into())
+		}
+
+		/// Set the format of the payment initiations sent for burn requests
+		///
+		/// JSON unpeg requests are understood by the EBICS service, pain.001 messages can be
+		/// sent to any EBICS gateway. JSON batches need a version of the EBICS service with the
+		/// `api-v1/unpeg/batch` endpoint
+		///
+		/// # Arguments
+		///
+		/// `format`: Format of the payment initiations
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		#[pallet::call_index(13)]
+		pub fn set_unpeg_format(
+			origin: OriginFor<T>,
+			format: PaymentFormat,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			<UnpegFormat<T>>::put(format);
+			Ok(().into())
+		}
+
+		/// Submit the receipt of a queued statement batch
+		///
+		/// The receipt is verified on chain against an active image id and its journal has to
+		/// commit to the queued statements, so anyone can advance a batch with a valid proof.
+		/// Only succinct receipts are accepted, composite receipts are too expensive to verify.
+		///
+		/// # Arguments
+		///
+		/// `batch_id`: id of the queued statement batch
+		/// `image_id`: active image id of the guest program that produced the receipt
+		/// `receipt`: JSON encoded risc0 receipt of the batch
+		#[pallet::weight(Pallet::<T>::submit_receipt_weight(
+			T::MaxStatements::get() as u64,
+			T::MaxStatements::get() as u64,
+			receipt.len() as u64,
+		))]
+		#[pallet::call_index(14)]
+		pub fn submit_receipt(
+			origin: OriginFor<T>,
+			batch_id: u64,
+			image_id: [u8; 32],
+			receipt: BoundedVec<u8, T::MaxReceiptLength>,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			ensure!(Self::is_image_id_active(&image_id), Error::<T>::ImageIdNotActive);
+			let statements_info =
+				QueuedStatements::<T>::get(batch_id).ok_or(Error::<T>::StatementsNotQueued)?;
+
+			let receipt_length = receipt.len() as u64;
+			let (receipt, _): (Receipt, _) =
+				serde_json_core::from_slice(&receipt).map_err(|_| Error::<T>::InvalidReceipt)?;
+
+			ensure!(
+				!matches!(receipt.inner, InnerReceipt::Composite(_)),
+				Error::<T>::InvalidReceipt
+			);
+			let (_, fake_receipt) =
+				Self::check_receipt(&receipt, &[image_id]).ok_or(Error::<T>::InvalidReceipt)?;
+			journal::check::<T>(&receipt.journal.bytes, &statements_info.statements)
+				.map_err(|_| Error::<T>::JournalMismatch)?;
+
+			Self::take_queued_statements(batch_id);
+			Self::remove_attestations(&AttestationSubject::Verification(batch_id));
+
+			let (statements_count, transactions_count) =
+				Self::apply_statements(batch_id, image_id, fake_receipt, statements_info)?;
+
+			let weight =
+				Self::submit_receipt_weight(statements_count, transactions_count, receipt_length);
+			Ok(Some(weight).into())
+		}
+
+		/// Register an image id of the risc0 module
+		///
+		/// Receipts of the image are accepted from the activation block until the image is
+		/// retired, so that a new guest program can be rolled out before the previous one is
+		/// sunset.
+		///
+		/// # Arguments
+		///
+		/// `image_id`: image id of the guest program
+		/// `activation`: block from which receipts of the image are accepted
+		/// `label`: label or version of the guest program
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 2))]
+		#[pallet::call_index(15)]
+		pub fn add_risc0_image_id(
+			origin: OriginFor<T>,
+			image_id: [u8; 32],
+			activation: BlockNumberFor<T>,
+			label: StringOf<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			ensure!(
+				!Risc0ImageIds::<T>::contains_key(image_id),
+				Error::<T>::ImageIdAlreadyRegistered
+			);
+			ensure!(
+				Risc0ImageIds::<T>::count() < T::MaxImageIds::get(),
+				Error::<T>::TooManyImageIds
+			);
+
+			Risc0ImageIds::<T>::insert(
+				image_id,
+				ImageIdInfo { activation, sunset: None, label: label.clone() },
+			);
+
+			Self::deposit_event(Event::ImageIdAdded { image_id, activation, label });
+
+			Ok(().into())
+		}
+
+		/// Retire an image id of the risc0 module
+		///
+		/// Receipts of the image are no longer accepted from the sunset block. An image that is
+		/// retired at the current block, or earlier, is removed from the registry.
+		///
+		/// # Arguments
+		///
+		/// `image_id`: image id of the guest program
+		/// `sunset`: block from which receipts of the image are no longer accepted
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 2))]
+		#[pallet::call_index(16)]
+		pub fn retire_risc0_image_id(
+			origin: OriginFor<T>,
+			image_id: [u8; 32],
+			sunset: BlockNumberFor<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			let mut info = Risc0ImageIds::<T>::get(image_id).ok_or(Error::<T>::ImageIdNotFound)?;
+
+			if sunset <= frame_system::Pallet::<T>::block_number() {
+				Risc0ImageIds::<T>::remove(image_id);
+			} else {
+				info.sunset = Some(sunset);
+				Risc0ImageIds::<T>::insert(image_id, info);
+			}
+
+			Self::deposit_event(Event::ImageIdRetired { image_id, sunset });
+
+			Ok(().into())
+		}
+
+		/// Allow or refuse fake receipts in place of proofs
+		///
+		/// Fake receipts can only be allowed on chains that permit them in their genesis config,
+		/// e.g. development and staging networks.
+		///
+		/// # Arguments
+		///
+		/// `allowed`: whether fake receipts are accepted
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		#[pallet::call_index(17)]
+		pub fn set_fake_receipts_allowed(
+			origin: OriginFor<T>,
+			allowed: bool,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			ensure!(
+				!allowed || Self::fake_receipts_permitted(),
+				Error::<T>::FakeReceiptsNotPermitted
+			);
+
+			FakeReceiptsAllowed::<T>::put(allowed);
+
+			Self::deposit_event(Event::FakeReceiptsAllowedSet { allowed });
+
+			Ok(().into())
+		}
+
+		/// Add an offchain worker authority
+		///
+		/// # Arguments
+		///
+		/// `who`: account the offchain worker of the authority signs with
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		#[pallet::call_index(18)]
+		pub fn add_ocw_authority(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			OcwAuthorities::<T>::try_mutate(|authorities| {
+				ensure!(!authorities.contains(&who), Error::<T>::OcwAuthorityAlreadyAdded);
+				authorities.try_push(who.clone()).map_err(|_| Error::<T>::TooManyOcwAuthorities)
+			})?;
+
+			Self::deposit_event(Event::OcwAuthorityAdded { who });
+
+			Ok(().into())
+		}
+
+		/// Remove an offchain worker authority
+		///
+		/// The remaining authorities have to be able to reach `AttestationThreshold`. Pending
+		/// attestations of the authority no longer count. The `UnpegAuthority` can not be
+		/// removed before another authority takes its place.
+		///
+		/// # Arguments
+		///
+		/// `who`: account of the authority
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+		#[pallet::call_index(19)]
+		pub fn remove_ocw_authority(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			ensure!(!Self::is_unpeg_authority(&who), Error::<T>::UnpegAuthorityInUse);
+
+			OcwAuthorities::<T>::try_mutate(|authorities| {
+				ensure!(authorities.contains(&who), Error::<T>::NotOcwAuthority);
+				ensure!(
+					authorities.len() as u32 > Self::attestation_threshold(),
+					Error::<T>::InvalidAttestationThreshold
+				);
+				authorities.retain(|authority| authority != &who);
+				Ok::<_, Error<T>>(())
+			})?;
+
+			Self::deposit_event(Event::OcwAuthorityRemoved { who });
+
+			Ok(().into())
+		}
+
+		/// Set the number of authorities that have to attest identical content
+		///
+		/// # Arguments
+		///
+		/// `threshold`: number of agreeing authorities, at least one and at most all of them
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		#[pallet::call_index(20)]
+		pub fn set_attestation_threshold(
+			origin: OriginFor<T>,
+			threshold: u32,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			ensure!(
+				threshold > 0 && threshold <= OcwAuthorities::<T>::decode_len().unwrap_or(0) as u32,
+				Error::<T>::InvalidAttestationThreshold
+			);
+
+			AttestationThreshold::<T>::put(threshold);
+
+			Self::deposit_event(Event::AttestationThresholdSet { threshold });
+
+			Ok(().into())
+		}
+
+		/// Claim due burn requests before their unpeg requests are sent
+		///
+		/// Claimed requests are in flight until the outcome of their unpeg request is reported
+		/// with `report_unpeg_results`, so that they can not be cancelled while the EBICS
+		/// service might execute them. Requests that are not due are ignored, as are requests
+		/// beyond `MaxUnpegBatch` in flight.
+		///
+		/// NOTE: This call can be called only by the `UnpegAuthority`, which is the only one
+		/// sending unpeg requests
+		///
+		/// # Arguments
+		///
+		/// `request_ids`: ids of at most `MaxUnpegBatch` burn requests
+		#[pallet::weight(T::DbWeight::get().reads_writes(
+			2 + request_ids.len() as u64,
+			1 + request_ids.len() as u64,
+		))]
+		#[pallet::call_index(21)]
+		pub fn claim_unpeg_requests(
+			origin: OriginFor<T>,
+			request_ids: Vec<u64>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::is_unpeg_authority(&who), Error::<T>::UnauthorizedCall);
+			ensure!(
+				request_ids.len() <= T::MaxUnpegBatch::get() as usize,
+				Error::<T>::UnpegBatchTooLarge
+			);
+
+			let block_number = frame_system::Pallet::<T>::block_number();
+			let mut in_flight = InFlightBurnRequests::<T>::get();
+
+			for request_id in request_ids {
+				let mut request = match BurnRequests::<T>::get(request_id) {
+					Some(request) if Self::is_unpeg_due(&request, block_number) => request,
+					_ => continue,
+				};
+				if in_flight.try_push(request_id).is_err() {
+					break
+				}
+
+				let attempts = match request.status {
+					BurnRequestStatus::Failed { attempts } => attempts,
+					_ => 0,
+				};
+
+				request.status = BurnRequestStatus::InFlight { attempts };
+				request.updated_at = block_number;
+
+				BurnRequests::<T>::insert(request_id, &request);
+				Self::deposit_event(Event::BurnRequestStatusChanged {
+					request_id,
+					status: request.status,
+				});
+			}
+
+			InFlightBurnRequests::<T>::put(in_flight);
+
+			Ok(().into())
+		}
+
+		/// Resolve the unknown outcome of an unpeg request
+		///
+		/// Used after checking with the bank whether the transfer was executed. Accepted
+		/// requests wait for their outgoing transaction, rejected ones are retried.
+		///
+		/// # Arguments
+		///
+		/// `request_id`: id of the burn request whose unpeg request has an unknown outcome
+		/// `accepted`: whether the EBICS service executed the unpeg request
+		#[pallet::weight(T::DbWeight::get().reads_writes(3, 3))]
+		#[pallet::call_index(22)]
+		pub fn resolve_burn_request(
+			origin: OriginFor<T>,
+			request_id: u64,
+			accepted: bool,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			let request =
+				BurnRequests::<T>::get(request_id).ok_or(Error::<T>::BurnRequestNotFound)?;
+
+			let attempts = match request.status {
+				BurnRequestStatus::Unknown { attempts } => attempts,
+				_ => return Err(Error::<T>::UnpegOutcomeKnown.into()),
+			};
+
+			let outcome = if accepted { UnpegOutcome::Accepted } else { UnpegOutcome::Rejected };
+			Self::apply_unpeg_outcome(request_id, request, attempts, outcome);
+
+			Ok(().into())
+		}
+
+		/// Set the authority that sends unpeg requests
+		///
+		/// The previous authority might have sent the requests that are in flight, their
+		/// outcome becomes unknown and has to be resolved with `resolve_burn_request`.
+		///
+		/// # Arguments
+		///
+		/// `who`: account of an offchain worker authority
+		#[pallet::weight(T::DbWeight::get().reads_writes(
+			2 + T::MaxUnpegBatch::get() as u64,
+			2 + T::MaxUnpegBatch::get() as u64,
+		))]
+		#[pallet::call_index(23)]
+		pub fn set_unpeg_authority(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			ensure!(Self::ocw_authorities().contains(&who), Error::<T>::NotOcwAuthority);
+
+			for request_id in InFlightBurnRequests::<T>::take() {
+				if let Some(request) = BurnRequests::<T>::get(request_id) {
+					if let BurnRequestStatus::InFlight { attempts } = request.status {
+						let outcome = UnpegOutcome::Unknown;
+						Self::apply_unpeg_outcome(request_id, request, attempts, outcome);
+					}
+				}
+			}
+
+			UnpegAuthority::<T>::put(&who);
+
+			Self::deposit_event(Event::UnpegAuthoritySet { who });
+
+			Ok(().into())
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// New IBAN has been mapped to an account
+		AccountCreated(T::AccountId, IbanOf<T>),
+		/// IBAN has been un-mapped from an account
+		AccountDestroyed(T::AccountId, IbanOf<T>),
+		/// New minted tokens to an account
+		Minted { who: T::AccountId, iban: IbanOf<T>, amount: BalanceOf<T>, currency: CurrencyCode },
+		/// New burned tokens from an account
+		Burned { who: T::AccountId, iban: IbanOf<T>, amount: BalanceOf<T>, currency: CurrencyCode },
+		/// New Burn request has been made
+		BurnRequest {
+			request_id: u64,
+			burner: T::AccountId,
+			dest: Option<T::AccountId>,
+			dest_iban: IbanOf<T>,
+			amount: BalanceOf<T>,
+			currency: CurrencyCode,
+		},
+		/// Transfer event with IBAN numbers
+		Transfer { from: IbanOf<T>, to: IbanOf<T>, amount: BalanceOf<T> },
+		/// Statement processed
+		StatementProcessed {
+			holder: AccountIdOf<T>,
+			iban: IbanOf<T>,
+			failed_transactions: Vec<u32>,
+		},
+		/// ZK proof verified against the image id, statements include their balances and dates
+		ProofVerified {
+			batch_id: u64,
+			image_id: [u8; 32],
+			statements_info: QueuedStatementsInfo<BlockNumberFor<T>, StatementsOf<T>, StringOf<T>>,
+		},
+		/// New statement batch has been queued for verification
+		StatementsQueued { batch_id: u64, receipt_url: StringOf<T> },
+		/// Receipt of the statement batch failed verification
+		StatementsRejected { batch_id: u64 },
+		/// Statement batch was not verified in time and has been removed
+		StatementsExpired { batch_id: u64 },
+		/// Journal of the receipt does not commit to the statement batch, which has been removed
+		JournalMismatch { batch_id: u64, error: JournalError },
+		/// Image id of the risc0 module has been registered
+		ImageIdAdded { image_id: [u8; 32], activation: BlockNumberFor<T>, label: StringOf<T> },
+		/// Image id of the risc0 module is no longer accepted from the sunset block
+		ImageIdRetired { image_id: [u8; 32], sunset: BlockNumberFor<T> },
+		/// Fake receipts are now accepted or refused in place of proofs
+		FakeReceiptsAllowedSet { allowed: bool },
+		/// Statement batch has been processed with a fake receipt instead of a proof
+		FakeReceiptAccepted { batch_id: u64, image_id: [u8; 32] },
+		/// Offchain worker authority has been added
+		OcwAuthorityAdded { who: T::AccountId },
+		/// Offchain worker authority has been removed
+		OcwAuthorityRemoved { who: T::AccountId },
+		/// Number of authorities that have to agree has been changed
+		AttestationThresholdSet { threshold: u32 },
+		/// Authority that sends unpeg requests has been changed
+		UnpegAuthoritySet { who: T::AccountId },
+		/// Authority attested the hash of the content of the subject
+		Attested { subject: AttestationSubjectOf<T>, authority: T::AccountId, hash: [u8; 32] },
+		/// Authorities attested other content than the threshold agreed on
+		AttestationDisagreement {
+			subject: AttestationSubjectOf<T>,
+			agreed: [u8; 32],
+			disagreeing: Vec<(T::AccountId, [u8; 32])>,
+		},
+		/// IBAN link is waiting for a deposit of `amount` with `code` in its reference
+		IbanLinkRequested {
+			who: T::AccountId,
+			iban: IbanOf<T>,
+			code: [u8; LINK_CODE_LENGTH],
+			amount: u128,
+			expires_at: BlockNumberFor<T>,
+		},
+		/// Pending IBAN link has not been confirmed in time and has been removed
+		IbanLinkExpired { who: T::AccountId, iban: IbanOf<T> },
+		/// Burn request has moved to a new status
+		BurnRequestStatusChanged { request_id: u64, status: BurnRequestStatus },
+		/// Funds of a confirmed burn request could not leave the pallet's account, the request
+		/// keeps its status until its outgoing transaction is processed again
+		BurnRequestSettlementFailed { request_id: u64, error: DispatchError },
+		/// Burn request has been cancelled and the funds returned to the burner
+		BurnRequestCancelled { request_id: u64, burner: T::AccountId, amount: BalanceOf<T> },
+		/// Default beneficiary of the account's bank account has been changed
+		BeneficiaryUpdated { who: T::AccountId },
+		/// Closing balance of the IBAN does not match the funds on chain
+		ReconciliationMismatch { iban: IbanOf<T>, bank: u128, chain: u128 },
+		/// Minting for the IBAN has been resumed after a reconciliation mismatch
+		MintingResumed { iban: IbanOf<T> },
+		/// Transaction of the statement has already been processed
+		DuplicateTransactionSkipped { iban: IbanOf<T>, transaction_id: [u8; 32] },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Account is not mapped to an IBAN
+		AccountNotMapped,
+		/// IBAN is not mapped to an account
+		IbanNotMapped,
+		/// Account not found when trying to map IBAN
+		AccountNotFound,
+		/// Amount is zero
+		AmountIsZero,
+		/// Insufficient funds
+		InsufficientBalance,
+		/// Unauthorized call for `process_statements`. Only ocw account can call this
+		UnauthorizedCall,
+		/// No statement batch is queued under the given id
+		StatementsNotQueued,
+		/// Statements with the same receipt are already waiting for verification
+		StatementsAlreadyQueued,
+		/// IBAN is already linked to an account
+		IbanAlreadyMapped,
+		/// Account is already linked to an IBAN
+		AccountAlreadyMapped,
+		/// IBAN does not match the one linked to the account
+		IbanMismatch,
+		/// Burn requests of the account have funds in escrow
+		BurnRequestsPending,
+		/// IBAN has `MaxPendingLinks` pending links
+		TooManyPendingLinks,
+		/// Micro-deposit of an IBAN link can not be expressed with `FiatDecimals` decimal places
+		UnsupportedFiatDecimals,
+		/// There is no burn request with the given id
+		BurnRequestNotFound,
+		/// Burn request was made by another account
+		NotBurnRequestOwner,
+		/// Unpeg request has already been accepted by the EBICS service
+		BurnRequestAlreadySubmitted,
+		/// Unpeg request might be executed by the EBICS service, its outcome is not known yet
+		BurnRequestInFlight,
+		/// Outcome of the unpeg request of the burn request is not unknown
+		UnpegOutcomeKnown,
+		/// Currency is not mapped to an on-chain currency
+		UnsupportedCurrency,
+		/// Receiving bank account has no default beneficiary
+		BeneficiaryNotSet,
+		/// Beneficiary has no name, or an invalid country code or BIC
+		InvalidBeneficiary,
+		/// Minting for the IBAN is paused after a reconciliation mismatch
+		MintingPaused,
+		/// Minting for the IBAN is not paused
+		MintingNotPaused,
+		/// Image id is not registered or not active at the current block
+		ImageIdNotActive,
+		/// Image id is already registered
+		ImageIdAlreadyRegistered,
+		/// Image id is not registered
+		ImageIdNotFound,
+		/// Registry has `MaxImageIds` image ids
+		TooManyImageIds,
+		/// Receipt cannot be decoded, is not succinct or does not verify against the image id
+		InvalidReceipt,
+		/// Journal of the receipt does not commit to the queued statements
+		JournalMismatch,
+		/// Fake receipts are not accepted at the moment
+		FakeReceiptsNotAllowed,
+		/// Fake receipts are not permitted on this chain
+		FakeReceiptsNotPermitted,
+		/// Account is already an offchain worker authority
+		OcwAuthorityAlreadyAdded,
+		/// Account is not an offchain worker authority
+		NotOcwAuthority,
+		/// There are `MaxOcwAuthorities` authorities
+		TooManyOcwAuthorities,
+		/// Threshold has to be at least one and at most the number of authorities
+		InvalidAttestationThreshold,
+		/// Authority has `MaxPendingAttestations` subjects that did not reach the threshold
+		TooManyPendingAttestations,
+		/// Authority sends the unpeg requests, set another `UnpegAuthority` first
+		UnpegAuthorityInUse,
+		/// More than `MaxUnpegBatch` burn requests are claimed at once
+		UnpegBatchTooLarge,
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::process_statements { .. } = call {
+				Self::validate_tx_parameters()
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
+	}
+
+	/// Genesis configuration
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub accounts: Vec<(T::AccountId, Vec<u8>)>,
+		/// Whether fake receipts may be allowed on the chain, e.g. on development chains
+		pub fake_receipts_permitted: bool,
+		/// Whether fake receipts are accepted from genesis
+		pub fake_receipts_allowed: bool,
+		/// Accounts of the offchain worker authorities
+		pub ocw_authorities: Vec<T::AccountId>,
+		/// Number of authorities that have to attest identical content
+		pub attestation_threshold: u32,
+	}
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			GenesisConfig {
+				accounts: vec![],
+				fake_receipts_permitted: false,
+				fake_receipts_allowed: false,
+				ocw_authorities: vec![],
+				attestation_threshold: 0,
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			assert!(
+				self.fake_receipts_permitted || !self.fake_receipts_allowed,
+				"Fake receipts can only be allowed if they are permitted"
+			);
+			FakeReceiptsPermitted::<T>::put(self.fake_receipts_permitted);
+			FakeReceiptsAllowed::<T>::put(self.fake_receipts_allowed);
+
+			assert!(
+				self.attestation_threshold as usize <= self.ocw_authorities.len(),
+				"Attestation threshold can not exceed the number of authorities"
+			);
+			let authorities: BoundedVec<_, T::MaxOcwAuthorities> = self
+				.ocw_authorities
+				.clone()
+				.try_into()
+				.expect("Too many offchain worker authorities");
+			if let Some(authority) = authorities.first() {
+				UnpegAuthority::<T>::put(authority);
+			}
+			OcwAuthorities::<T>::put(authorities);
+			AttestationThreshold::<T>::put(self.attestation_threshold);
+
+			for (account, iban) in &self.accounts {
+				let iban: IbanOf<T> = iban.clone().try_into().expect("IBAN should be valid");
+
+				IbanToAccount::<T>::insert(&iban, account);
+				Accounts::<T>::insert(
+					account,
+					BankAccount {
+						iban,
+						opening_balance: 0u128,
+						balance: 0u128,
+						currency: None,
+						last_updated: T::TimeProvider::now().as_millis() as u64,
+						beneficiary: None,
+					},
+				);
+			}
+		}
+	}
+}
+
+/// Utility functions
+impl<T: Config> Pallet<T> {
+	/// AccountId associated with Pallet
+	fn account_id() -> T::AccountId {
+		PALLET_ID.try_into_account().expect("Should never fail")
+	}
+
+	/// Returns the action to take
+	fn choose_ocw_activity() -> OcwActivity {
+		// Get last activity recorded
+		let last_activity_ref = StorageValueRef::persistent(b"fiat_ramps:last_activity");
 
 		// Fetch last activity and set new one
 		// Activity order is following: Process statements -> Process burn requests -> Do nothing -> Repeat
@@ -544,50 +1630,557 @@ impl<T: Config> Pallet<T> {
 		let now = T::TimeProvider::now();
 		let minimum_interval = T::MinimumInterval::get();
 
-		// Start off by creating a reference to Local Storage value.
-		let val = StorageValueRef::persistent(b"fiat_ramps::last_sync");
+		// Start off by creating a reference to Local Storage value.
+		let val = StorageValueRef::persistent(b"fiat_ramps::last_sync");
+
+		// Retrieve the value from the storage.
+		let res = val.mutate(|last_sync: Result<Option<u128>, StorageRetrievalError>| {
+			match last_sync {
+				// If we already have a value in storage and the block number is recent enough
+				// we avoid sending another transaction at this time.
+				Ok(Some(last_sync_at))
+					if now.as_millis() < last_sync_at + minimum_interval as u128 =>
+					Err(RECENTLY_SENT),
+				// In every other case we attempt to acquire the lock and send a transaction.
+				_ => Ok(now.as_millis()),
+			}
+		});
+
+		match res {
+			Ok(_now) => true,
+			Err(MutateStorageError::ValueFunctionFailed(RECENTLY_SENT)) => false,
+			Err(MutateStorageError::ConcurrentModification(_)) => false,
+		}
+	}
+
+	/// Determines if we should process transactions for the statement
+	///
+	/// We should always sync statements if:
+	/// - the balances on chain and on the bank do not match
+	/// - iban account is not mapped to any account, we should create new account and sync transactions
+	///
+	/// # Arguments
+	///
+	/// `iban_account`: IbanAccount to check
+	fn should_process_transactions(_iban_account: &BankAccountOf<T>) -> bool {
+		true
+	}
+
+	/// Checks if a statement batch with the given receipt is already queued
+	fn is_receipt_queued(receipt_url: &StringOf<T>) -> bool {
+		QueuedReceipts::<T>::contains_key(receipt_url)
+	}
+
+	/// Removes a statement batch and its receipt URL from the queue
+	fn take_queued_statements(batch_id: u64) -> Option<QueuedStatementsInfoOf<T>> {
+		let statements_info = QueuedStatements::<T>::take(batch_id)?;
+		QueuedReceipts::<T>::remove(&statements_info.receipt_url);
+		Some(statements_info)
+	}
+
+	/// Removes statement batches that were not verified within `StatementsExpiry` blocks
+	///
+	/// Returns the weight consumed
+	fn prune_expired_statements(block_number: BlockNumberFor<T>) -> Weight {
+		let expiring = StatementsExpiring::<T>::take(block_number);
+		let mut writes = 1u64;
+
+		for batch_id in &expiring {
+			// Batch might have been processed or rejected in the meantime
+			if Self::take_queued_statements(*batch_id).is_some() {
+				Self::deposit_event(Event::StatementsExpired { batch_id: *batch_id });
+				writes += 2;
+			}
+		}
+
+		T::DbWeight::get().reads_writes(1 + expiring.len() as u64, writes)
+	}
+
+	/// Removes attestations that did not reach the threshold within `StatementsExpiry` blocks
+	///
+	/// Returns the weight consumed
+	fn prune_expired_attestations(block_number: BlockNumberFor<T>) -> Weight {
+		let expiry = T::StatementsExpiry::get();
+		let expiring = AttestationsExpiring::<T>::take(block_number);
+
+		let mut reads = 1u64;
+		let mut writes = 1u64;
+
+		for subject in &expiring {
+			reads += 1;
+			// Subject might have reached the threshold and been attested again in the meantime
+			match Attestations::<T>::get(subject) {
+				Some(info) if info.block_number.saturating_add(expiry) < block_number => {
+					reads += info.votes.len() as u64;
+					writes += 1 + info.votes.len() as u64;
+					Self::remove_attestations(subject);
+				},
+				_ => {},
+			}
+		}
+
+		T::DbWeight::get().reads_writes(reads, writes)
+	}
+
+	/// Ensures that the account is an offchain worker authority
+	fn ensure_ocw_authority(who: &T::AccountId) -> DispatchResult {
+		ensure!(Self::ocw_authorities().contains(who), Error::<T>::UnauthorizedCall);
+		Ok(())
+	}
+
+	/// Whether the account is the authority that sends unpeg requests
+	fn is_unpeg_authority(who: &T::AccountId) -> bool {
+		Self::unpeg_authority().as_ref() == Some(who)
+	}
+
+	/// Hash of the outcome of verifying a batch that authorities attest: the image id, the
+	/// journal and whether the receipt is fake, or `None` if the receipt was rejected
+	fn verification_hash(outcome: Option<([u8; 32], &[u8], bool)>) -> [u8; 32] {
+		outcome.using_encoded(sp_io::hashing::blake2_256)
+	}
+
+	/// Records the attestation of the authority and returns whether `AttestationThreshold`
+	/// authorities agree on its hash
+	///
+	/// An authority that attests again replaces its previous attestation, attestations of
+	/// removed authorities do not count. An authority can attest at most
+	/// `MaxPendingAttestations` subjects that did not reach the threshold, they expire after
+	/// `StatementsExpiry` blocks. Once the threshold is reached the attestations of the subject
+	/// are removed and authorities that attested another hash are reported.
+	fn attest(
+		subject: AttestationSubjectOf<T>,
+		who: T::AccountId,
+		hash: [u8; 32],
+	) -> Result<bool, DispatchError> {
+		let authorities = Self::ocw_authorities();
+
+		let mut info = match Attestations::<T>::get(&subject) {
+			Some(info) => info,
+			None => {
+				let block_number = frame_system::Pallet::<T>::block_number();
+				let expires_at = block_number.saturating_add(T::StatementsExpiry::get());
+				AttestationsExpiring::<T>::append(expires_at.saturating_add(1u32.into()), &subject);
+				AttestationsInfo { block_number, votes: vec![] }
+			},
+		};
+
+		if !info.votes.iter().any(|(authority, _)| authority == &who) {
+			PendingAttestations::<T>::try_mutate(&who, |pending| {
+				ensure!(
+					*pending < T::MaxPendingAttestations::get(),
+					Error::<T>::TooManyPendingAttestations
+				);
+				*pending += 1;
+				Ok::<_, Error<T>>(())
+			})?;
+		}
+
+		let (votes, removed): (Vec<_>, Vec<_>) = core::mem::take(&mut info.votes)
+			.into_iter()
+			.filter(|(authority, _)| authority != &who)
+			.partition(|(authority, _)| authorities.contains(authority));
+		for (authority, _) in &removed {
+			Self::release_attestation(authority);
+		}
+		info.votes = votes;
+		info.votes.push((who.clone(), hash));
+
+		Self::deposit_event(Event::Attested { subject: subject.clone(), authority: who, hash });
+
+		let agreeing = info.votes.iter().filter(|(_, attested)| attested == &hash).count() as u32;
+		if agreeing < Self::attestation_threshold().max(1) {
+			Attestations::<T>::insert(&subject, info);
+			return Ok(false)
+		}
+
+		Attestations::<T>::remove(&subject);
+		for (authority, _) in &info.votes {
+			Self::release_attestation(authority);
+		}
+
+		let disagreeing: Vec<_> =
+			info.votes.into_iter().filter(|(_, attested)| attested != &hash).collect();
+		if !disagreeing.is_empty() {
+			log::warn!("[OCW] Authorities disagree on {:?}: {:?}", subject, disagreeing);
+			Self::deposit_event(Event::AttestationDisagreement {
+				subject,
+				agreed: hash,
+				disagreeing,
+			});
+		}
+
+		Ok(true)
+	}
+
+	/// Removes the attestations of the subject and releases them from the authorities' counts
+	fn remove_attestations(subject: &AttestationSubjectOf<T>) {
+		if let Some(info) = Attestations::<T>::take(subject) {
+			for (authority, _) in &info.votes {
+				Self::release_attestation(authority);
+			}
+		}
+	}
+
+	/// Releases one of the pending attestations of the authority
+	fn release_attestation(authority: &T::AccountId) {
+		PendingAttestations::<T>::mutate_exists(authority, |pending| {
+			*pending = pending.and_then(|pending| pending.checked_sub(1)).filter(|p| *p > 0);
+		});
+	}
+
+	/// Weight of `attest`
+	///
+	/// Reads and writes the attestations of the subject, the expiry index and the pending
+	/// attestations of every authority that attested it
+	pub(crate) fn attest_weight() -> Weight {
+		let authorities = T::MaxOcwAuthorities::get() as u64;
+		T::DbWeight::get().reads_writes(3 + authorities, 2 + authorities)
+	}
+
+	/// Weight of `process_statements`
+	///
+	/// Every statement resolves its owner and is reconciled with its on-chain balance, every
+	/// transaction is checked for duplicates, needs at most three IBAN lookups, a burn request
+	/// lookup and a balance transfer. A deposit might activate one of the `MaxPendingLinks`
+	/// pending links to its IBAN, which reads and removes all of them.
+	pub(crate) fn process_statements_weight(statements: u64, transactions: u64) -> Weight {
+		let links = T::MaxPendingLinks::get() as u64;
+		T::DbWeight::get().reads_writes(
+			1 + statements * 5 + transactions * (10 + links),
+			2 + statements * 4 + transactions * (8 + links),
+		)
+	}
+
+	/// Image ids that receipts are accepted for at the current block
+	fn active_image_ids() -> Vec<[u8; 32]> {
+		let block_number = frame_system::Pallet::<T>::block_number();
+
+		Risc0ImageIds::<T>::iter()
+			.filter(|(_, info)| info.is_active(&block_number))
+			.map(|(image_id, _)| image_id)
+			.collect()
+	}
+
+	/// Whether the image id is registered and active at the current block
+	fn is_image_id_active(image_id: &[u8; 32]) -> bool {
+		Risc0ImageIds::<T>::get(image_id)
+			.map_or(false, |info| info.is_active(&frame_system::Pallet::<T>::block_number()))
+	}
+
+	/// Most recently activated image id that is active at the current block
+	pub fn risc0_image_id() -> Option<[u8; 32]> {
+		let block_number = frame_system::Pallet::<T>::block_number();
+
+		Risc0ImageIds::<T>::iter()
+			.filter(|(_, info)| info.is_active(&block_number))
+			.max_by_key(|(_, info)| info.activation)
+			.map(|(image_id, _)| image_id)
+	}
+
+	/// Weight of `submit_receipt`
+	///
+	/// Verifying the receipt dominates, on top of decoding its `receipt_length` bytes, reading
+	/// the image id and processing the statements
+	pub(crate) fn submit_receipt_weight(
+		statements: u64,
+		transactions: u64,
+		receipt_length: u64,
+	) -> Weight {
+		T::ReceiptVerificationWeight::get()
+			.saturating_add(T::ReceiptByteWeight::get().saturating_mul(receipt_length))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(Self::process_statements_weight(statements, transactions))
+	}
+
+	/// Processes the transactions of a statement batch that has been taken from the queue
+	///
+	/// Returns the number of statements and processed transactions
+	fn apply_statements(
+		batch_id: u64,
+		image_id: [u8; 32],
+		fake_receipt: bool,
+		statements_info: QueuedStatementsInfoOf<T>,
+	) -> Result<(u64, u64), DispatchError> {
+		log::info!("[OCW] Processing statements");
+
+		let mut transactions_count = 0u64;
+
+		for (iban_account, transactions) in statements_info.clone().statements {
+			let should_process = Self::should_process_transactions(&iban_account);
+
+			if should_process {
+				Self::process_transactions(&iban_account, &transactions)?;
+				// Notifications do not report balances
+				if T::StatementFormat::get().reports_balances() {
+					Self::reconcile_balance(&iban_account, &transactions);
+				}
+				transactions_count += transactions.len() as u64;
+			}
+		}
+
+		let statements_count = statements_info.statements.len() as u64;
+
+		if fake_receipt {
+			log::warn!("[OCW] Statement batch {} processed with a fake receipt", batch_id);
+			Self::deposit_event(Event::FakeReceiptAccepted { batch_id, image_id });
+		}
+		Self::deposit_event(Event::ProofVerified { batch_id, image_id, statements_info });
+
+		Ok((statements_count, transactions_count))
+	}
+
+	/// Links IBAN to the account in `Accounts` and `IbanToAccount`
+	fn link_account(who: T::AccountId, iban: IbanOf<T>) {
+		IbanToAccount::<T>::insert(&iban, &who);
+		Accounts::<T>::insert(
+			&who,
+			BankAccountOf::<T> {
+				iban: iban.clone(),
+				opening_balance: 0u128,
+				balance: 0u128,
+				currency: None,
+				last_updated: T::TimeProvider::now().as_millis() as u64,
+				beneficiary: None,
+			},
+		);
+
+		Self::deposit_event(Event::AccountCreated(who, iban));
+	}
+
+	/// Generates reference code and amount of the micro-deposit for an IBAN link
+	///
+	/// Both are derived from the parent block hash, so they can not be known before
+	/// the link is requested. The amount is between 0.01 and 0.99 units, or between 0.1 and
+	/// 0.9 units if amounts have a single decimal place. Amounts without decimal places can not
+	/// be smaller than a unit, and amounts with too many of them overflow, so no micro-deposit
+	/// can be requested then.
+	fn generate_link_code(
+		who: &T::AccountId,
+		iban: &IbanOf<T>,
+	) -> Result<([u8; LINK_CODE_LENGTH], u128), Error<T>> {
+		let seed = (
+			frame_system::Pallet::<T>::parent_hash(),
+			frame_system::Pallet::<T>::extrinsic_index(),
+			who,
+			iban,
+		)
+			.using_encoded(sp_io::hashing::blake2_256);
+
+		let mut code = [0u8; LINK_CODE_LENGTH];
+		for (c, byte) in code.iter_mut().zip(seed.iter()) {
+			*c = LINK_CODE_ALPHABET[*byte as usize % LINK_CODE_ALPHABET.len()];
+		}
+
+		let random = u16::from_le_bytes([seed[30], seed[31]]) as u128;
+
+		let amount = match T::FiatDecimals::get() {
+			0 => None,
+			1 => Some(random % 9 + 1),
+			decimals => 10u128
+				.checked_pow(decimals - 2)
+				.filter(|cent| cent.checked_mul(99).is_some())
+				.map(|cent| (random % 99 + 1) * cent),
+		}
+		.ok_or(Error::<T>::UnsupportedFiatDecimals)?;
+
+		Ok((code, amount))
+	}
+
+	/// Activates the pending IBAN link whose micro-deposit the transaction is
+	///
+	/// The deposit proves ownership of the IBAN, so the links of other accounts to the IBAN
+	/// are removed. Returns whether the IBAN was linked.
+	fn try_activate_link(transaction: &TransactionOf<T>) -> bool {
+		if !PendingLinkCount::<T>::contains_key(&transaction.iban) {
+			return false
+		}
+
+		let now = frame_system::Pallet::<T>::block_number();
+
+		let link = PendingLinks::<T>::iter_prefix_values(&transaction.iban).find(|link| {
+			let has_code =
+				transaction.reference.windows(link.code.len()).any(|w| w == &link.code[..]);
+			link.expires_at >= now && link.amount == transaction.amount && has_code
+		});
+
+		let link = match link {
+			Some(link) => link,
+			None => return false,
+		};
+
+		let _ = PendingLinks::<T>::clear_prefix(&transaction.iban, T::MaxPendingLinks::get(), None);
+		PendingLinkCount::<T>::remove(&transaction.iban);
+
+		// IBAN or account might have been linked by root in the meantime
+		if IbanToAccount::<T>::contains_key(&transaction.iban) ||
+			Accounts::<T>::contains_key(&link.who)
+		{
+			log::info!("[OCW] IBAN link {:?} is obsolete", &transaction.iban);
+			return false
+		}
+
+		Self::link_account(link.who, transaction.iban.clone());
+
+		true
+	}
+
+	/// Removes pending IBAN links that were not confirmed within `LinkExpiry` blocks
+	///
+	/// Returns the weight consumed
+	fn prune_expired_links(block_number: BlockNumberFor<T>) -> Weight {
+		let expiring = PendingLinksExpiring::<T>::take(block_number);
+		let mut writes = 1u64;
+
+		for (iban, who) in &expiring {
+			// Link might have been renewed or activated in the meantime
+			match PendingLinks::<T>::get(iban, who) {
+				Some(link) if link.expires_at < block_number => {
+					PendingLinks::<T>::remove(iban, who);
+					PendingLinkCount::<T>::mutate_exists(iban, |count| {
+						*count =
+							count.map(|count| count.saturating_sub(1)).filter(|count| *count > 0)
+					});
+					Self::deposit_event(Event::IbanLinkExpired {
+						who: who.clone(),
+						iban: iban.clone(),
+					});
+					writes += 2;
+				},
+				_ => {},
+			}
+		}
+
+		T::DbWeight::get().reads_writes(1 + 2 * expiring.len() as u64, writes)
+	}
+
+	/// Forgets the processed transactions that expire at `block_number`
+	///
+	/// Returns the weight consumed
+	fn prune_processed_transactions(block_number: BlockNumberFor<T>) -> Weight {
+		let expired = ProcessedTransactionsExpiring::<T>::take(block_number);
+
+		for (iban, transaction_id) in &expired {
+			ProcessedTransactions::<T>::remove(iban, transaction_id);
+		}
+
+		T::DbWeight::get().reads_writes(1, 1 + expired.len() as u64)
+	}
+
+	/// Checks if burn requests of the account that are not settled yet hold funds in escrow
+	///
+	/// Requests to the account do not hold its funds, so they do not count
+	fn has_escrowed_funds(who: &T::AccountId) -> bool {
+		EscrowedFunds::<T>::iter_prefix(who).next().is_some()
+	}
+
+	/// Checks if the unpeg request of a burn request should be sent at `block_number`
+	///
+	/// Failed requests wait `UnpegRetryDelay` blocks after the first failure, doubling the
+	/// delay after each further failure. Requests that failed `MaxUnpegAttempts` times are
+	/// refunded instead.
+	fn is_unpeg_due(request: &BurnRequestOf<T>, block_number: BlockNumberFor<T>) -> bool {
+		match request.status {
+			BurnRequestStatus::Pending => true,
+			BurnRequestStatus::Failed { attempts } if attempts < T::MaxUnpegAttempts::get() => {
+				let backoff = T::UnpegRetryDelay::get()
+					.saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)).into());
+				request.updated_at.saturating_add(backoff) <= block_number
+			},
+			_ => false,
+		}
+	}
+
+	/// Updates the status of a burn request with the outcome of its unpeg request
+	///
+	/// Reports for requests that are not in flight are ignored
+	fn record_unpeg_result(request_id: u64, outcome: UnpegOutcome) {
+		let request = match BurnRequests::<T>::get(request_id) {
+			Some(request) => request,
+			None => return,
+		};
+
+		if let BurnRequestStatus::InFlight { attempts } = request.status {
+			InFlightBurnRequests::<T>::mutate(|in_flight| in_flight.retain(|id| *id != request_id));
+			Self::apply_unpeg_outcome(request_id, request, attempts, outcome);
+		}
+	}
+
+	/// Sets the status of a burn request after `attempts` earlier failures to the outcome of
+	/// its unpeg request, refunding it after `MaxUnpegAttempts` failures
+	fn apply_unpeg_outcome(
+		request_id: u64,
+		mut request: BurnRequestOf<T>,
+		attempts: u32,
+		outcome: UnpegOutcome,
+	) {
+		let status = match outcome {
+			UnpegOutcome::Accepted => BurnRequestStatus::Submitted,
+			UnpegOutcome::Rejected =>
+				BurnRequestStatus::Failed { attempts: attempts.saturating_add(1) },
+			UnpegOutcome::Unknown => BurnRequestStatus::Unknown { attempts },
+		};
+
+		request.status = status;
+		request.updated_at = frame_system::Pallet::<T>::block_number();
+
+		BurnRequests::<T>::insert(request_id, &request);
+		Self::deposit_event(Event::BurnRequestStatusChanged { request_id, status });
 
-		// Retrieve the value from the storage.
-		let res = val.mutate(|last_sync: Result<Option<u128>, StorageRetrievalError>| {
-			match last_sync {
-				// If we already have a value in storage and the block number is recent enough
-				// we avoid sending another transaction at this time.
-				Ok(Some(last_sync_at))
-					if now.as_millis() < last_sync_at + minimum_interval as u128 =>
-					Err(RECENTLY_SENT),
-				// In every other case we attempt to acquire the lock and send a transaction.
-				_ => Ok(now.as_millis()),
+		if let BurnRequestStatus::Failed { attempts } = status {
+			if attempts >= T::MaxUnpegAttempts::get() {
+				Self::refund_burn_request(request_id, request);
 			}
+		}
+	}
+
+	/// Adds the amount of the burn request to the funds of the burner held in escrow
+	fn hold_in_escrow(request: &BurnRequestOf<T>) {
+		EscrowedFunds::<T>::mutate(&request.burner_account, request.currency, |escrow| {
+			*escrow = escrow.saturating_add(request.amount)
 		});
+	}
 
-		match res {
-			Ok(_now) => true,
-			Err(MutateStorageError::ValueFunctionFailed(RECENTLY_SENT)) => false,
-			Err(MutateStorageError::ConcurrentModification(_)) => false,
-		}
+	/// Removes the amount of the burn request from the funds of the burner held in escrow
+	fn release_escrow(request: &BurnRequestOf<T>) {
+		EscrowedFunds::<T>::mutate_exists(&request.burner_account, request.currency, |escrow| {
+			*escrow = escrow
+				.map(|escrow| escrow.saturating_sub(request.amount))
+				.filter(|escrow| !escrow.is_zero());
+		});
 	}
 
-	/// Determines if we should process transactions for the statement
-	///
-	/// We should always sync statements if:
-	/// - the balances on chain and on the bank do not match
-	/// - iban account is not mapped to any account, we should create new account and sync transactions
-	///
-	/// # Arguments
+	/// Returns the escrowed funds of a burn request to the burner and removes the request
 	///
-	/// `iban_account`: IbanAccount to check
-	fn should_process_transactions(_iban_account: &BankAccountOf<T>) -> bool {
-		true
-	}
+	/// If the transfer fails, the request is kept as `RefundFailed` until the burner cancels it
+	fn refund_burn_request(request_id: u64, mut request: BurnRequestOf<T>) {
+		if let Err(e) = Self::transfer_funds(
+			&request.currency,
+			&Self::account_id(),
+			&request.burner_account,
+			request.amount,
+		) {
+			log::error!("[OCW] Refund of burn request {} failed: {:?}", request_id, e);
+
+			request.status = BurnRequestStatus::RefundFailed;
+			BurnRequests::<T>::insert(request_id, &request);
+			Self::deposit_event(Event::BurnRequestStatusChanged {
+				request_id,
+				status: BurnRequestStatus::RefundFailed,
+			});
+			return
+		}
 
-	/// Checks if iban is mapped to an account in the storage
-	fn iban_exists(iban: &IbanOf<T>) -> bool {
-		Accounts::<T>::iter().any(|(_, v)| &v.iban == iban)
+		Self::release_escrow(&request);
+		BurnRequests::<T>::remove(request_id);
+		Self::deposit_event(Event::BurnRequestStatusChanged {
+			request_id,
+			status: BurnRequestStatus::Refunded,
+		});
 	}
 
 	/// Extract AccountId mapped to IbanOf<T>
 	fn get_account_id(iban: &IbanOf<T>) -> Option<T::AccountId> {
-		Accounts::<T>::iter().find(|(_, v)| &v.iban == iban).map(|(k, _)| k)
+		IbanToAccount::<T>::get(iban)
 	}
 
 	/// Ensures that an IBAN  is mapped to an account in the storage
@@ -595,9 +2188,9 @@ impl<T: Config> Pallet<T> {
 	/// If necessary, creates new account
 	fn ensure_iban_is_mapped(iban: &IbanOf<T>) -> AccountIdOf<T> {
 		// If iban is already mapped to account, return it
-		if Self::iban_exists(iban) {
+		if let Some(account_id) = Self::get_account_id(iban) {
 			log::debug!("IBAN already exists in the storage {:?}", &iban);
-			Accounts::<T>::iter().find(|(_, v)| &v.iban == iban).unwrap().0
+			account_id
 		} else {
 			let bank_account: BankAccountOf<T> = iban.into();
 			let hashed_iban = sp_io::hashing::blake2_256(iban.clone().into_inner().as_ref());
@@ -608,12 +2201,142 @@ impl<T: Config> Pallet<T> {
 
 			// Map new account id to IBAN
 			Accounts::<T>::insert(&new_account_id, bank_account);
+			IbanToAccount::<T>::insert(iban, &new_account_id);
 
 			new_account_id
 		}
 	}
 }
 
+/// Functions used by the runtime API
+impl<T: Config> Pallet<T> {
+	/// IBAN linked to the account
+	pub fn iban_of(who: &T::AccountId) -> Option<IbanOf<T>> {
+		Accounts::<T>::get(who).map(|bank_account| bank_account.iban)
+	}
+
+	/// Burn requests of the account whose funds are still held in escrow
+	pub fn pending_burn_requests_of(who: &T::AccountId) -> Vec<BurnRequestOf<T>> {
+		let mut requests = BurnRequests::<T>::iter_values()
+			.filter(|request| &request.burner_account == who)
+			.collect::<Vec<_>>();
+
+		requests.sort_by_key(|request| request.id);
+		requests
+	}
+
+	/// Statement batches that are waiting for verification
+	pub fn queued_statements_status() -> Vec<QueuedStatementsStatusOf<T>> {
+		let mut batches = QueuedStatements::<T>::iter()
+			.map(|(batch_id, info)| QueuedStatementsStatus {
+				batch_id,
+				receipt_url: info.receipt_url.into_inner(),
+				statements: info.statements.len() as u32,
+				transactions: info
+					.statements
+					.iter()
+					.map(|(_, transactions)| transactions.len() as u32)
+					.sum(),
+				queued_at: info.block_number,
+				expires_at: info.block_number.saturating_add(T::StatementsExpiry::get()),
+			})
+			.collect::<Vec<_>>();
+
+		batches.sort_by_key(|batch| batch.batch_id);
+		batches
+	}
+}
+
+/// Functions related to fiat currencies
+impl<T: Config> Pallet<T> {
+	/// Returns the on-chain currency of an ISO 4217 currency code
+	fn currency_id(currency: &CurrencyCode) -> Result<CurrencyId<AssetIdOf<T>>, DispatchError> {
+		T::CurrencyFor::convert(*currency).ok_or_else(|| Error::<T>::UnsupportedCurrency.into())
+	}
+
+	/// Returns the currency code if it is mapped to an on-chain currency
+	fn supported_currency(currency: &[u8]) -> Option<CurrencyCode> {
+		let currency = CurrencyCode::try_from(currency).ok()?;
+		T::CurrencyFor::convert(currency).map(|_| currency)
+	}
+
+	/// Free balance of the account in the given currency
+	fn free_balance(
+		currency: &CurrencyCode,
+		who: &T::AccountId,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		Ok(match Self::currency_id(currency)? {
+			CurrencyId::Native => T::Currency::free_balance(who),
+			CurrencyId::Asset(asset) => <T::Assets as fungibles::Inspect<_>>::balance(asset, who),
+		})
+	}
+
+	/// Transfers funds between two accounts in the given currency
+	fn transfer_funds(
+		currency: &CurrencyCode,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: BalanceOf<T>,
+	) -> DispatchResult {
+		match Self::currency_id(currency)? {
+			CurrencyId::Native =>
+				T::Currency::transfer(from, to, amount, ExistenceRequirement::AllowDeath),
+			CurrencyId::Asset(asset) => <T::Assets as fungibles::Mutate<_>>::transfer(
+				asset,
+				from,
+				to,
+				amount,
+				Preservation::Expendable,
+			)
+			.map(|_| ()),
+		}
+	}
+
+	/// Mints new funds to the account in the given currency
+	fn mint(currency: &CurrencyCode, who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+		match Self::currency_id(currency)? {
+			CurrencyId::Native => {
+				// Returns negative imbalance
+				let mint = T::Currency::issue(amount);
+
+				// deposit negative imbalance into the account
+				T::Currency::resolve_creating(who, mint);
+
+				Ok(())
+			},
+			CurrencyId::Asset(asset) =>
+				<T::Assets as fungibles::Mutate<_>>::mint_into(asset, who, amount).map(|_| ()),
+		}
+	}
+
+	/// Burns funds of the account in the given currency
+	fn burn(currency: &CurrencyCode, who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+		match Self::currency_id(currency)? {
+			CurrencyId::Native => {
+				// Returns negative imbalance
+				let burn = T::Currency::burn(amount);
+
+				// Burn negative imbalance from the account
+				T::Currency::settle(
+					who,
+					burn,
+					WithdrawReasons::TRANSFER,
+					ExistenceRequirement::AllowDeath,
+				)
+				.map_err(|_| Error::<T>::InsufficientBalance.into())
+			},
+			CurrencyId::Asset(asset) => <T::Assets as fungibles::Mutate<_>>::burn_from(
+				asset,
+				who,
+				amount,
+				Precision::Exact,
+				Fortitude::Polite,
+			)
+			.map(|_| ()),
+		}
+	}
+}
+
 /// Functions related to statement processing
 impl<T: Config> Pallet<T> {
 	/// Process a single transaction
@@ -626,6 +2349,7 @@ impl<T: Config> Pallet<T> {
 	/// - `dest`: Destination/receiver of the transaction
 	/// - `transaction`: Transaction data
 	/// - `reference`: Optional reference field (usually contains burn request id)
+	/// - `currency`: Currency of the transaction, must be supported
 	fn process_transaction(
 		statement_owner: &AccountIdOf<T>,
 		statement_iban: &IbanOf<T>,
@@ -633,6 +2357,7 @@ impl<T: Config> Pallet<T> {
 		dest: Option<T::AccountId>,
 		transaction: &TransactionOf<T>,
 		reference: Option<u64>,
+		currency: CurrencyCode,
 	) -> DispatchResult {
 		let amount: BalanceOf<T> = BalanceOf::<T>::try_from(transaction.amount).unwrap_or_default();
 
@@ -641,135 +2366,69 @@ impl<T: Config> Pallet<T> {
 			TransactionType::Incoming => {
 				log::debug!("[OCW] Incoming transaction {:?}", &transaction);
 				if let Some(sender) = source {
-					T::Currency::transfer(
-						&sender,
-						statement_owner,
-						amount,
-						ExistenceRequirement::AllowDeath,
-					)?;
+					Self::transfer_funds(&currency, &sender, statement_owner, amount)?;
 				} else {
 					// Sender is not on-chain, therefore we simply mint to statement owner
 					log::info!("[OCW] Mint to {:?} {:?}", statement_owner, amount.clone());
 
-					// Returns negative imbalance
-					let mint = T::Currency::issue(amount);
+					ensure!(
+						!PausedMinting::<T>::contains_key(statement_iban),
+						Error::<T>::MintingPaused
+					);
 
-					// deposit negative imbalance into the account
-					T::Currency::resolve_creating(statement_owner, mint);
+					Self::mint(&currency, statement_owner, amount)?;
 
 					Self::deposit_event(Event::Minted {
 						who: statement_owner.clone(),
 						iban: statement_iban.clone(),
 						amount,
+						currency,
 					});
 				}
 			},
 			TransactionType::Outgoing => {
 				log::debug!("[OCW] Outgoing transaction {:?}", &transaction);
 
-				let pallet_account = Self::account_id();
-				let burn_request = reference.and_then(BurnRequests::<T>::take);
-
-				if dest.is_some() {
-					// Here we get the actual sender and receiver of the transaction
-					// If user has submitted burn request, his funds are stored in the pallet's account
-					// and if we detect that the reference field is populated with a burn request id,
-					// we can transfer the funds from the pallet's account to the specified destination
-					// account specified in the burn request.
-					//
-					// Otherwise, we simply transfer the funds from the statement owner to the receiver
-					if let Some(request) = burn_request {
-						match (
-							Self::get_account_id(&request.burner),
-							Self::get_account_id(&request.dest_iban),
-						) {
-							(Some(from), Some(to)) => {
-								if from == to {
-									// user is burning funds to himself, meaning he is withdrawing from ihis bank account
-
-									// Returns negative imbalance
-									let burn = T::Currency::burn(amount);
-									// Burn negative imbalance from the account
-									if T::Currency::settle(
-										&pallet_account,
-										burn,
-										WithdrawReasons::TRANSFER,
-										ExistenceRequirement::AllowDeath,
-									)
-									.is_ok()
-									{
-										Self::deposit_event(Event::Burned {
-											who: pallet_account,
-											iban: transaction.iban.clone(),
-											amount,
-										});
-									}
-								} else {
-									// both sides are on-chain, so we can simply transfer the funds
-									T::Currency::transfer(
-										&from,
-										&to,
-										amount,
-										ExistenceRequirement::AllowDeath,
-									)?;
-								}
-							},
-							(Some(_account), None) => {
-								// user is sending to an unknown account, so we burn the funds
-								// Returns negative imbalance
-								let burn = T::Currency::burn(amount);
-								// Burn negative imbalance from the account
-								if T::Currency::settle(
-									&pallet_account,
-									burn,
-									WithdrawReasons::TRANSFER,
-									ExistenceRequirement::AllowDeath,
-								)
-								.is_ok()
-								{
-									Self::deposit_event(Event::Burned {
-										who: pallet_account,
-										iban: transaction.iban.clone(),
-										amount,
-									});
-								}
-							},
-							_ => {},
-						}
-					} else {
-						// Receiver is on-chain, therefore we simply transfer from statement owner
-						T::Currency::transfer(
-							statement_owner,
-							&dest.unwrap(),
-							amount,
-							ExistenceRequirement::AllowDeath,
-						)?;
+				let burn_request = reference.and_then(|request_id| {
+					Self::matching_burn_request(request_id, statement_iban, amount, currency)
+						.map(|request| (request_id, request))
+				});
+
+				if let Some((request_id, request)) = burn_request {
+					// Request is only confirmed once its funds have left the pallet's account,
+					// otherwise it keeps its status and the transaction can be processed again
+					if let Err(error) = Self::settle_burn_request(&request) {
+						log::error!(
+							"[OCW] Settlement of burn request {} failed: {:?}",
+							request_id,
+							error
+						);
+						Self::deposit_event(Event::BurnRequestSettlementFailed {
+							request_id,
+							error,
+						});
+						return Err(error)
 					}
+
+					BurnRequests::<T>::remove(request_id);
+					Self::release_escrow(&request);
+					Self::deposit_event(Event::BurnRequestStatusChanged {
+						request_id,
+						status: BurnRequestStatus::Confirmed,
+					});
+				} else if let Some(to) = dest {
+					// Receiver is on-chain, therefore we simply transfer from statement owner
+					Self::transfer_funds(&currency, statement_owner, &to, amount)?;
 				} else {
 					// Receiver is not on-chain, therefore we simply burn from statement owner
-					log::info!("[OCW] Burn from {:?} {:?}", pallet_account, amount.clone());
-					let burn_from = if burn_request.is_some() {
-						pallet_account.clone()
-					} else {
-						statement_owner.clone()
-					};
-
-					// Returns negative imbalance
-					let burn = T::Currency::burn(amount);
+					log::info!("[OCW] Burn from {:?} {:?}", statement_owner, amount.clone());
 
-					// Burn negative imbalance from the account
-					if T::Currency::settle(
-						&burn_from,
-						burn,
-						WithdrawReasons::TRANSFER,
-						ExistenceRequirement::AllowDeath,
-					)
-					.is_ok()
-					{
+					if Self::burn(&currency, statement_owner, amount).is_ok() {
 						Self::deposit_event(Event::Burned {
-							who: burn_from,
+							who: statement_owner.clone(),
 							iban: transaction.iban.clone(),
 							amount,
+							currency,
 						});
 					}
 				}
@@ -780,6 +2439,63 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
+	/// Returns the burn request that is confirmed by an outgoing transaction
+	///
+	/// The request is only confirmed if it was sent to the EBICS service and the transaction
+	/// debits its amount in its currency from the burner's IBAN.
+	fn matching_burn_request(
+		request_id: u64,
+		statement_iban: &IbanOf<T>,
+		amount: BalanceOf<T>,
+		currency: CurrencyCode,
+	) -> Option<BurnRequestOf<T>> {
+		let request = BurnRequests::<T>::get(request_id)?;
+
+		let sent = matches!(
+			request.status,
+			BurnRequestStatus::Submitted | BurnRequestStatus::Unknown { .. }
+		);
+		if !sent ||
+			request.burner != *statement_iban ||
+			request.amount != amount ||
+			request.currency != currency
+		{
+			log::warn!(
+				"[OCW] Outgoing transaction does not match burn request {}: {:?}",
+				request_id,
+				request
+			);
+			return None
+		}
+
+		Some(request)
+	}
+
+	/// Moves the escrowed funds of a confirmed burn request out of the pallet's account
+	///
+	/// Funds are transferred to the on-chain owner of the destination IBAN, or burned if the
+	/// burner withdraws to their own bank account or the destination is not on-chain.
+	fn settle_burn_request(request: &BurnRequestOf<T>) -> DispatchResult {
+		let pallet_account = Self::account_id();
+
+		match Self::get_account_id(&request.dest_iban).filter(|to| *to != request.burner_account) {
+			Some(to) =>
+				Self::transfer_funds(&request.currency, &pallet_account, &to, request.amount),
+			None => {
+				Self::burn(&request.currency, &pallet_account, request.amount)?;
+
+				Self::deposit_event(Event::Burned {
+					who: pallet_account,
+					iban: request.dest_iban.clone(),
+					amount: request.amount,
+					currency: request.currency,
+				});
+
+				Ok(())
+			},
+		}
+	}
+
 	/// Process list of transactions for a given iban account
 	///
 	/// # Arguments
@@ -799,53 +2515,79 @@ impl<T: Config> Pallet<T> {
 		// Iterate over all transactions, with index
 
 		for (i, transaction) in transactions.iter().enumerate() {
-			// decode destination account id from reference
-			let reference_str = core::str::from_utf8(&transaction.reference).unwrap_or("default");
+			let transaction_id = transaction.id();
+
+			// Transaction might have been applied already with an overlapping statement
+			if ProcessedTransactions::<T>::contains_key(&iban_account.iban, transaction_id) {
+				log::info!("[OCW] Skipping duplicate transaction {}", i);
+				Self::deposit_event(Event::DuplicateTransactionSkipped {
+					iban: iban_account.iban.clone(),
+					transaction_id,
+				});
+				continue
+			}
+
+			// Incoming transaction might be the deposit that confirms an IBAN link, whatever
+			// its reference looks like otherwise
+			let linked = transaction.tx_type == TransactionType::Incoming &&
+				Self::try_activate_link(transaction);
+
+			// Transactions in currencies without on-chain representation can not be processed
+			let currency = match Self::supported_currency(&transaction.currency) {
+				Some(currency) => currency,
+				None => {
+					log::error!("[OCW] Unsupported currency of transaction {}", i);
+					failed_transactions.push(i as u32);
+					continue
+				},
+			};
 
-			// Format of the reference is the following:
-			// Purpose:AccountId; ourReference:nonce(of burn request)
-			// E.g, "Purp:5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty; ourRef:12",
-			let reference_decoded: Vec<&str> = reference_str.split(';').collect();
+			// decode destination account id and burn request id from reference
+			let reference = match ReferenceOf::<T>::try_from(&transaction.reference[..]) {
+				Ok(reference) => reference,
+				Err(e) => {
+					log::error!("[OCW] Malformed reference of transaction {}: {:?}", i, e);
+					failed_transactions.push(i as u32);
+					continue
+				},
+			};
 
-			log::info!("[OCW] Purpose: {}", reference_decoded[0]);
-			log::info!("[OCW] Reference: {}", reference_decoded[1]);
+			log::info!("[OCW] Reference: {:?}", reference);
 
-			// Source (initiator) of the transaction
+			// Source (initiator) of the transaction, the deposit of an IBAN link was sent before
+			// the IBAN was linked
 			let source: Option<AccountIdOf<T>> = match transaction.tx_type {
+				TransactionType::Incoming if linked => None,
 				TransactionType::Incoming => Self::get_account_id(&transaction.iban),
 				TransactionType::Outgoing => Self::get_account_id(&iban_account.iban),
 				_ => None,
 			};
 
 			// Destination (recipient) of the transaction
-			let dest = match AccountId32::from_ss58check(&reference_decoded[0][5..]) {
-				Ok(dest) => Some(
-					<T::AccountId>::decode(&mut &dest.encode()[..]).expect("Should not fail; qed"),
-				),
-				Err(_e) => {
-					log::error!("[OCW] Failed to decode destination account from reference");
-					match transaction.tx_type {
-						TransactionType::Incoming => Self::get_account_id(&iban_account.iban),
-						TransactionType::Outgoing => Self::get_account_id(&transaction.iban),
-						_ => None,
-					}
+			// If reference does not contain it, we look for the iban in the pallet storage
+			let dest = match reference.dest {
+				Some(dest) => Some(dest),
+				None => match transaction.tx_type {
+					TransactionType::Incoming => Self::get_account_id(&iban_account.iban),
+					TransactionType::Outgoing => Self::get_account_id(&transaction.iban),
+					_ => None,
 				},
 			};
 
 			// Proces transaction based on the value of reference
-			// If decoding returns error, we look for the iban in the pallet storage
-			let reference = reference_decoded[1][8..].parse::<u64>().ok();
-
 			if Self::process_transaction(
 				&statement_owner,
 				&iban_account.iban,
 				source,
 				dest,
 				transaction,
-				reference,
+				reference.burn_request_id,
+				currency,
 			)
-			.is_err()
+			.is_ok()
 			{
+				Self::mark_transaction_processed(&iban_account.iban, transaction_id);
+			} else {
 				failed_transactions.push(i as u32);
 			}
 		}
@@ -859,209 +2601,513 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
-	/// Send unpeq request to the remote endpoint
-	/// Populates the unpeg request and sends it
+	/// Remembers a processed transaction for `ProcessedTransactionsExpiry` blocks
+	fn mark_transaction_processed(iban: &IbanOf<T>, transaction_id: [u8; 32]) {
+		let expires_at = frame_system::Pallet::<T>::block_number()
+			.saturating_add(T::ProcessedTransactionsExpiry::get());
+
+		ProcessedTransactions::<T>::insert(iban, transaction_id, expires_at);
+		ProcessedTransactionsExpiring::<T>::append(expires_at, (iban.clone(), transaction_id));
+	}
+
+	/// Compares the closing balance of a statement with the funds on chain
+	///
+	/// Funds on chain are the balance of the linked account in the currency of the statement
+	/// and the funds of its burn requests in that currency that are held in escrow. The
+	/// currency is the one reported by the statement, otherwise the one of its transactions or
+	/// of the previous statement. Mismatch is reported and pauses minting for the IBAN if
+	/// `PauseMintingOnMismatch` is set.
+	///
+	/// # Arguments
+	///
+	/// `iban_account`: bank account of the statement, with its closing balance
+	/// `transactions`: transactions of the statement
+	fn reconcile_balance(iban_account: &BankAccountOf<T>, transactions: &[TransactionOf<T>]) {
+		let owner = match Self::get_account_id(&iban_account.iban) {
+			Some(owner) => owner,
+			None => return,
+		};
+
+		// Keep the balances, currency and closing date of the last statement
+		let currency = Accounts::<T>::mutate(&owner, |account| {
+			let account = account.as_mut()?;
+			account.opening_balance = iban_account.opening_balance;
+			account.balance = iban_account.balance;
+			account.last_updated = iban_account.last_updated;
+			account.currency = iban_account
+				.currency
+				.or_else(|| {
+					transactions.iter().find_map(|tx| Self::supported_currency(&tx.currency))
+				})
+				.or(account.currency);
+			account.currency
+		});
+
+		let free_balance = currency
+			.and_then(|currency| Some((currency, Self::free_balance(&currency, &owner).ok()?)));
+		let (currency, free_balance) = match free_balance {
+			Some(free_balance) => free_balance,
+			None => {
+				log::warn!("[OCW] Currency of {:?} is unknown, not reconciled", iban_account.iban);
+				return
+			},
+		};
+
+		let chain: u128 = free_balance
+			.saturating_add(EscrowedFunds::<T>::get(&owner, currency))
+			.saturated_into();
+		if chain == iban_account.balance {
+			return
+		}
+
+		log::warn!(
+			"[OCW] Balance mismatch of {:?}: bank {}, chain {}",
+			iban_account.iban,
+			iban_account.balance,
+			chain
+		);
+
+		if T::PauseMintingOnMismatch::get() {
+			PausedMinting::<T>::insert(
+				&iban_account.iban,
+				frame_system::Pallet::<T>::block_number(),
+			);
+		}
+
+		Self::deposit_event(Event::ReconciliationMismatch {
+			iban: iban_account.iban.clone(),
+			bank: iban_account.balance,
+			chain,
+		});
+	}
+
+	/// Send a batch of unpeq requests to the remote endpoint
+	///
+	/// Depending on `UnpegFormat`, every request is sent as JSON unpeg request on its own, or
+	/// the batch is sent at once as JSON array of unpeg requests or as pain.001 message with a
+	/// credit transfer per request. Requests sent on their own are sent concurrently and their
+	/// responses are awaited until a single deadline. Requests whose amount can not be represented in the format
+	/// are rejected without being sent. If the service refuses a call with a client error, all
+	/// its requests are rejected. If the call times out or the response can not be read, the
+	/// outcome of all its requests is unknown.
 	///
 	/// Note: This function is not called from the runtime, but from the OCW module
 	///
 	/// ### Arguments
-	/// * `request_id` - id of the request to send
-	/// * `burner` - AccountId of the burner, used to populate `purpose` field
-	/// * `dest_iban` - IBAN destination
-	/// * `amount` - Amount to be burned
-	fn unpeg(
-		request_id: u64,
-		burner: Option<AccountIdOf<T>>,
-		dest_iban: IbanOf<T>,
-		amount: BalanceOf<T>,
-	) -> Result<(), &'static str> {
-		let remote_url = ApiUrl::<T>::get();
-
-		let remote_url_str = core::str::from_utf8(&remote_url[..])
-			.map_err(|_| "Error in converting remote_url to string")?;
-
-		// add /unpeg to the url
-		let remote_url_str = format!("{}/api-v1/unpeg", remote_url_str);
-
-		let amount_u128 = amount.saturated_into::<u128>();
-
-		// In the reference field, we save the request id (nonce)
-		let reference = format!("{}", request_id);
-
-		// Send request to remote endpoint
-		let body = unpeg_request::<T>(
-			&format!(
-				"{:?}",
-				AccountId32::try_from(burner.unwrap_or_else(Self::account_id).encode().as_ref())
-					.expect("Should not fail")
-					.to_ss58check()
-			),
-			amount_u128,
-			&dest_iban,
-			&reference,
-		)
-		.serialize();
+	/// * `batch` - ids and burn requests to send
+	///
+	/// Returns the outcome of each request of the batch
+	fn unpeg(batch: &[(u64, BurnRequestOf<T>)]) -> Vec<(u64, UnpegOutcome)> {
+		let format = UnpegFormat::<T>::get();
+		let endpoint = format.endpoint();
+
+		let mut results = Vec::new();
+		let mut sent = Vec::new();
+
+		for (request_id, burn_request) in batch {
+			let amount = burn_request.amount.saturated_into::<u128>();
+			let convertible = match format {
+				PaymentFormat::Json | PaymentFormat::JsonBatch => i128::try_from(amount)
+					.map_err(|_| AmountError::Overflow)
+					.and_then(|amount| to_json_number(amount, T::FiatDecimals::get()))
+					.is_ok(),
+				PaymentFormat::Pain001 => iso_amount(amount, T::FiatDecimals::get()).is_ok(),
+			};
 
-		log::info!("[OCW] Sending unpeg request to {}", remote_url_str);
+			if convertible {
+				sent.push((request_id, burn_request));
+			} else {
+				log::error!("[OCW] Amount of burn request {} can not be sent", request_id);
+				results.push((*request_id, UnpegOutcome::Rejected));
+			}
+		}
 
-		let post_request = rt_offchain::http::Request::new(&remote_url_str)
-			.method(rt_offchain::http::Method::Post)
-			.body(vec![body])
-			.add_header("Content-Type", "application/json")
-			.add_header("accept", "*/*")
-			.send()
-			.map_err(|_| "Error in sending http POST request")?;
+		if sent.is_empty() {
+			return results
+		}
 
-		log::info!("[OCW] Request sent to {}", remote_url_str);
+		// Owners of the destination IBANs, used to populate `purpose` field
+		let dests = sent
+			.iter()
+			.map(|(_, burn_request)| {
+				AccountId32::try_from(
+					Self::get_account_id(&burn_request.dest_iban)
+						.unwrap_or_else(Self::account_id)
+						.encode()
+						.as_ref(),
+				)
+				.expect("Should not fail")
+				.to_ss58check()
+			})
+			.collect::<Vec<_>>();
+
+		let request_ids = sent.iter().map(|(request_id, _)| **request_id).collect::<Vec<_>>();
+
+		// Calls to the endpoint, with the ids of the requests they carry
+		let calls = match format {
+			PaymentFormat::Json | PaymentFormat::JsonBatch => sent
+				.iter()
+				.zip(&dests)
+				.map(|((request_id, burn_request), dest)| {
+					unpeg_request::<T>(
+						&format!("{:?}", dest),
+						burn_request.amount.saturated_into::<u128>(),
+						&burn_request.currency,
+						&burn_request.dest_iban,
+						&burn_request.beneficiary,
+						// In the reference field, we save the request id (nonce)
+						&format!("{}", request_id),
+					)
+				})
+				.collect::<Result<Vec<_>, _>>()
+				.map(|requests| match format {
+					PaymentFormat::JsonBatch =>
+						vec![(request_ids.clone(), JsonValue::Array(requests).serialize())],
+					_ => request_ids
+						.iter()
+						.zip(requests)
+						.map(|(request_id, request)| (vec![*request_id], request.serialize()))
+						.collect(),
+				}),
+			PaymentFormat::Pain001 => {
+				let debtors = sent
+					.iter()
+					.map(|(_, burn_request)| {
+						Accounts::<T>::get(&burn_request.burner_account)
+							.and_then(|account| account.beneficiary)
+					})
+					.collect::<Vec<_>>();
+				let transfers = sent
+					.iter()
+					.zip(&debtors)
+					.zip(&dests)
+					.map(|(((_, burn_request), debtor), dest)| CreditTransfer {
+						request: burn_request,
+						debtor: debtor.as_ref(),
+						purpose: dest,
+					})
+					.collect::<Vec<_>>();
+
+				pain001_request::<T>(
+					&pain001_message_id(frame_system::Pallet::<T>::block_number(), &request_ids),
+					T::TimeProvider::now().as_millis().saturated_into(),
+					&transfers,
+				)
+				.map(|body| vec![(request_ids.clone(), body)])
+			},
+		}
+		.map_err(|e| log::error!("[OCW] Error in converting amount {:?}", e));
+
+		// Requests that can not be serialized are not sent
+		let calls = match calls {
+			Ok(calls) => calls,
+			Err(_) => {
+				let rejected =
+					request_ids.iter().map(|request_id| (*request_id, UnpegOutcome::Rejected));
+				results.extend(rejected);
+				return results
+			},
+		};
 
-		let response = post_request.wait().map_err(|_| "Error in waiting http response back")?;
+		log::info!("[OCW] Sending {} unpeg requests to {}", request_ids.len(), endpoint);
+
+		// Calls are sent at once and share a deadline, so the run waits for a single timeout
+		let (call_request_ids, bodies): (Vec<_>, Vec<_>) = calls.into_iter().unzip();
+		let responses = http::post_all::<T>(endpoint, format.content_type(), bodies);
+
+		for (request_ids, response) in call_request_ids.into_iter().zip(responses) {
+			let reported = match (format, response) {
+				(_, Err(e)) => Err(e),
+				// EBICS service reports the result of every request of the batch
+				(PaymentFormat::JsonBatch, Ok(response)) => http::parse(&response)
+					.map(|response| parse_unpeg_results(&response))
+					.map_err(|e| http::record_error(endpoint, e)),
+				// Request is accepted, or the upload of the message for all transfers at once
+				(PaymentFormat::Json | PaymentFormat::Pain001, Ok(_)) =>
+					Ok(request_ids.iter().map(|request_id| (*request_id, true)).collect()),
+			};
 
-		log::info!("[OCW] Unpeg response received {:?}", response.code);
+			let outcome = |request_id: u64| match &reported {
+				Ok(reported) if reported.contains(&(request_id, true)) => UnpegOutcome::Accepted,
+				Ok(reported) if reported.contains(&(request_id, false)) => UnpegOutcome::Rejected,
+				// Service refused the whole call, none of its requests was executed
+				Err(OcwError::HttpStatus(400..=499)) => UnpegOutcome::Rejected,
+				// Request might have been executed without a response, or without reporting it
+				_ => UnpegOutcome::Unknown,
+			};
 
-		if response.code != 200 {
-			return Err("Error in unpeg response")
+			let outcomes = request_ids.iter().map(|request_id| (*request_id, outcome(*request_id)));
+			results.extend(outcomes);
 		}
 
-		Ok(())
+		results
 	}
 
 	/// Process burn requets
 	///
-	/// Processes registered burn requests, by sending http call to `unpeg` endpoint
+	/// Burn requests are sent in two steps, so that they can not be cancelled while the EBICS
+	/// service might execute them. Due requests, i.e pending ones and failed ones whose backoff
+	/// has elapsed, are claimed with `claim_unpeg_requests` first, oldest first and at most
+	/// `MaxUnpegBatch` in flight. Requests in flight that were claimed by this node are then
+	/// sent to the `unpeg` endpoint in a single batch and their outcomes are reported with
+	/// `report_unpeg_results`. A claimed request is sent only once.
 	fn process_burn_requests() -> Result<(), &'static str> {
+		// Only the unpeg authority sends unpeg requests, so that transfers are not duplicated
+		let authority = Self::unpeg_authority().into_iter().collect::<Vec<_>>();
+		let signer =
+			Signer::<T, T::AuthorityId>::all_accounts().with_filter(Self::local_keys(&authority));
+
+		if !signer.can_sign() {
+			log::info!("[OCW] Burn requests are sent by the unpeg authority only");
+			return Ok(())
+		}
+
+		let block_number = frame_system::Pallet::<T>::block_number();
+		let retry_delay = T::UnpegRetryDelay::get();
+		let mut in_flight = 0usize;
+		let mut to_claim = vec![];
+		let mut to_send = vec![];
+		let mut to_report = vec![];
+
 		for (request_id, burn_request) in <BurnRequests<T>>::iter() {
 			// This is a default value, should not be processed
 			if burn_request.burner == IbanOf::<T>::default() {
-				return Ok(())
+				continue
 			}
 
-			// Process burn requests that are either not processed yet or failed
-			let dest_account = Self::get_account_id(&burn_request.dest_iban);
+			// Attempt claimed by this node and the block of the claim, the claim might not be
+			// included yet
+			let (claimed, claimed_at) =
+				StorageValueRef::persistent(&Self::unpeg_claim_key(request_id))
+					.get::<(u32, BlockNumberFor<T>)>()
+					.ok()
+					.flatten()
+					.unwrap_or_default();
+
+			match burn_request.status {
+				BurnRequestStatus::InFlight { attempts } => {
+					in_flight += 1;
 
-			// send the unpeg request
-			match Self::unpeg(request_id, dest_account, burn_request.dest_iban, burn_request.amount)
-			{
-				Ok(_) => {
-					log::info!("[OCW] Unpeq request successfull");
-					BurnRequests::<T>::remove(request_id);
+					// Requests claimed by another node are never sent by this one
+					if claimed != attempts.saturating_add(1) {
+						continue
+					}
+
+					// Attempt sent by this node, the block it was sent at and its outcome
+					let sent = StorageValueRef::persistent(&Self::unpeg_sent_key(request_id))
+						.get::<(u32, BlockNumberFor<T>, Option<UnpegOutcome>)>()
+						.ok()
+						.flatten()
+						.filter(|(sent, _, _)| *sent == claimed);
+
+					match sent {
+						None => to_send.push((request_id, burn_request, claimed)),
+						// Report again if the report got lost. Without an outcome the node
+						// stopped while sending, so the outcome is unknown
+						Some((_, sent_at, outcome))
+							if sent_at.saturating_add(retry_delay) <= block_number =>
+							to_report.push((request_id, outcome.unwrap_or(UnpegOutcome::Unknown))),
+						_ => {},
+					}
 				},
-				Err(e) => {
-					log::info!("[OCW] Unpeq request failed {}", e);
-					BurnRequests::<T>::remove(request_id);
+				_ if Self::is_unpeg_due(&burn_request, block_number) => {
+					let attempts = match burn_request.status {
+						BurnRequestStatus::Failed { attempts } => attempts,
+						_ => 0,
+					};
+
+					// Wait for the previous claim to be included, unless it got lost
+					if claimed > attempts && claimed_at.saturating_add(retry_delay) > block_number {
+						continue
+					}
+
+					to_claim.push((request_id, attempts));
 				},
-			};
+				_ => {},
+			}
 		}
 
-		Ok(())
-	}
-}
+		if !to_send.is_empty() {
+			to_send.sort_by_key(|(request_id, _, _)| *request_id);
 
-/// Functions related to the offchain worker communication
-impl<T: Config> Pallet<T> {
-	/// Fetch json from the Ebics Service API
-	/// Return parsed json file
-	fn fetch_json(endpoint: &str) -> Result<JsonValue, &'static str> {
-		// fetch json value
-		let remote_url = ApiUrl::<T>::get();
-		let remote_url_str = core::str::from_utf8(remote_url.as_bytes_ref())
-			.map_err(|_| "Error in converting remote_url to string")?;
+			// Recorded before sending, so that the requests are not sent again
+			for (request_id, _, attempt) in &to_send {
+				let sent_key = Self::unpeg_sent_key(*request_id);
+				let outcome: Option<UnpegOutcome> = None;
+				StorageValueRef::persistent(&sent_key).set(&(*attempt, block_number, outcome));
+			}
 
-		let final_url = format!("{}/{}", remote_url_str, endpoint);
-		let pending = rt_offchain::http::Request::get(&final_url)
-			.send()
-			.map_err(|_| "Error in sending http GET request")?;
+			let batch = to_send
+				.iter()
+				.map(|(request_id, burn_request, _)| (*request_id, burn_request.clone()))
+				.collect::<Vec<_>>();
+
+			// send the unpeg requests
+			let unpeg_results = Self::unpeg(&batch);
+			log::info!("[OCW] Unpeq batch sent: {:?}", unpeg_results);
+
+			for (request_id, _, attempt) in &to_send {
+				let sent_key = Self::unpeg_sent_key(*request_id);
+				let outcome = unpeg_results
+					.iter()
+					.find(|(id, _)| id == request_id)
+					.map(|(_, outcome)| *outcome);
+				StorageValueRef::persistent(&sent_key).set(&(*attempt, block_number, outcome));
+			}
 
-		let response = pending.wait().map_err(|_| "Error in waiting http response back")?;
+			to_report.extend(unpeg_results);
+		}
 
-		if response.code != 200 {
-			// runtime_print!("Unexpected status code: {}", response.code);
-			return Ok(JsonValue::Null)
+		if !to_report.is_empty() {
+			let results = to_report;
+			Self::send_unpeg_transaction(&signer, Call::report_unpeg_results { results })?;
 		}
 
-		let json_result: Vec<u8> = response.body().collect::<Vec<u8>>();
+		to_claim.sort_by_key(|(request_id, _)| *request_id);
+		to_claim.truncate((T::MaxUnpegBatch::get() as usize).saturating_sub(in_flight));
 
-		let json_str: &str = match core::str::from_utf8(&json_result) {
-			Ok(v) => v,
-			Err(_e) => "Error parsing json",
-		};
+		if !to_claim.is_empty() {
+			for (request_id, attempts) in &to_claim {
+				StorageValueRef::persistent(&Self::unpeg_claim_key(*request_id))
+					.set(&(attempts.saturating_add(1), block_number));
+			}
+
+			let request_ids = to_claim.into_iter().map(|(request_id, _)| request_id).collect();
+			Self::send_unpeg_transaction(&signer, Call::claim_unpeg_requests { request_ids })?;
+		}
+
+		Ok(())
+	}
+
+	/// Submits a transaction of the burn request flow signed by the unpeg authority
+	fn send_unpeg_transaction(
+		signer: &Signer<T, T::AuthorityId, ForAll>,
+		call: Call<T>,
+	) -> Result<(), &'static str> {
+		let results = signer.send_signed_transaction(|_account| call.clone());
+
+		for (acc, res) in &results {
+			if res.is_err() {
+				log::error!("[OCW] Failed to submit burn request transaction from: [{:?}]", acc.id);
+				return Err("Failed to submit transaction")
+			}
+		}
+
+		Ok(())
+	}
 
-		log::info!("[OCW] JSON received: {}", json_str);
+	/// Key of the offchain storage of the last unpeg attempt claimed by this node for a burn
+	/// request
+	fn unpeg_claim_key(request_id: u64) -> Vec<u8> {
+		(b"fiat_ramps::unpeg_claim", request_id).encode()
+	}
 
-		let json_val = parse_json(json_str).map_err(|_| "Error in parsing json")?;
+	/// Key of the offchain storage of the last unpeg attempt sent by this node for a burn
+	/// request
+	fn unpeg_sent_key(request_id: u64) -> Vec<u8> {
+		(b"fiat_ramps::unpeg_sent", request_id).encode()
+	}
+}
 
-		Ok(json_val)
+/// Functions related to the offchain worker communication
+impl<T: Config> Pallet<T> {
+	/// Keys of the local keystore that belong to the accounts
+	fn local_keys(accounts: &[T::AccountId]) -> Vec<T::Public> {
+		type Crypto<T> = <T as Config>::AuthorityId;
+		type Public<T> = <T as SigningTypes>::Public;
+		type Signature<T> = <T as SigningTypes>::Signature;
+
+		<Crypto<T> as AppCrypto<Public<T>, Signature<T>>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| <Crypto<T> as AppCrypto<Public<T>, Signature<T>>>::GenericPublic::from(key))
+			.map(|generic_public| -> T::Public { generic_public.into() })
+			.filter(|public| accounts.contains(&public.clone().into_account()))
+			.collect()
 	}
+
 	fn fetch_and_send_signed(ocw_activity: OcwActivity) -> Result<(), &'static str> {
 		log::info!("[OCW] Activity: {:?}", ocw_activity);
 
-		// get extrinsic signer
-		let signer = Signer::<T, T::AuthorityId>::all_accounts();
+		// get extrinsic signer, every local authority attests on its own
+		let signer = Signer::<T, T::AuthorityId>::all_accounts()
+			.with_filter(Self::local_keys(&Self::ocw_authorities()));
 
 		if !signer.can_sign() {
-			return Err("No local accounts available! Please, insert your keys!")
+			return Err("No local authority keys available! Please, insert your keys!")
 		}
 
-		let image_id = Risc0ImageId::<T>::get().ok_or("No image id set")?;
+		let image_ids = Self::active_image_ids();
+		if image_ids.is_empty() {
+			return Err("No active image id")
+		}
 
 		// Get statements from remote endpoint
 		let results = match ocw_activity {
 			OcwActivity::FetchStatements => {
-				let statements = Self::parse_statements()?;
+				let (statements, errors) = Self::parse_statements()?;
+				if !errors.is_empty() {
+					log::warn!(
+						"[OCW] {} statements could not be parsed, see `fiat_ramps::statement_errors`",
+						errors.len()
+					);
+				}
 				if statements.is_empty() {
 					return Ok(())
 				}
 
-				let receipt_url = statements.keys().next().expect("Should not be empty");
-				let statements = statements.values().cloned().collect::<Vec<(
-					BankAccountOf<T>,
-					BoundedVec<TransactionOf<T>, <T as Config>::MaxStatements>,
-				)>>();
-
-				Some(signer.send_signed_transaction(|_account| Call::queue_statements {
-					statements:
-						statements.clone().try_into().expect("convert to bounded vec, is fine"),
-					receipt_url: receipt_url.clone(),
-				}))
-			},
-			OcwActivity::VerifyAndProcessStatements => {
-				let receipt_url = QueuedStatements::<T>::get()
-					.ok_or("No queued statements")?
-					.receipt_url
-					.into_inner();
+				let mut results = vec![];
 
-				let receipt_url = core::str::from_utf8(&receipt_url[..])
-					.map_err(|_| "Error in converting receipt_url to string")?;
+				// Every receipt covers its own batch of statements
+				for (receipt_url, statements) in statements {
+					if Self::is_receipt_queued(&receipt_url) {
+						log::info!("[OCW] Statements for {:?} are already queued", receipt_url);
+						continue
+					}
 
-				let receipt = Self::fetch_json(receipt_url)?;
+					let statements: StatementsOf<T> = match statements.try_into() {
+						Ok(statements) => statements,
+						Err(_) => {
+							log::error!("[OCW] Too many statements for {:?}", receipt_url);
+							continue
+						},
+					};
 
-				if receipt.is_null() {
-					return Ok(())
+					results.extend(signer.send_signed_transaction(|_account| {
+						Call::queue_statements {
+							receipt_url: receipt_url.clone(),
+							statements: statements.clone(),
+						}
+					}));
 				}
 
-				let (receipt, _): (Receipt, _) = serde_json_core::from_slice(&receipt.serialize())
-					.map_err(|_| "Error in deserializing receipt")?;
-
-				match receipt.verify(image_id) {
-					Ok(_) =>
-						Some(signer.send_signed_transaction(|_account| Call::process_statements {})),
-					Err(e) =>
-						if matches!(receipt.inner, InnerReceipt::Fake { .. }) {
-							#[cfg(any(feature = "risc0-dev-mode", test))]
-							{
-								Some(signer.send_signed_transaction(|_account| {
-									Call::process_statements {}
-								}))
-							}
-							#[cfg(not(any(feature = "risc0-dev-mode", test)))]
-							{
-								log::error!("[OCW] Receipt verification failed {:?}", e);
-								None
-							}
-						} else {
-							log::error!("[OCW] Receipt verification failed {:?}", e);
-							None
+				Some(results)
+			},
+			OcwActivity::VerifyAndProcessStatements => {
+				let mut results = vec![];
+
+				// Each queued batch is verified and processed independently
+				for (batch_id, statements_info) in QueuedStatements::<T>::iter() {
+					match Self::verify_receipt(&statements_info.receipt_url, &image_ids) {
+						Ok(Some((image_id, journal, fake_receipt))) => results.extend(
+							signer.send_signed_transaction(|_account| Call::process_statements {
+								batch_id,
+								image_id,
+								journal: journal.clone(),
+								fake_receipt,
+							}),
+						),
+						Ok(None) => results.extend(
+							signer.send_signed_transaction(|_account| Call::reject_statements {
+								batch_id,
+							}),
+						),
+						Err(e) => {
+							log::error!("[OCW] Could not verify batch {}: {:?}", batch_id, e);
 						},
+					}
 				}
+
+				Some(results)
 			},
 			_ => None,
 		};
@@ -1088,59 +3134,166 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
+	/// Fetch the receipt of a statement batch and verify it against the image ids
+	///
+	/// Returns the matching image id, the journal of the receipt and whether it is a fake
+	/// receipt, or `Ok(None)` if the
+	/// receipt does not verify. Errors are returned only when the receipt could not be fetched,
+	/// so that the batch can be retried in the next cycle
+	fn verify_receipt(
+		receipt_url: &StringOf<T>,
+		image_ids: &[[u8; 32]],
+	) -> Result<Option<([u8; 32], Vec<u8>, bool)>, OcwError> {
+		let receipt_url =
+			core::str::from_utf8(&receipt_url[..]).map_err(|_| OcwError::InvalidUtf8)?;
+
+		let receipt = http::get_json::<T>(receipt_url)?;
+
+		let (receipt, _): (Receipt, _) = serde_json_core::from_slice(&receipt.serialize())
+			.map_err(|_| http::record_error(receipt_url, OcwError::Schema))?;
+
+		Ok(Self::check_receipt(&receipt, image_ids)
+			.map(|(image_id, fake_receipt)| (image_id, receipt.journal.bytes, fake_receipt)))
+	}
+
+	/// Verifies the receipt against the image ids and returns the one it verifies against,
+	/// and whether the receipt is a fake receipt
+	///
+	/// Fake receipts are accepted only while `FakeReceiptsAllowed` is set, for the first of the
+	/// image ids
+	fn check_receipt(receipt: &Receipt, image_ids: &[[u8; 32]]) -> Option<([u8; 32], bool)> {
+		if let Some(image_id) = image_ids.iter().find(|image_id| receipt.verify(**image_id).is_ok())
+		{
+			return Some((*image_id, false))
+		}
+
+		if matches!(receipt.inner, InnerReceipt::Fake { .. }) {
+			if Self::fake_receipts_allowed() {
+				return image_ids.first().map(|image_id| (*image_id, true))
+			}
+			log::error!("[OCW] Fake receipt refused, fake receipts are not allowed");
+			return None
+		}
+
+		log::error!("[OCW] Receipt does not verify against any of the image ids {:?}", image_ids);
+		None
+	}
+
 	/// Parse bank statements
 	///
-	/// Returns:
+	/// Statements are fetched in the format of `Config::StatementFormat`. Returns statements
+	/// grouped by their `receipt_url`, each statement consists of:
 	///
 	/// - `iban_account: IbanAccount` - IBAN account that owns the statement
-	/// - `incoming_txs: Vec<TransactionOf<T>>` - Incoming transactions in the statement
-	/// - `outgoing_txs: Vec<TransactionOf<T>>` - Outgoing transactions in the statement
+	/// - `transactions: Vec<TransactionOf<T>>` - Outgoing and incoming transactions in the
+	///   statement
+	///
+	/// Statements that can not be parsed are skipped and returned as errors, so that a single
+	/// malformed statement does not block the rest of the response.
 	fn parse_statements() -> Result<
-		BTreeMap<
-			StringOf<T>,
-			(BankAccountOf<T>, BoundedVec<TransactionOf<T>, <T as Config>::MaxStatements>),
-		>,
-		&'static str,
+		(
+			BTreeMap<
+				StringOf<T>,
+				Vec<(BankAccountOf<T>, BoundedVec<TransactionOf<T>, <T as Config>::MaxStatements>)>,
+			>,
+			Vec<StatementError>,
+		),
+		OcwError,
 	> {
-		let json = Self::fetch_json("api-v1/bankstatements")?;
+		let format = T::StatementFormat::get();
+		let endpoint = format.endpoint();
+
+		let statements: Vec<ParsedStatement<T>> = match format {
+			StatementFormat::Json => http::get_json::<T>(endpoint)?
+				.as_array()
+				.ok_or_else(|| http::record_error(endpoint, OcwError::Schema))?
+				.iter()
+				.map(Self::parse_statement)
+				.collect(),
+			StatementFormat::Camt053 | StatementFormat::Camt054 =>
+				match http::get::<T>(endpoint)? {
+					message if !message.is_empty() => {
+						let (message_id, statements) = camt::parse_message::<T>(&message)
+							.map_err(|e| {
+								log::error!("[OCW] Invalid camt message: {:?}", e);
+								http::record_error(endpoint, OcwError::Schema)
+							})?;
+
+						// Receipt of the statements is published under the message id
+						statements
+							.into_iter()
+							.map(|statement| {
+								statement.map(|(bank_account, transactions)| {
+									(message_id.clone(), bank_account, transactions)
+								})
+							})
+							.collect()
+					},
+					_ => Vec::new(),
+				},
+		};
 
-		let raw_array = json.as_array();
+		let mut balances = BTreeMap::new();
+		let mut errors = Vec::new();
 
-		if let Some(v) = raw_array {
-			let mut balances = BTreeMap::new();
+		for (index, statement) in statements.into_iter().enumerate() {
+			let statement = statement.and_then(|(receipt_url, bank_account, transactions)| {
+				let count = transactions.len() as u32;
+				let transactions = BoundedVec::try_from(transactions)
+					.map_err(|_| (None, ParseError::TooManyTransactions(count)))?;
 
-			for val in v.iter() {
-				let as_object = val.as_object().ok_or("Error in parsing json")?;
-				let receipt_url = extract_value::<Vec<u8>>("receiptUrl", as_object)
-					.try_into()
-					.unwrap_or_default();
+				Ok((receipt_url, bank_account, transactions))
+			});
 
-				// extract iban account
-				if let Ok(bank_account) = BankAccountOf::<T>::try_from(val) {
-					// extract transactions
-					let mut transactions =
-						TransactionOf::<T>::parse_transactions(val, TransactionType::Outgoing)
-							.unwrap_or_default();
-					let mut incoming_transactions =
-						TransactionOf::<T>::parse_transactions(val, TransactionType::Incoming)
-							.unwrap_or_default();
-
-					transactions.append(&mut incoming_transactions);
-
-					// try pushing to balances
-					balances.insert(
-						receipt_url,
-						(
-							bank_account,
-							transactions.try_into().expect("tx length should not exceed; qed"),
-						),
-					);
-				}
+			match statement {
+				Ok((receipt_url, bank_account, transactions)) => balances
+					.entry(receipt_url)
+					.or_insert_with(Vec::new)
+					.push((bank_account, transactions)),
+				Err((transaction, error)) => {
+					let error = StatementError { statement: index as u32, transaction, error };
+					log::error!("[OCW] Skipping statement: {:?}", error);
+					errors.push(error);
+				},
 			}
-			Ok(balances)
-		} else {
-			Ok(BTreeMap::new())
 		}
+
+		Self::record_statement_errors(&errors);
+
+		Ok((balances, errors))
+	}
+
+	/// Writes the statements that could not be parsed as the errors of the last response
+	///
+	/// Stored in the persistent offchain storage under `STATEMENT_ERRORS_KEY` together with
+	/// the unix timestamp in milliseconds, so that operators can inspect which statement and
+	/// field were skipped. A response without errors clears the list.
+	fn record_statement_errors(errors: &[StatementError]) {
+		StorageValueRef::persistent(STATEMENT_ERRORS_KEY)
+			.set(&(sp_io::offchain::timestamp().unix_millis(), errors));
+	}
+
+	/// Parse a single bank statement
+	///
+	/// Returns the receipt url, bank account and transactions of the statement. Fails with
+	/// the type and index of the transaction if the error is in one of the transactions.
+	fn parse_statement(json: &JsonValue) -> ParsedStatement<T> {
+		let decimals = T::FiatDecimals::get();
+
+		let obj = json.as_object().ok_or((None, ParseError::NotAnObject))?;
+		let receipt_url = extract_bounded("receiptUrl", obj).map_err(|e| (None, e))?;
+		let bank_account = BankAccountOf::<T>::from_json(json, decimals).map_err(|e| (None, e))?;
+
+		// extract transactions
+		let mut transactions = Vec::new();
+		for tx_type in [TransactionType::Outgoing, TransactionType::Incoming] {
+			transactions.extend(
+				TransactionOf::<T>::parse_transactions(json, tx_type, decimals)
+					.map_err(|(index, e)| (index.map(|index| (tx_type, index)), e))?,
+			);
+		}
+
+		Ok((receipt_url, bank_account, transactions))
 	}
 
 	fn validate_tx_parameters() -> TransactionValidity {
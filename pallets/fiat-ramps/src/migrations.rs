@@ -0,0 +1,253 @@
+//! Storage migrations of the pallet
+use crate::*;
+use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+/// Migration from the unversioned storage layout to keyed statement batches, burn requests with
+/// a lifecycle, the image id registry and the set of offchain worker authorities
+pub mod v1 {
+	use super::*;
+
+	/// Storage layout before the storage was versioned
+	pub(super) mod v0 {
+		use super::*;
+
+		#[derive(Encode, Decode)]
+		pub(crate) struct BankAccount<MaxLength: Get<u32>> {
+			pub iban: Iban<MaxLength>,
+			pub balance: u128,
+			pub last_updated: u64,
+		}
+
+		pub(crate) type BankAccountOf<T> = BankAccount<<T as Config>::MaxIbanLength>;
+
+		#[derive(Encode, Decode)]
+		pub(crate) struct Transaction<MaxLength: Get<u32>, StringMaxLength: Get<u32>> {
+			pub iban: Iban<MaxLength>,
+			pub name: BoundedString<StringMaxLength>,
+			pub currency: BoundedString<StringMaxLength>,
+			pub amount: u128,
+			pub reference: BoundedString<StringMaxLength>,
+			pub tx_type: TransactionType,
+		}
+
+		pub(crate) type TransactionOf<T> =
+			Transaction<<T as Config>::MaxIbanLength, <T as Config>::MaxStringLength>;
+
+		pub(crate) type StatementsOf<T> = BoundedVec<
+			(BankAccountOf<T>, BoundedVec<TransactionOf<T>, <T as Config>::MaxStatements>),
+			<T as Config>::MaxStatements,
+		>;
+
+		pub(crate) type QueuedStatementsInfoOf<T> =
+			QueuedStatementsInfo<BlockNumberFor<T>, StatementsOf<T>, StringOf<T>>;
+
+		#[derive(Encode, Decode)]
+		pub(crate) struct BurnRequest<MaxLength: Get<u32>, Balance> {
+			pub id: u64,
+			pub burner: Iban<MaxLength>,
+			pub dest_iban: Iban<MaxLength>,
+			pub amount: Balance,
+		}
+
+		pub(crate) type BurnRequestOf<T> = BurnRequest<<T as Config>::MaxIbanLength, BalanceOf<T>>;
+
+		/// Statements waiting for verification, before they were keyed by batch id
+		#[frame_support::storage_alias]
+		pub(crate) type QueuedStatements<T: Config> =
+			StorageValue<Pallet<T>, QueuedStatementsInfoOf<T>, OptionQuery>;
+
+		/// Image id of the risc0 module, before several image ids could be registered
+		#[frame_support::storage_alias]
+		pub(crate) type Risc0ImageId<T: Config> = StorageValue<Pallet<T>, [u8; 32], OptionQuery>;
+	}
+
+	/// Bank account with a zero opening balance and no currency and beneficiary, they are set by
+	/// the next statement of the bank account and by its holder
+	fn migrate_bank_account<T: Config>(old: v0::BankAccountOf<T>) -> BankAccountOf<T> {
+		BankAccount {
+			iban: old.iban,
+			opening_balance: 0,
+			balance: old.balance,
+			currency: None,
+			last_updated: old.last_updated,
+			beneficiary: None,
+		}
+	}
+
+	/// Transaction without an entry reference and dates, it is identified by the hash of its
+	/// details
+	fn migrate_transaction<T: Config>(old: v0::TransactionOf<T>) -> TransactionOf<T> {
+		Transaction {
+			iban: old.iban,
+			name: old.name,
+			currency: old.currency,
+			amount: old.amount,
+			reference: old.reference,
+			tx_type: old.tx_type,
+			entry_reference: None,
+			booking_date: 0,
+			validation_date: 0,
+		}
+	}
+
+	/// Statements of a queued batch, the number of statements and transactions is unchanged,
+	/// so they are still within the bounds
+	fn migrate_statements<T: Config>(old: v0::StatementsOf<T>) -> Option<StatementsOf<T>> {
+		old.into_iter()
+			.map(|(bank_account, transactions)| {
+				let transactions =
+					transactions.into_iter().map(migrate_transaction::<T>).collect::<Vec<_>>();
+				Some((migrate_bank_account::<T>(bank_account), transactions.try_into().ok()?))
+			})
+			.collect::<Option<Vec<_>>>()?
+			.try_into()
+			.ok()
+	}
+
+	/// Beneficiary of burn requests made before beneficiaries were required
+	fn empty_beneficiary<T: Config>() -> BeneficiaryOf<T> {
+		Beneficiary {
+			name: Default::default(),
+			street: Default::default(),
+			street_nr: Default::default(),
+			zip: Default::default(),
+			city: Default::default(),
+			country: Default::default(),
+			bic: Default::default(),
+			bank_name: Default::default(),
+			national: false,
+		}
+	}
+
+	/// Migrates the storage of the pallet to v1
+	///
+	/// - Bank accounts are indexed in `IbanToAccount`, they get a zero opening balance and no
+	///   currency and beneficiary.
+	/// - Burn requests are marked as pending in EUR, so that their unpeg request is sent again.
+	///   The burner account is looked up by the burner IBAN and their funds are added to
+	///   `EscrowedFunds`. They get an empty beneficiary, so their unpeg request is rejected by
+	///   the EBICS service and they are refunded after `MaxUnpegAttempts` attempts.
+	/// - Statements that are still waiting for verification are moved into a new batch and
+	///   indexed by expiry block and receipt URL, a batch that already expired is removed in the
+	///   next block. Its transactions get no entry reference and dates. `ProcessedTransactions`
+	///   starts empty, transactions processed before the upgrade are not protected against
+	///   replay.
+	/// - The image id that was set is registered, active from genesis and without sunset.
+	/// - The account that was configured as `OcwAccount` becomes the only authority, so that a
+	///   single attestation is enough until governance adds more authorities. The account keeps
+	///   sending the unpeg requests.
+	pub struct MigrateToV1<T, OcwAccount>(PhantomData<(T, OcwAccount)>);
+
+	impl<T: Config, OcwAccount: Get<T::AccountId>> OnRuntimeUpgrade for MigrateToV1<T, OcwAccount> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() >= 1 {
+				log::info!("[Migration] Storage already migrated, skipping");
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut reads = 1u64;
+			let mut writes = 1u64;
+
+			let mut accounts = 0u64;
+			Accounts::<T>::translate::<v0::BankAccountOf<T>, _>(|account_id, old| {
+				accounts += 1;
+				IbanToAccount::<T>::insert(&old.iban, account_id);
+				Some(migrate_bank_account::<T>(old))
+			});
+			reads += accounts;
+			writes += 2 * accounts;
+
+			let block_number = frame_system::Pallet::<T>::block_number();
+			let mut burn_requests = 0u64;
+			BurnRequests::<T>::translate::<v0::BurnRequestOf<T>, _>(|_, old| {
+				burn_requests += 1;
+
+				let burner_account = IbanToAccount::<T>::get(&old.burner).unwrap_or_else(|| {
+					log::error!("[Migration] Burn request {} has no burner account", old.id);
+					Pallet::<T>::account_id()
+				});
+				let currency = *b"EUR";
+
+				EscrowedFunds::<T>::mutate(&burner_account, currency, |escrow| {
+					*escrow = escrow.saturating_add(old.amount)
+				});
+
+				Some(BurnRequest {
+					id: old.id,
+					burner: old.burner,
+					burner_account,
+					dest_iban: old.dest_iban,
+					beneficiary: empty_beneficiary::<T>(),
+					amount: old.amount,
+					currency,
+					status: BurnRequestStatus::Pending,
+					updated_at: block_number,
+				})
+			});
+			reads += 3 * burn_requests;
+			writes += 2 * burn_requests;
+
+			// Old value and new batches share the storage prefix, so the value is taken first
+			reads += 1;
+			writes += 1;
+			if let Some(old) = v0::QueuedStatements::<T>::take() {
+				match migrate_statements::<T>(old.statements) {
+					Some(statements) => {
+						let batch_id = StatementBatchCount::<T>::get();
+						let expires_at =
+							old.block_number.saturating_add(T::StatementsExpiry::get());
+						let removed_at = expires_at
+							.saturating_add(1u32.into())
+							.max(block_number.saturating_add(1u32.into()));
+
+						StatementsExpiring::<T>::append(removed_at, batch_id);
+						QueuedReceipts::<T>::insert(&old.receipt_url, batch_id);
+						QueuedStatements::<T>::insert(
+							batch_id,
+							QueuedStatementsInfo {
+								block_number: old.block_number,
+								statements,
+								receipt_url: old.receipt_url,
+							},
+						);
+						StatementBatchCount::<T>::put(batch_id + 1);
+
+						reads += 1;
+						writes += 4;
+					},
+					None => log::error!("[Migration] Queued statements exceed the bounds, dropped"),
+				}
+			}
+
+			reads += 1;
+			writes += 1;
+			if let Some(image_id) = v0::Risc0ImageId::<T>::take() {
+				Risc0ImageIds::<T>::insert(
+					image_id,
+					ImageIdInfo {
+						activation: Zero::zero(),
+						sunset: None,
+						label: BoundedVec::truncate_from(b"migrated".to_vec()),
+					},
+				);
+
+				writes += 2;
+			}
+
+			OcwAuthorities::<T>::put(BoundedVec::truncate_from(vec![OcwAccount::get()]));
+			AttestationThreshold::<T>::put(1);
+			UnpegAuthority::<T>::put(OcwAccount::get());
+			writes += 3;
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			log::info!(
+				"[Migration] Migrated {} bank accounts and {} burn requests to v1",
+				accounts,
+				burn_requests
+			);
+
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+	}
+}
@@ -1,11 +1,18 @@
-use crate::{self as fiat_ramps, crypto::Public};
+use crate::{self as fiat_ramps, crypto::Public, types::StatementFormat};
 use codec::{Decode, Encode, MaxEncodedLen};
-use frame_support::{parameter_types, weights::Weight};
+use frame_support::{
+	parameter_types,
+	traits::{AsEnsureOriginWithArg, ConstU128, ConstU32},
+	weights::Weight,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
 use scale_info::TypeInfo;
 use sp_core::{sr25519::Signature, ByteArray, ConstU16, ConstU64, H256};
 use sp_runtime::{
 	testing::TestXt,
-	traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify},
+	traits::{
+		BlakeTwo256, Convert, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify,
+	},
 	BuildStorage,
 };
 
@@ -23,6 +30,9 @@ pub type Block = frame_system::mocking::MockBlock<Test>;
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Asset representing Swiss francs.
+pub const CHF_ASSET_ID: u32 = 1;
+
 const MILLISECS_PER_BLOCK: u64 = 4000;
 
 // Mock runtime for our tests
@@ -33,6 +43,7 @@ frame_support::construct_runtime!(
 		Timestamp: pallet_timestamp,
 		Sudo: pallet_sudo,
 		Balances: pallet_balances,
+		Assets: pallet_assets,
 	}
 );
 
@@ -100,6 +111,29 @@ impl pallet_balances::Config for Test {
 	type RuntimeHoldReason = ();
 }
 
+impl pallet_assets::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type RemoveItemsLimit = ConstU32<1000>;
+	type AssetId = u32;
+	type AssetIdParameter = u32;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = ConstU128<1>;
+	type AssetAccountDeposit = ConstU128<1>;
+	type MetadataDepositBase = ConstU128<1>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type CallbackHandle = ();
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
 parameter_types! {
 	pub const MinimumPeriod: u64 = 2;
 }
@@ -127,11 +161,64 @@ parameter_types! {
 	/// Bound of string length
 	#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo)]
 	pub const MaxStringLength: u32 = 255;
-	/// OCW account
+	/// Lead OCW authority
 	pub OcwAccount: AccountId = AccountId::from(Public::from_slice(hex_literal::hex!("bcc8880ea4f0aa7c2ab91395da43c465bc2232dd93ac671350258728130d5914").as_ref()).unwrap());
 	/// Bound for statements
 	#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo)]
 	pub const MaxStatements: u32 = 255;
+	/// Number of blocks queued statements wait for their receipt
+	pub const StatementsExpiry: u64 = 10;
+	/// Number of blocks an IBAN link waits for its micro-deposit
+	pub const LinkExpiry: u64 = 10;
+	/// Number of accounts that can wait for a micro-deposit from the same IBAN
+	pub const MaxPendingLinks: u32 = 2;
+	/// Number of failed unpeg attempts before a burn request is refunded
+	pub const MaxUnpegAttempts: u32 = 3;
+	/// Number of blocks before a failed unpeg request is retried
+	pub const UnpegRetryDelay: u64 = 2;
+	/// Maximum number of burn requests in an unpeg batch, set by tests
+	pub static MaxUnpegBatch: u32 = 10;
+	/// Milliseconds to wait for the response of the EBICS service
+	pub const HttpTimeout: u64 = 10_000;
+	/// Number of retries of failed requests to the EBICS service
+	pub const HttpRetries: u32 = 2;
+	/// Number of decimal places of fiat amounts, set by tests
+	pub static FiatDecimals: u32 = 10;
+	/// Whether a reconciliation mismatch pauses minting, set by tests
+	pub static PauseMintingOnMismatch: bool = false;
+	/// Number of blocks a processed transaction is remembered
+	pub const ProcessedTransactionsExpiry: u64 = 100;
+	/// Format of the fetched statements, set by tests
+	pub static FetchedStatementFormat: StatementFormat = StatementFormat::Json;
+	/// Maximum size of a submitted receipt
+	pub const MaxReceiptLength: u32 = 64 * 1024;
+	/// Weight of verifying a submitted receipt
+	pub const ReceiptVerificationWeight: Weight = Weight::from_parts(1_000_000_000, 64 * 1024);
+	/// Weight of decoding a byte of a submitted receipt
+	pub const ReceiptByteWeight: Weight = Weight::from_parts(100_000, 0);
+	/// Maximum number of registered image ids
+	pub const MaxImageIds: u32 = 2;
+	/// Maximum number of offchain worker authorities
+	pub const MaxOcwAuthorities: u32 = 3;
+	/// Maximum number of subjects an authority attests before they reach the threshold
+	pub const MaxPendingAttestations: u32 = 4;
+}
+
+/// Euro is the native currency, Swiss francs are held in an asset
+pub struct FiatCurrencies;
+
+impl Convert<fiat_ramps::types::CurrencyCode, Option<fiat_ramps::types::CurrencyId<u32>>>
+	for FiatCurrencies
+{
+	fn convert(
+		code: fiat_ramps::types::CurrencyCode,
+	) -> Option<fiat_ramps::types::CurrencyId<u32>> {
+		match &code {
+			b"EUR" => Some(fiat_ramps::types::CurrencyId::Native),
+			b"CHF" => Some(fiat_ramps::types::CurrencyId::Asset(CHF_ASSET_ID)),
+			_ => None,
+		}
+	}
 }
 
 impl fiat_ramps::Config for Test {
@@ -143,8 +230,27 @@ impl fiat_ramps::Config for Test {
 	type UnsignedPriority = UnsignedPriority;
 	type MaxIbanLength = MaxIbanLength;
 	type MaxStringLength = MaxStringLength;
-	type OcwAccount = OcwAccount;
+	type MaxOcwAuthorities = MaxOcwAuthorities;
+	type MaxPendingAttestations = MaxPendingAttestations;
 	type MaxStatements = MaxStatements;
+	type StatementsExpiry = StatementsExpiry;
+	type LinkExpiry = LinkExpiry;
+	type MaxPendingLinks = MaxPendingLinks;
+	type MaxUnpegAttempts = MaxUnpegAttempts;
+	type UnpegRetryDelay = UnpegRetryDelay;
+	type MaxUnpegBatch = MaxUnpegBatch;
+	type HttpTimeout = HttpTimeout;
+	type HttpRetries = HttpRetries;
+	type FiatDecimals = FiatDecimals;
+	type PauseMintingOnMismatch = PauseMintingOnMismatch;
+	type ProcessedTransactionsExpiry = ProcessedTransactionsExpiry;
+	type StatementFormat = FetchedStatementFormat;
+	type MaxReceiptLength = MaxReceiptLength;
+	type ReceiptVerificationWeight = ReceiptVerificationWeight;
+	type ReceiptByteWeight = ReceiptByteWeight;
+	type MaxImageIds = MaxImageIds;
+	type Assets = Assets;
+	type CurrencyFor = FiatCurrencies;
 }
 
 impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
@@ -184,5 +290,25 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 	.assimilate_storage(&mut t)
 	.unwrap();
 
+	// Swiss francs asset, owned by the pallet
+	pallet_assets::GenesisConfig::<Test> {
+		assets: vec![(CHF_ASSET_ID, FiatRampsExample::account_id(), true, 1)],
+		metadata: vec![],
+		accounts: vec![],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	// Receipts served by the mock are fake receipts
+	fiat_ramps::GenesisConfig::<Test> {
+		accounts: vec![],
+		fake_receipts_permitted: true,
+		fake_receipts_allowed: true,
+		ocw_authorities: vec![OcwAccount::get()],
+		attestation_threshold: 1,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
 	t.into()
 }
@@ -1,23 +1,46 @@
-use codec::Decode;
-use frame_support::{assert_err, assert_noop, assert_ok};
-use lite_json::Serialize;
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_err, assert_noop, assert_ok,
+	dispatch::GetDispatchInfo,
+	traits::{GetStorageVersion, Hooks, OnRuntimeUpgrade, StorageVersion},
+	Blake2_128Concat, BoundedVec, StorageHasher,
+};
+use lite_json::{parse_json, JsonValue, Serialize};
+use risc0_zkvm::{InnerReceipt, Receipt};
 use sp_core::{
 	crypto::Ss58Codec,
-	offchain::{testing, OffchainWorkerExt, TransactionPoolExt},
+	offchain::{
+		testing, Duration, Externalities, HttpError, HttpRequestId, HttpRequestStatus,
+		OffchainDbExt, OffchainWorkerExt, OpaqueNetworkState, OpaquePeerId,
+		Timestamp as OffchainTimestamp, TransactionPoolExt,
+	},
 };
+use sp_io::hashing::blake2_256;
 use sp_keystore::{Keystore, KeystoreExt};
-use sp_runtime::{traits::BadOrigin, DispatchError, RuntimeAppPublic};
-use std::sync::Arc;
+use sp_runtime::{offchain::http::Method, traits::BadOrigin, DispatchError, RuntimeAppPublic};
+use std::sync::{Arc, Mutex};
 
 use crate::{
+	amount::{from_json_number, to_json_number},
+	camt,
 	helpers::{
-		get_mock_receipt, get_mock_response, string_to_bounded_vec, ResponseTypes, StatementTypes,
+		get_mock_beneficiary, get_mock_camt_message, get_mock_journal, get_mock_receipt,
+		get_mock_response, get_mock_unpeg_response, string_to_bounded_vec, ResponseTypes,
+		StatementTypes,
 	},
+	http, journal,
 	types::{
-		BankAccountOf, IbanOf, Transaction, TransactionOf, TransactionType, TransferDestination,
+		AmountError, AttestationSubject, BankAccountOf, Beneficiary, BurnRequest,
+		BurnRequestStatus, IbanOf, JournalError, OcwError, ParseError, PaymentFormat, Reference,
+		ReferenceError, ReferenceOf, StatementError, StatementFormat, StatementsOf, Transaction,
+		TransactionOf, TransactionType, TransferDestination, UnpegOutcome, XmlError,
+		STATEMENT_ERRORS_KEY,
 	},
 	utils::*,
-	Config, QueuedStatements,
+	xml, Accounts, Attestations, AttestationsExpiring, Config, EscrowedFunds,
+	FakeReceiptsPermitted, InFlightBurnRequests, OcwAuthorities, PendingAttestations,
+	PendingLinkCount, QueuedReceipts, QueuedStatements, Risc0ImageIds, StatementsExpiring,
+	UnpegAuthority,
 };
 
 use crate::{mock::*, Error};
@@ -40,6 +63,7 @@ fn test_processing(statement_type: StatementTypes, response_type: ResponseTypes)
 
 	let mut t = new_test_ext();
 
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
 	t.register_extension(OffchainWorkerExt::new(offchain));
 	t.register_extension(TransactionPoolExt::new(pool));
 	t.register_extension(KeystoreExt(Arc::new(keystore)));
@@ -61,7 +85,8 @@ fn test_processing(statement_type: StatementTypes, response_type: ResponseTypes)
 	);
 
 	t.execute_with(|| {
-		FiatRampsExample::set_risc0_image_id(RuntimeOrigin::root(), [0u8; 32]).unwrap();
+		set_local_ocw_authority();
+		add_image_id();
 
 		match response_type {
 			ResponseTypes::Empty => {
@@ -84,27 +109,75 @@ fn test_processing(statement_type: StatementTypes, response_type: ResponseTypes)
 						assert_ok!(FiatRampsExample::fetch_and_send_signed(
 							crate::OcwActivity::FetchStatements
 						));
-						let tx = pool_state.write().transactions.pop().unwrap();
 
-						assert!(pool_state.read().transactions.is_empty());
+						// Every receipt is queued as a separate batch
+						let txs = pool_state.write().transactions.drain(..).collect::<Vec<_>>();
+						assert_eq!(txs.len(), parsed_response.len());
 
-						let tx = Extrinsic::decode(&mut &*tx).unwrap();
-						assert_eq!(tx.signature.unwrap().0, 0);
+						for (tx, statements_info) in txs.into_iter().zip(parsed_response) {
+							let tx = Extrinsic::decode(&mut &*tx).unwrap();
+							assert_eq!(tx.signature.unwrap().0, 0);
 
-						assert_eq!(
-							tx.call,
-							crate::Call::queue_statements {
-								receipt_url: parsed_response.clone().unwrap().receipt_url,
-								statements: parsed_response.unwrap().statements,
-							}
-							.into()
-						);
+							assert_eq!(
+								tx.call,
+								crate::Call::queue_statements {
+									receipt_url: statements_info.receipt_url,
+									statements: statements_info.statements,
+								}
+								.into()
+							);
+						}
 					},
 				},
 		}
 	})
 }
 
+/// Image id that fake receipts are accepted for
+const IMAGE_ID: [u8; 32] = [0u8; 32];
+
+/// Registers `IMAGE_ID`, active from genesis
+fn add_image_id() {
+	assert_ok!(FiatRampsExample::add_risc0_image_id(
+		RuntimeOrigin::root(),
+		IMAGE_ID,
+		0,
+		string_to_bounded_vec("v1")
+	));
+}
+
+/// Makes the key of the local keystore an offchain worker authority that sends unpeg requests
+fn set_local_ocw_authority() {
+	let local_key = sp_io::crypto::sr25519_public_keys(crate::crypto::Public::ID)[0];
+	let mut authorities = vec![local_key];
+	if local_key != OcwAccount::get() {
+		authorities.push(OcwAccount::get());
+	}
+	OcwAuthorities::<Test>::put(BoundedVec::truncate_from(authorities));
+	UnpegAuthority::<Test>::put(local_key);
+}
+
+/// Applies the claim of burn requests submitted by the offchain worker as the local authority
+fn apply_unpeg_claim(tx: Vec<u8>) -> Vec<u64> {
+	let local_key = sp_io::crypto::sr25519_public_keys(crate::crypto::Public::ID)[0];
+
+	match Extrinsic::decode(&mut &*tx).unwrap().call {
+		RuntimeCall::FiatRampsExample(crate::Call::claim_unpeg_requests { request_ids }) => {
+			assert_ok!(FiatRampsExample::claim_unpeg_requests(
+				RuntimeOrigin::signed(local_key),
+				request_ids.clone()
+			));
+			request_ids
+		},
+		call => panic!("Unexpected call {:?}", call),
+	}
+}
+
+/// Journal that commits to the queued statement batch
+fn queued_journal(batch_id: u64) -> Vec<u8> {
+	get_mock_journal::<Test>(&FiatRampsExample::queued_statements(batch_id).unwrap().statements)
+}
+
 /// Mock server response
 fn ebics_server_response(
 	state: &mut testing::OffchainState,
@@ -142,6 +215,7 @@ fn should_make_http_call_and_parse() {
 	let (offchain, state) = testing::TestOffchainExt::new();
 	let mut t = new_test_ext();
 
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
 	t.register_extension(OffchainWorkerExt::new(offchain));
 
 	let (response_bytes, parsed_response) = get_mock_response::<Test>(
@@ -170,12 +244,12 @@ fn should_make_http_call_and_parse() {
 			let mut balances: Vec<(BankAccountOf<Test>, Vec<TransactionOf<Test>>)> =
 				Vec::with_capacity(v.len());
 			for val in v.iter() {
-				if let Ok(bank_account) = BankAccountOf::<Test>::try_from(val) {
+				if let Ok(bank_account) = BankAccountOf::<Test>::from_json(val, 10) {
 					let mut transactions =
-						Transaction::parse_transactions(&val, TransactionType::Outgoing)
+						Transaction::parse_transactions(&val, TransactionType::Outgoing, 10)
 							.unwrap_or_default();
 					let mut incoming_transactions =
-						Transaction::parse_transactions(&val, TransactionType::Incoming)
+						Transaction::parse_transactions(&val, TransactionType::Incoming, 10)
 							.unwrap_or_default();
 
 					transactions.append(&mut incoming_transactions);
@@ -184,19 +258,113 @@ fn should_make_http_call_and_parse() {
 			}
 
 			assert_eq!(balances.len(), 1);
-			assert_eq!(
-				b"abcd.json".to_vec(),
-				parsed_response.clone().unwrap().receipt_url.into_inner()
-			);
-			assert_eq!(balances[0].0, parsed_response.clone().unwrap().statements[0].0);
-			assert_eq!(
-				balances[0].1,
-				parsed_response.unwrap().statements[0].clone().1.into_inner()
-			);
+			assert_eq!(b"abcd.json".to_vec(), parsed_response[0].receipt_url.clone().into_inner());
+			assert_eq!(balances[0].0, parsed_response[0].statements[0].0);
+			assert_eq!(balances[0].1, parsed_response[0].statements[0].clone().1.into_inner());
 		}
 	})
 }
 
+#[test]
+fn malformed_statements_are_skipped() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+
+	let response = r#"[
+		{
+			"iban": "CH2108307000289537313",
+			"balanceCL": 100,
+			"receiptUrl": "abcd.json",
+			"incomingTransactions": [
+				{
+					"iban": "CH2108307000289537320",
+					"name": "LONG_NAME",
+					"currency": "EUR",
+					"amount": 10,
+					"reference": "none"
+				}
+			]
+		},
+		{
+			"iban": "CH2108307000289537313",
+			"receiptUrl": "abcd.json"
+		},
+		{
+			"iban": "CH1230116000289537312",
+			"balanceCL": 100,
+			"receiptUrl": "abcd.json",
+			"incomingTransactions": [
+				{
+					"iban": "CH2108307000289537320",
+					"name": "Alice",
+					"currency": "EUR",
+					"amount": 10,
+					"reference": "none"
+				},
+				{
+					"iban": "CH2108307000289537320",
+					"name": "Alice",
+					"currency": "EUR",
+					"amount": 0.00000000001,
+					"reference": "none"
+				}
+			]
+		},
+		"statement"
+	]"#
+	.replace("LONG_NAME", &"A".repeat(300));
+
+	ebics_server_response(
+		&mut state.write(),
+		testing::PendingRequest {
+			method: "GET".to_string(),
+			uri: "http://w.e36.io:8093/ebics/api-v1/bankstatements".to_string(),
+			response: Some(response.into_bytes()),
+			sent: true,
+			..Default::default()
+		},
+	);
+
+	t.execute_with(|| {
+		let (statements, errors) = FiatRampsExample::parse_statements().unwrap();
+
+		// Only the first statement is parsed, the name of the counterparty is truncated
+		let statements = &statements[&string_to_bounded_vec::<MaxStringLength>("abcd.json")];
+		assert_eq!(statements.len(), 1);
+		assert_eq!(statements[0].1.len(), 1);
+		assert_eq!(statements[0].1[0].name.to_vec(), vec![b'A'; 255]);
+
+		assert_eq!(
+			errors,
+			vec![
+				StatementError {
+					statement: 1,
+					transaction: None,
+					error: ParseError::InvalidAmount("balanceCL", AmountError::NotANumber),
+				},
+				StatementError {
+					statement: 2,
+					transaction: Some((TransactionType::Incoming, 1)),
+					error: ParseError::InvalidAmount("amount", AmountError::TooPrecise),
+				},
+				StatementError { statement: 3, transaction: None, error: ParseError::NotAnObject },
+			]
+		);
+
+		// Errors are kept in the offchain storage for operators
+		assert_eq!(
+			sp_io::offchain::local_storage_get(
+				sp_core::offchain::StorageKind::PERSISTENT,
+				STATEMENT_ERRORS_KEY
+			),
+			Some((0u64, errors).encode())
+		);
+	})
+}
+
 #[test]
 fn test_queue_empty_statement() {
 	test_processing(StatementTypes::Empty, ResponseTypes::Empty)
@@ -227,6 +395,250 @@ fn test_queue_invalid_transactions() {
 	test_processing(StatementTypes::InvalidTransactions, ResponseTypes::SingleStatement)
 }
 
+#[test]
+fn test_queue_camt_statements() {
+	for format in [StatementFormat::Camt053, StatementFormat::Camt054] {
+		let (offchain, state) = testing::TestOffchainExt::new();
+		let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+		let keystore = sp_keystore::testing::MemoryKeystore::new();
+
+		keystore
+			.sr25519_generate_new(
+				crate::crypto::Public::ID,
+				Some(&format!(
+					"{}/alice",
+					"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+				)),
+			)
+			.unwrap();
+
+		let mut t = new_test_ext();
+
+		t.register_extension(OffchainDbExt::new(offchain.clone()));
+		t.register_extension(OffchainWorkerExt::new(offchain));
+		t.register_extension(TransactionPoolExt::new(pool));
+		t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+		let (message, parsed_message) = get_mock_camt_message::<Test>(format);
+
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				method: "GET".to_string(),
+				uri: format!("http://w.e36.io:8093/ebics/{}", format.endpoint()),
+				response: Some(message),
+				sent: true,
+				..Default::default()
+			},
+		);
+
+		t.execute_with(|| {
+			set_local_ocw_authority();
+			FetchedStatementFormat::set(format);
+			add_image_id();
+
+			assert_ok!(FiatRampsExample::fetch_and_send_signed(
+				crate::OcwActivity::FetchStatements
+			));
+
+			// Statements of the message are queued with the message id as receipt url
+			let tx = pool_state.write().transactions.pop().unwrap();
+			let tx = Extrinsic::decode(&mut &*tx).unwrap();
+			let statements_info = parsed_message[0].clone();
+
+			assert_eq!(
+				tx.call,
+				crate::Call::queue_statements {
+					receipt_url: statements_info.receipt_url,
+					statements: statements_info.statements,
+				}
+				.into()
+			);
+		})
+	}
+}
+
+#[test]
+fn malformed_camt_messages_are_reported() {
+	let message = br#"<Document>
+		<BkToCstmrStmt>
+			<GrpHdr><MsgId>camt053-20220216</MsgId></GrpHdr>
+			<Stmt>
+				<Acct><Id><IBAN>CH2108307000289537313</IBAN></Id></Acct>
+			</Stmt>
+			<Stmt>
+				<Acct><Id><IBAN>CH2108307000289537313</IBAN></Id></Acct>
+				<Bal><Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp><Amt Ccy="EUR">1.00</Amt></Bal>
+				<Ntry><Amt Ccy="EUR">1.00</Amt></Ntry>
+			</Stmt>
+		</BkToCstmrStmt>
+	</Document>"#;
+
+	let (_, statements) = camt::parse_message::<Test>(message).unwrap();
+
+	// Statement without closing balance and entry without direction are skipped
+	assert_eq!(
+		statements,
+		vec![
+			Err((None, ParseError::MissingField("Bal"))),
+			Err((Some((TransactionType::None, 0)), ParseError::MissingField("CdtDbtInd"))),
+		]
+	);
+
+	assert_eq!(
+		camt::parse_message::<Test>(b"<Document><BkToCstmrStmt></Document>"),
+		Err(ParseError::InvalidXml(XmlError::Malformed(25)))
+	);
+	assert_eq!(
+		camt::parse_message::<Test>(b"<Document><Unknown/></Document>"),
+		Err(ParseError::MissingField("BkToCstmrStmt"))
+	);
+}
+
+#[test]
+fn failed_requests_are_recorded_per_endpoint() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+
+	let endpoint = "api-v1/bankstatements";
+	let expect_response = |response: &[u8]| {
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				method: "GET".to_string(),
+				uri: format!("http://w.e36.io:8093/ebics/{}", endpoint),
+				response: Some(response.to_vec()),
+				sent: true,
+				..Default::default()
+			},
+		);
+	};
+
+	t.execute_with(|| {
+		assert_eq!(http::last_error(endpoint), None);
+
+		// Response is not JSON
+		expect_response(b"<html>Bad Gateway</html>");
+		assert_eq!(FiatRampsExample::parse_statements().unwrap_err(), OcwError::InvalidJson);
+		assert_eq!(http::last_error(endpoint), Some((0, OcwError::InvalidJson)));
+
+		// Statements are not a list
+		expect_response(br#"{"statements": []}"#);
+		assert_eq!(FiatRampsExample::parse_statements().unwrap_err(), OcwError::Schema);
+		assert_eq!(http::last_error(endpoint), Some((0, OcwError::Schema)));
+
+		// Request is not sent if the API url is invalid
+		assert_ok!(FiatRampsExample::set_api_url(
+			RuntimeOrigin::root(),
+			vec![0xff, 0xfe].try_into().unwrap()
+		));
+		assert_eq!(http::get::<Test>(endpoint), Err(OcwError::InvalidUtf8));
+		assert_eq!(http::last_error(endpoint), Some((0, OcwError::InvalidUtf8)));
+
+		// Other endpoints keep their own errors
+		assert_eq!(http::last_error("api-v1/unpeg/batch"), None);
+	});
+
+	// Only timeouts and server errors are retried
+	assert!(OcwError::Timeout.is_retryable());
+	assert!(OcwError::HttpStatus(503).is_retryable());
+	assert!(!OcwError::HttpStatus(404).is_retryable());
+	assert!(!OcwError::Transport.is_retryable());
+	assert!(!OcwError::Schema.is_retryable());
+
+	// Payment instructions are never sent twice
+	assert_eq!(http::max_retries::<Test>(&Method::Get), HttpRetries::get());
+	assert_eq!(http::max_retries::<Test>(&Method::Post), 0);
+}
+
+#[test]
+fn requests_are_signed_with_node_secret() {
+	// RFC 4231 test cases 2 and 6
+	assert_eq!(
+		http::hmac_sha256(b"Jefe", b"what do ya want for nothing?"),
+		hex_literal::hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+	);
+	assert_eq!(
+		http::hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First"),
+		hex_literal::hex!("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+	);
+
+	assert_eq!(
+		http::signing_message("GET", "/ebics/api-v1/bankstatements", 0, b""),
+		b"GET\n/ebics/api-v1/bankstatements\n0\n\
+		e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+			.to_vec()
+	);
+
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+
+	// Signatures of the reference verifier of the EBICS service for the secret `secret`
+	let signed = |timestamp: &str, signature: &str| {
+		vec![
+			("X-Ramp-Timestamp".to_string(), timestamp.to_string()),
+			("X-Ramp-Signature".to_string(), signature.to_string()),
+		]
+	};
+
+	t.execute_with(|| {
+		sp_io::offchain::local_storage_set(
+			sp_core::offchain::StorageKind::PERSISTENT,
+			http::API_SECRET_KEY,
+			b"secret",
+		);
+
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				method: "GET".to_string(),
+				uri: "http://w.e36.io:8093/ebics/api-v1/bankstatements".to_string(),
+				headers: signed(
+					"0",
+					"8d05b0e37aad75baaf867c8a106d8fb7d5acaa3d1c65a997153ce57c04808260",
+				),
+				response: Some(b"[]".to_vec()),
+				sent: true,
+				..Default::default()
+			},
+		);
+		let (statements, errors) = FiatRampsExample::parse_statements().unwrap();
+		assert!(statements.is_empty() && errors.is_empty());
+
+		// Body is signed by its hash
+		let mut headers = vec![
+			("Content-Type".to_string(), "application/json".to_string()),
+			("accept".to_string(), "*/*".to_string()),
+		];
+		headers.extend(signed(
+			"0",
+			"cc0582b1dcaff46db0d8486d0c3ff930d6da175bf8752e9fac5739e454b0f8c9",
+		));
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				method: "POST".to_string(),
+				uri: "http://w.e36.io:8093/ebics/api-v1/unpeg/batch".to_string(),
+				headers,
+				body: b"[]".to_vec(),
+				response: Some(b"[]".to_vec()),
+				sent: true,
+				..Default::default()
+			},
+		);
+		assert_eq!(
+			http::post::<Test>("api-v1/unpeg/batch", "application/json", b"[]".to_vec()),
+			Ok(b"[]".to_vec())
+		);
+	});
+}
+
 #[test]
 fn test_verify_queued_statements_works() {
 	let (offchain, state) = testing::TestOffchainExt::new();
@@ -247,6 +659,7 @@ fn test_verify_queued_statements_works() {
 
 	let mut t = new_test_ext();
 
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
 	t.register_extension(OffchainWorkerExt::new(offchain));
 	t.register_extension(TransactionPoolExt::new(pool));
 	t.register_extension(KeystoreExt(Arc::new(keystore)));
@@ -255,6 +668,7 @@ fn test_verify_queued_statements_works() {
 		ResponseTypes::SingleStatement,
 		StatementTypes::OutgoingTransactions,
 	);
+	let parsed_response = parsed_response[0].clone();
 
 	let statements_endpoint = "http://w.e36.io:8093/ebics/api-v1/bankstatements".to_string();
 
@@ -269,7 +683,8 @@ fn test_verify_queued_statements_works() {
 		},
 	);
 
-	let (receipt_response_bytes, _receipt_response) = get_mock_receipt();
+	let journal = get_mock_journal::<Test>(&parsed_response.statements);
+	let (receipt_response_bytes, _receipt_response) = get_mock_receipt(&journal);
 
 	let receipt_endpoint = "http://w.e36.io:8093/ebics/abcd.json".to_string();
 
@@ -285,7 +700,8 @@ fn test_verify_queued_statements_works() {
 	);
 
 	t.execute_with(|| {
-		assert_ok!(FiatRampsExample::set_risc0_image_id(RuntimeOrigin::root(), [0u8; 32]));
+		set_local_ocw_authority();
+		add_image_id();
 		assert_ok!(FiatRampsExample::fetch_and_send_signed(crate::OcwActivity::FetchStatements));
 
 		let tx_in_pool = pool_state.write().transactions.pop().unwrap();
@@ -298,19 +714,19 @@ fn test_verify_queued_statements_works() {
 			statements,
 		}) = tx.call
 		{
-			assert_eq!(receipt_url, parsed_response.clone().unwrap().receipt_url);
+			assert_eq!(receipt_url, parsed_response.receipt_url);
 			statements
 		} else {
 			panic!("Unexpected call: {:?}", tx.call);
 		};
 
-		assert!(parsed_response.clone().unwrap().statements.len() > 0);
-		assert_eq!(statements, parsed_response.clone().unwrap().statements);
+		assert!(parsed_response.statements.len() > 0);
+		assert_eq!(statements, parsed_response.statements);
 
 		assert_ok!(FiatRampsExample::queue_statements(
 			RuntimeOrigin::signed(public),
-			parsed_response.clone().unwrap().receipt_url,
-			parsed_response.unwrap().statements
+			parsed_response.receipt_url,
+			parsed_response.statements
 		));
 
 		assert_ok!(FiatRampsExample::fetch_and_send_signed(
@@ -321,10 +737,15 @@ fn test_verify_queued_statements_works() {
 		let tx_in_pool = pool_state.write().transactions.pop().unwrap();
 		let tx = Extrinsic::decode(&mut &*tx_in_pool).unwrap();
 
-		assert!(matches!(
+		assert_eq!(
 			tx.call,
-			RuntimeCall::FiatRampsExample(crate::Call::process_statements { .. })
-		));
+			RuntimeCall::FiatRampsExample(crate::Call::process_statements {
+				batch_id: 0,
+				image_id: IMAGE_ID,
+				journal,
+				fake_receipt: true
+			})
+		);
 	});
 }
 
@@ -344,13 +765,19 @@ fn test_iban_mapping() {
 
 	t.execute_with(|| {
 		assert_ok!(FiatRampsExample::create_account(
-			Some(alice.clone()).into(),
+			RuntimeOrigin::root(),
+			alice.clone(),
 			alice_iban.clone(),
 		));
-		assert_ok!(FiatRampsExample::create_account(Some(bob.clone()).into(), bob_iban.clone(),));
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			bob.clone(),
+			bob_iban.clone(),
+		));
 
 		assert_ok!(FiatRampsExample::create_account(
-			Some(charlie.clone()).into(),
+			RuntimeOrigin::root(),
+			charlie.clone(),
 			charlie_iban.clone(),
 		));
 
@@ -365,120 +792,799 @@ fn test_iban_mapping() {
 		));
 		// Should be mapped to None
 		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), None);
+		assert_eq!(FiatRampsExample::iban_to_account(&alice_iban), None);
+		assert_eq!(FiatRampsExample::account_of(&alice), None);
 	})
 }
 
 #[test]
-fn test_burn_request() {
-	let (offchain, state) = testing::TestOffchainExt::new();
-	let (pool, _pool_state) = testing::TestTransactionPoolExt::new();
-	let keystore = sp_keystore::testing::MemoryKeystore::new();
+fn iban_mapping_is_unique() {
+	new_test_ext().execute_with(|| {
+		let test_accounts = get_test_accounts();
 
-	keystore
-		.sr25519_generate_new(
-			crate::crypto::Public::ID,
-			Some(&format!(
-				"{}/alice",
-				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
-			)),
-		)
-		.unwrap();
+		let alice = test_accounts[0];
+		let bob = test_accounts[1];
 
-	let mut t = new_test_ext();
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
 
-	t.register_extension(OffchainWorkerExt::new(offchain));
-	t.register_extension(TransactionPoolExt::new(pool));
-	t.register_extension(KeystoreExt(Arc::new(keystore)));
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
 
-	let test_accounts = get_test_accounts();
+		// IBAN can not be claimed by another account
+		assert_noop!(
+			FiatRampsExample::create_account(RuntimeOrigin::root(), bob, alice_iban.clone()),
+			Error::<Test>::IbanAlreadyMapped
+		);
+		// Linked IBAN can not be overwritten without unmapping it first
+		assert_noop!(
+			FiatRampsExample::create_account(RuntimeOrigin::root(), alice, bob_iban.clone()),
+			Error::<Test>::AccountAlreadyMapped
+		);
+		// Only the linked IBAN can be unmapped
+		assert_noop!(
+			FiatRampsExample::unmap_iban_account(Some(alice).into(), bob_iban.clone()),
+			Error::<Test>::IbanMismatch
+		);
+		assert_noop!(
+			FiatRampsExample::unmap_iban_account(Some(bob).into(), bob_iban.clone()),
+			Error::<Test>::AccountNotMapped
+		);
 
-	let alice = test_accounts[0].clone();
-	let bob = test_accounts[1].clone();
-	let charlie = test_accounts[2].clone();
+		// Funds in escrow block unmapping
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Iban(bob_iban.clone(), get_mock_beneficiary::<Test>("Bob"))
+		));
+		assert_noop!(
+			FiatRampsExample::unmap_iban_account(Some(alice).into(), alice_iban.clone()),
+			Error::<Test>::BurnRequestsPending
+		);
 
-	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
-	let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
-	let charlie_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537313");
+		// Receiver of the burn request has no funds in escrow
+		assert_ok!(FiatRampsExample::create_account(RuntimeOrigin::root(), bob, bob_iban.clone()));
+		assert_ok!(FiatRampsExample::unmap_iban_account(Some(bob).into(), bob_iban.clone()));
 
-	{
-		let mock_unpeg_request = unpeg_request::<Test>(
-			&format!("{:?}", bob.to_ss58check()),
-			10000,
-			&bob_iban,
-			&"0".to_string(),
-		)
-		.serialize();
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(alice).into(), 0));
 
-		let mock_unpeg_request_1 = unpeg_request::<Test>(
-			&format!("{:?}", charlie.to_ss58check()),
-			100,
-			&charlie_iban,
-			&"1".to_string(),
-		)
-		.serialize();
+		assert_ok!(FiatRampsExample::unmap_iban_account(Some(alice).into(), alice_iban.clone()));
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			bob,
+			alice_iban.clone()
+		));
+		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), Some(bob));
+	})
+}
 
-		let mock_unpeg_request_2 = unpeg_request::<Test>(
-			&format!("{:?}", charlie.to_ss58check()),
-			1000,
-			&charlie_iban,
-			&"2".to_string(),
-		)
-		.serialize();
+/// Queues and processes a statement of the ramp account with a single incoming transaction
+fn process_incoming_transaction(iban: &IbanOf<Test>, amount: u128, reference: &str) {
+	process_single_transaction(iban, amount, "EUR", reference, TransactionType::Incoming);
+}
 
-		let unpeg_endpoint = "http://w.e36.io:8093/ebics/api-v1/unpeg";
+/// Queues and processes a statement of the ramp account with a single transaction
+fn process_single_transaction(
+	iban: &IbanOf<Test>,
+	amount: u128,
+	currency: &str,
+	reference: &str,
+	tx_type: TransactionType,
+) {
+	process_statement_transaction(
+		&string_to_bounded_vec("CH2108307000289537313"),
+		iban,
+		amount,
+		currency,
+		reference,
+		tx_type,
+	)
+}
 
-		ebics_server_response(
-			&mut state.write(),
-			testing::PendingRequest {
-				uri: unpeg_endpoint.to_string(),
-				method: "POST".to_string(),
-				body: mock_unpeg_request.clone(),
-				response: Some(mock_unpeg_request),
-				headers: [
-					("Content-Type".to_string(), "application/json".to_string()),
-					("accept".to_string(), "*/*".to_string()),
-				]
-				.to_vec(),
-				sent: true,
-				..Default::default()
-			},
+/// Queues and processes a statement of `statement_iban` with a single transaction
+fn process_statement_transaction(
+	statement_iban: &IbanOf<Test>,
+	iban: &IbanOf<Test>,
+	amount: u128,
+	currency: &str,
+	reference: &str,
+	tx_type: TransactionType,
+) {
+	let ocw_account = OcwAccount::get();
+	let batch_id = FiatRampsExample::statement_batch_count();
+
+	let statements = vec![(
+		BankAccountOf::<Test>::from(statement_iban),
+		vec![TransactionOf::<Test> {
+			iban: iban.clone(),
+			name: string_to_bounded_vec("Alice"),
+			currency: string_to_bounded_vec(currency),
+			amount,
+			reference: string_to_bounded_vec(reference),
+			tx_type,
+			entry_reference: None,
+			booking_date: 0,
+			validation_date: 0,
+		}]
+		.try_into()
+		.unwrap(),
+	)];
+
+	assert_ok!(FiatRampsExample::queue_statements(
+		RuntimeOrigin::signed(ocw_account),
+		string_to_bounded_vec(&format!("receipt-{}.json", batch_id)),
+		statements.try_into().unwrap(),
+	));
+	if FiatRampsExample::risc0_image_id().is_none() {
+		add_image_id();
+	}
+	assert_ok!(FiatRampsExample::process_statements(
+		RuntimeOrigin::signed(ocw_account),
+		batch_id,
+		IMAGE_ID,
+		queued_journal(batch_id),
+		false
+	));
+}
+
+#[test]
+fn iban_link_is_activated_by_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let alice = get_test_accounts()[0];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_noop!(
+			FiatRampsExample::create_account(Some(alice).into(), alice, alice_iban.clone()),
+			BadOrigin
 		);
 
-		ebics_server_response(
-			&mut state.write(),
-			testing::PendingRequest {
-				uri: unpeg_endpoint.to_string(),
-				method: "POST".to_string(),
-				body: mock_unpeg_request_1.clone(),
-				response: Some(mock_unpeg_request_1),
-				headers: [
-					("Content-Type".to_string(), "application/json".to_string()),
-					("accept".to_string(), "*/*".to_string()),
-				]
-				.to_vec(),
-				sent: true,
-				..Default::default()
-			},
+		let bob = get_test_accounts()[1];
+
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+
+		let link = FiatRampsExample::pending_links(&alice_iban, alice).unwrap();
+		let code = std::str::from_utf8(&link.code).unwrap().to_string();
+
+		assert_eq!(link.who, alice);
+		// Amount is between 0.01 and 0.99
+		let cent = 10u128.pow(<Test as Config>::FiatDecimals::get() - 2);
+		assert!(link.amount >= cent && link.amount < 100 * cent);
+
+		// Another account can request the IBAN too, but only the owner of the IBAN can make
+		// the deposit
+		assert_ok!(FiatRampsExample::request_iban_link(Some(bob).into(), alice_iban.clone()));
+		assert_ne!(FiatRampsExample::pending_links(&alice_iban, bob).unwrap().code, link.code);
+
+		// Deposit without the code or with a different amount does not link the IBAN
+		process_incoming_transaction(&alice_iban, link.amount, "Purp:none; ourRef:none");
+		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), None);
+
+		// Code in the reference field is not a valid burn request id, the deposit links the
+		// IBAN anyway
+		let reference = format!("ourRef:{}", code);
+		assert_eq!(
+			ReferenceOf::<Test>::try_from(reference.as_bytes()),
+			Err(ReferenceError::InvalidRequestId)
 		);
-		ebics_server_response(
-			&mut state.write(),
-			testing::PendingRequest {
-				uri: unpeg_endpoint.to_string(),
-				method: "POST".to_string(),
-				body: mock_unpeg_request_2.clone(),
-				response: Some(mock_unpeg_request_2),
-				headers: [
-					("Content-Type".to_string(), "application/json".to_string()),
-					("accept".to_string(), "*/*".to_string()),
-				]
-				.to_vec(),
-				sent: true,
-				..Default::default()
-			},
+
+		process_incoming_transaction(&alice_iban, link.amount + 1, &reference);
+		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), None);
+
+		process_incoming_transaction(&alice_iban, link.amount, &reference);
+
+		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), Some(alice));
+		assert_eq!(FiatRampsExample::account_of(alice).unwrap().iban, alice_iban);
+		assert!(FiatRampsExample::pending_links(&alice_iban, alice).is_none());
+		assert!(FiatRampsExample::pending_links(&alice_iban, bob).is_none());
+
+		System::assert_has_event(crate::Event::<Test>::AccountCreated(alice, alice_iban).into());
+	})
+}
+
+#[test]
+fn iban_link_expires() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let alice = get_test_accounts()[0];
+		let bob = get_test_accounts()[1];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+
+		// Requesting the link again renews it
+		System::set_block_number(2);
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+
+		let expiry_block = 2 + <Test as Config>::LinkExpiry::get();
+
+		System::set_block_number(expiry_block);
+		FiatRampsExample::on_initialize(expiry_block);
+		assert!(FiatRampsExample::pending_links(&alice_iban, alice).is_some());
+
+		System::set_block_number(expiry_block + 1);
+		FiatRampsExample::on_initialize(expiry_block + 1);
+
+		assert!(FiatRampsExample::pending_links(&alice_iban, alice).is_none());
+		System::assert_has_event(
+			crate::Event::<Test>::IbanLinkExpired { who: alice, iban: alice_iban.clone() }.into(),
+		);
+
+		// IBAN can be requested again once the link expired
+		assert_ok!(FiatRampsExample::request_iban_link(Some(bob).into(), alice_iban.clone()));
+	})
+}
+
+#[test]
+fn link_amount_depends_on_fiat_decimals() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let alice = get_test_accounts()[0];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		// Amount is between 0.01 and 0.99
+		FiatDecimals::set(2);
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+		let amount = FiatRampsExample::pending_links(&alice_iban, alice).unwrap().amount;
+		assert!((1..100).contains(&amount));
+
+		// Amount is between 0.1 and 0.9
+		FiatDecimals::set(1);
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+		let amount = FiatRampsExample::pending_links(&alice_iban, alice).unwrap().amount;
+		assert!((1..10).contains(&amount));
+
+		// There is no amount below a unit
+		FiatDecimals::set(0);
+		assert_noop!(
+			FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()),
+			Error::<Test>::UnsupportedFiatDecimals
+		);
+
+		// 0.99 units overflow
+		for decimals in [39, 41, u32::MAX] {
+			FiatDecimals::set(decimals);
+			assert_noop!(
+				FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()),
+				Error::<Test>::UnsupportedFiatDecimals
+			);
+		}
+
+		// 0.99 units fit
+		FiatDecimals::set(38);
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+	})
+}
+
+#[test]
+fn pending_links_to_an_iban_are_capped() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let test_accounts = get_test_accounts();
+		let alice = test_accounts[0];
+		let bob = test_accounts[1];
+		let charlie = test_accounts[2];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_eq!(<Test as Config>::MaxPendingLinks::get(), 2);
+
+		assert_ok!(FiatRampsExample::request_iban_link(Some(alice).into(), alice_iban.clone()));
+		assert_ok!(FiatRampsExample::request_iban_link(Some(bob).into(), alice_iban.clone()));
+		assert_noop!(
+			FiatRampsExample::request_iban_link(Some(charlie).into(), alice_iban.clone()),
+			Error::<Test>::TooManyPendingLinks
+		);
+
+		// Renewing a link does not count as another link
+		System::set_block_number(2);
+		assert_ok!(FiatRampsExample::request_iban_link(Some(bob).into(), alice_iban.clone()));
+		assert_eq!(PendingLinkCount::<Test>::get(&alice_iban), 2);
+
+		// Expired link frees its place
+		let expiry_block = 1 + <Test as Config>::LinkExpiry::get();
+		System::set_block_number(expiry_block + 1);
+		FiatRampsExample::on_initialize(expiry_block + 1);
+
+		assert_eq!(PendingLinkCount::<Test>::get(&alice_iban), 1);
+		assert_ok!(FiatRampsExample::request_iban_link(Some(charlie).into(), alice_iban.clone()));
+
+		// Deposit activates one of the links and removes the others
+		let link = FiatRampsExample::pending_links(&alice_iban, charlie).unwrap();
+		let reference = format!("ourRef:{}", std::str::from_utf8(&link.code).unwrap());
+		process_incoming_transaction(&alice_iban, link.amount, &reference);
+
+		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), Some(charlie));
+		assert!(FiatRampsExample::pending_links(&alice_iban, bob).is_none());
+		assert!(!PendingLinkCount::<Test>::contains_key(&alice_iban));
+	})
+}
+
+#[test]
+fn reference_is_parsed() {
+	let alice =
+		AccountId::from_ss58check("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+	let parse = |reference: &str| ReferenceOf::<Test>::try_from(reference.as_bytes());
+
+	assert_eq!(
+		parse("Purp:5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY; ourRef:12"),
+		Ok(Reference { dest: Some(alice), burn_request_id: Some(12) })
+	);
+	assert_eq!(
+		parse("Purp:5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY; ourRef: none"),
+		Ok(Reference { dest: Some(alice), burn_request_id: None })
+	);
+	assert_eq!(
+		parse("Purp:None; ourRef:RF3442"),
+		Ok(Reference { dest: None, burn_request_id: Some(42) })
+	);
+	assert_eq!(
+		parse(" 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY "),
+		Ok(Reference { dest: Some(alice), burn_request_id: None })
+	);
+
+	// Structured creditor references
+	assert_eq!(parse("RF3442"), Ok(Reference { dest: None, burn_request_id: Some(42) }));
+	assert_eq!(parse("RF09 7"), Ok(Reference { dest: None, burn_request_id: Some(7) }));
+	assert_eq!(parse("RF47ABC123"), Ok(Reference { dest: None, burn_request_id: None }));
+	assert_eq!(parse("RF3542"), Err(ReferenceError::InvalidCreditorReference));
+	assert_eq!(parse("RF34-42"), Err(ReferenceError::InvalidCreditorReference));
+
+	// Free text does not carry any information
+	assert_eq!(parse(""), Ok(Reference::default()));
+	assert_eq!(parse("Invoice 2023-11"), Ok(Reference::default()));
+
+	// Malformed references
+	assert_eq!(parse("Purp:5Grwva; ourRef:12"), Err(ReferenceError::InvalidAddress));
+	assert_eq!(parse("Purp:none; ourRef:12a"), Err(ReferenceError::InvalidRequestId));
+	assert_eq!(
+		ReferenceOf::<Test>::try_from(&[0xffu8, 0xfe][..]),
+		Err(ReferenceError::InvalidUtf8)
+	);
+}
+
+#[test]
+fn amounts_are_converted_exactly() {
+	let parse = |number: &str, decimals: u32| match parse_json(number).unwrap() {
+		JsonValue::Number(number) => from_json_number(&number, decimals),
+		_ => unreachable!(),
+	};
+	let round_trip = |amount: i128, decimals: u32| {
+		let json = JsonValue::Number(to_json_number(amount, decimals).unwrap()).serialize();
+		parse(core::str::from_utf8(&json).unwrap(), decimals)
+	};
+
+	assert_eq!(parse("449.00", 10), Ok(4_490_000_000_000));
+	assert_eq!(parse("100.05", 2), Ok(10_005));
+	assert_eq!(parse("0.001", 3), Ok(1));
+	assert_eq!(parse("-12.34", 2), Ok(-1_234));
+	assert_eq!(parse("0e100", 2), Ok(0));
+
+	// Scientific notation
+	assert_eq!(parse("1.5e3", 2), Ok(150_000));
+	assert_eq!(parse("1.5E-1", 2), Ok(15));
+	assert_eq!(parse("25e-2", 2), Ok(25));
+	assert_eq!(parse("-2.5e-1", 10), Ok(-2_500_000_000));
+
+	// Amounts that can not be represented
+	assert_eq!(parse("0.001", 2), Err(AmountError::TooPrecise));
+	assert_eq!(parse("1e-3", 2), Err(AmountError::TooPrecise));
+	assert_eq!(parse("1e40", 0), Err(AmountError::Overflow));
+	assert_eq!(parse("2e38", 0), Err(AmountError::Overflow));
+	assert_eq!(to_json_number(i128::MAX, 0), Err(AmountError::Overflow));
+	assert_eq!(to_json_number(1, 40), Err(AmountError::Overflow));
+
+	for decimals in [0, 2, 10] {
+		for amount in [0, 1, -1, 10_005, -1_234, 4_490_000_000_000, 123_456_789_000] {
+			assert_eq!(round_trip(amount, decimals), Ok(amount));
+		}
+	}
+
+	// Negative amount is a reversal of the transaction
+	let transaction = parse_json(
+		r#"{"iban":"CH12","name":"Bob","currency":"EUR","amount":-10.5,"reference":"none"}"#,
+	)
+	.unwrap();
+	let transaction =
+		TransactionOf::<Test>::from_json_statement(&transaction, &TransactionType::Incoming, 2)
+			.unwrap();
+
+	assert_eq!(transaction.amount, 1_050);
+	assert_eq!(transaction.tx_type, TransactionType::Outgoing);
+}
+
+#[test]
+fn dates_are_parsed() {
+	let parse = |date: &str| match parse_json(&format!(r#"{{"date":"{}"}}"#, date)).unwrap() {
+		JsonValue::Object(obj) => extract_date("date", &obj),
+		_ => unreachable!(),
+	};
+
+	assert_eq!(parse("1970-01-01"), Some(0));
+	assert_eq!(parse("2022-02-16"), Some(1_644_969_600_000));
+	assert_eq!(parse("2024-02-29"), Some(1_709_164_800_000));
+	assert_eq!(parse("2022-02-16T11:58:29.083"), Some(1_644_969_600_000));
+
+	assert_eq!(parse("2023-02-29"), None);
+	assert_eq!(parse("2022-13-01"), None);
+	assert_eq!(parse("2022-00-10"), None);
+	assert_eq!(parse("1969-12-31"), None);
+	assert_eq!(parse("16.02.2022"), None);
+	assert_eq!(parse("2022-2-16"), None);
+}
+
+#[test]
+fn malformed_reference_is_reported_as_failed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+		let owner_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537313");
+
+		process_incoming_transaction(&bob_iban, 1000, "Purp:invalid; ourRef:none");
+
+		let owner = FiatRampsExample::get_account_id(&owner_iban).unwrap();
+
+		System::assert_has_event(
+			crate::Event::<Test>::StatementProcessed {
+				holder: owner,
+				iban: owner_iban,
+				failed_transactions: vec![0],
+			}
+			.into(),
+		);
+		assert_eq!(Balances::free_balance(owner), 0);
+	})
+}
+
+#[test]
+fn transactions_are_processed_in_their_currency() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+		let owner_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537313");
+
+		process_single_transaction(
+			&bob_iban,
+			1000,
+			"CHF",
+			"Purp:none; ourRef:none",
+			TransactionType::Incoming,
+		);
+
+		let owner = FiatRampsExample::get_account_id(&owner_iban).unwrap();
+
+		assert_eq!(Assets::balance(CHF_ASSET_ID, owner), 1000);
+		assert_eq!(Balances::free_balance(owner), 0);
+		System::assert_has_event(
+			crate::Event::<Test>::Minted {
+				who: owner,
+				iban: owner_iban.clone(),
+				amount: 1000,
+				currency: *b"CHF",
+			}
+			.into(),
+		);
+
+		// Currency without on-chain representation
+		process_single_transaction(
+			&bob_iban,
+			1000,
+			"USD",
+			"Purp:none; ourRef:none",
+			TransactionType::Incoming,
+		);
+
+		System::assert_has_event(
+			crate::Event::<Test>::StatementProcessed {
+				holder: owner,
+				iban: owner_iban,
+				failed_transactions: vec![0],
+			}
+			.into(),
+		);
+		assert_eq!(Assets::balance(CHF_ASSET_ID, owner), 1000);
+
+		assert_noop!(
+			FiatRampsExample::transfer(
+				Some(owner).into(),
+				100,
+				*b"USD",
+				TransferDestination::Withdraw
+			),
+			Error::<Test>::UnsupportedCurrency
+		);
+
+		// Burn request escrows the asset
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(owner).into(),
+			Some(get_mock_beneficiary::<Test>("Owner"))
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(owner).into(),
+			400,
+			*b"CHF",
+			TransferDestination::Withdraw
+		));
+
+		assert_eq!(Assets::balance(CHF_ASSET_ID, owner), 600);
+		assert_eq!(Assets::balance(CHF_ASSET_ID, FiatRampsExample::account_id()), 400);
+		assert_eq!(FiatRampsExample::burn_requests(0).unwrap().currency, *b"CHF");
+		assert_eq!(EscrowedFunds::<Test>::get(owner, *b"CHF"), 400);
+
+		// Balance is reconciled in the currency of the statement, including escrowed funds
+		let process_balance = |balance, currency| {
+			let batch_id = FiatRampsExample::statement_batch_count();
+			let bank_account = BankAccountOf::<Test> {
+				balance,
+				currency,
+				..BankAccountOf::<Test>::from(&owner_iban)
+			};
+			assert_ok!(FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(OcwAccount::get()),
+				string_to_bounded_vec(&format!("receipt-{}.json", batch_id)),
+				vec![(bank_account, Default::default())].try_into().unwrap(),
+			));
+			assert_ok!(FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(OcwAccount::get()),
+				batch_id,
+				IMAGE_ID,
+				queued_journal(batch_id),
+				false
+			));
+		};
+		let mismatches = || {
+			System::events()
+				.into_iter()
+				.filter(|record| {
+					matches!(
+						record.event,
+						RuntimeEvent::FiatRampsExample(crate::Event::ReconciliationMismatch { .. })
+					)
+				})
+				.count()
+		};
+
+		System::reset_events();
+		process_balance(1000, Some(*b"CHF"));
+		assert_eq!(mismatches(), 0);
+		assert_eq!(FiatRampsExample::account_of(owner).unwrap().currency, Some(*b"CHF"));
+
+		// Statement without currency is reconciled in the currency of the previous one
+		process_balance(1000, None);
+		assert_eq!(mismatches(), 0);
+
+		process_balance(1000, Some(*b"EUR"));
+		System::assert_has_event(
+			crate::Event::<Test>::ReconciliationMismatch { iban: owner_iban, bank: 1000, chain: 0 }
+				.into(),
+		);
+
+		// Cancelled burn request is no longer held in escrow
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(owner).into(), 0));
+		assert!(!EscrowedFunds::<Test>::contains_key(owner, *b"CHF"));
+	})
+}
+
+#[test]
+fn migration_from_unversioned_storage() {
+	new_test_ext().execute_with(|| {
+		let expiry = <Test as Config>::StatementsExpiry::get();
+		let test_accounts = get_test_accounts();
+		let (alice, bob) = (test_accounts[0], test_accounts[1]);
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+		let receipt_url = string_to_bounded_vec::<MaxStringLength>("abcd.json");
+		let reference = "Purp:none; ourRef:none";
+		fn put(item: &[u8], hash: &[u8], value: impl Encode) {
+			frame_support::migration::put_storage_value(b"FiatRampsExample", item, hash, value)
+		}
+		System::set_block_number(expiry + 10);
+
+		// Bank account without reverse index, opening balance, currency and beneficiary
+		let old_account = (&alice_iban, 1000u128, 7u64);
+		put(b"Accounts", &Blake2_128Concat::hash(&alice.encode()), old_account);
+		// Burn requests without burner account, beneficiary, currency and status
+		for (id, dest_iban, amount) in [(0u64, &bob_iban, 300u128), (1, &alice_iban, 200)] {
+			put(
+				b"BurnRequests",
+				&Blake2_128Concat::hash(&id.encode()),
+				(id, &alice_iban, dest_iban, amount),
+			);
+		}
+		// Single batch of statements, queued before it expired, with transactions without
+		// entry reference and dates
+		let old_transaction = (
+			&bob_iban,
+			b"Bob".to_vec(),
+			b"EUR".to_vec(),
+			100u128,
+			reference,
+			TransactionType::Incoming,
+		);
+		put(
+			b"QueuedStatements",
+			&[],
+			(1u64, vec![(old_account, vec![old_transaction])], &receipt_url),
+		);
+		put(b"Risc0ImageId", &[], IMAGE_ID);
+
+		// Storage that did not exist before the migration
+		OcwAuthorities::<Test>::kill();
+		crate::AttestationThreshold::<Test>::kill();
+		UnpegAuthority::<Test>::kill();
+		StorageVersion::new(0).put::<FiatRampsExample>();
+
+		crate::migrations::v1::MigrateToV1::<Test, OcwAccount>::on_runtime_upgrade();
+
+		assert_eq!(FiatRampsExample::on_chain_storage_version(), 1);
+
+		let bank_account = BankAccountOf::<Test> {
+			iban: alice_iban.clone(),
+			opening_balance: 0,
+			balance: 1000,
+			currency: None,
+			last_updated: 7,
+			beneficiary: None,
+		};
+		assert_eq!(FiatRampsExample::account_of(alice), Some(bank_account.clone()));
+		assert_eq!(FiatRampsExample::get_account_id(&alice_iban), Some(alice));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(
+			(request.burner, request.burner_account, request.dest_iban, request.amount),
+			(alice_iban.clone(), alice, bob_iban.clone(), 300)
+		);
+		assert_eq!(
+			(request.currency, request.status, request.updated_at),
+			(*b"EUR", BurnRequestStatus::Pending, expiry + 10)
+		);
+		// Unpeg request of an empty beneficiary is rejected, so the request is refunded
+		assert!(request.beneficiary.name.is_empty() && !request.beneficiary.is_valid());
+		assert_eq!(FiatRampsExample::burn_requests(1).unwrap().amount, 200);
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 500);
+
+		let transaction = TransactionOf::<Test> {
+			iban: bob_iban.clone(),
+			name: string_to_bounded_vec("Bob"),
+			currency: string_to_bounded_vec("EUR"),
+			amount: 100,
+			reference: string_to_bounded_vec(reference),
+			tx_type: TransactionType::Incoming,
+			entry_reference: None,
+			booking_date: 0,
+			validation_date: 0,
+		};
+		assert_eq!(
+			FiatRampsExample::queued_statements(0),
+			Some(crate::QueuedStatementsInfo {
+				block_number: 1,
+				statements: vec![(bank_account, vec![transaction].try_into().unwrap())]
+					.try_into()
+					.unwrap(),
+				receipt_url: receipt_url.clone(),
+			})
 		);
+		assert_eq!(FiatRampsExample::statement_batch_count(), 1);
+		assert_eq!(QueuedReceipts::<Test>::get(&receipt_url), Some(0));
+		// Expired batch is removed in the next block
+		assert_eq!(StatementsExpiring::<Test>::get(expiry + 11), vec![0]);
+
+		let image_id = Risc0ImageIds::<Test>::get(IMAGE_ID).unwrap();
+		assert_eq!((image_id.activation, image_id.sunset), (0, None));
+		assert_eq!(Risc0ImageIds::<Test>::count(), 1);
+
+		assert_eq!(FiatRampsExample::ocw_authorities().into_inner(), vec![OcwAccount::get()]);
+		assert_eq!(FiatRampsExample::attestation_threshold(), 1);
+		assert_eq!(FiatRampsExample::unpeg_authority(), Some(OcwAccount::get()));
+
+		FiatRampsExample::on_initialize(expiry + 11);
+		assert!(FiatRampsExample::queued_statements(0).is_none());
+		assert!(!QueuedReceipts::<Test>::contains_key(&receipt_url));
+
+		// Migration is applied once
+		crate::migrations::v1::MigrateToV1::<Test, OcwAccount>::on_runtime_upgrade();
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 500);
+	});
+}
+
+#[test]
+fn test_burn_request() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = sp_keystore::testing::MemoryKeystore::new();
+
+	keystore
+		.sr25519_generate_new(
+			crate::crypto::Public::ID,
+			Some(&format!(
+				"{}/alice",
+				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+			)),
+		)
+		.unwrap();
+
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let test_accounts = get_test_accounts();
+
+	let alice = test_accounts[0].clone();
+	let bob = test_accounts[1].clone();
+	let charlie = test_accounts[2].clone();
+
+	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+	let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+	let charlie_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537313");
+
+	{
+		let mock_unpeg_request = unpeg_request::<Test>(
+			&format!("{:?}", bob.to_ss58check()),
+			10000,
+			b"EUR",
+			&bob_iban,
+			&get_mock_beneficiary::<Test>("Bob"),
+			&"0".to_string(),
+		)
+		.unwrap();
+
+		let mock_unpeg_request_1 = unpeg_request::<Test>(
+			&format!("{:?}", charlie.to_ss58check()),
+			100,
+			b"EUR",
+			&charlie_iban,
+			&get_mock_beneficiary::<Test>("Charlie"),
+			&"1".to_string(),
+		)
+		.unwrap();
+
+		let mock_unpeg_request_2 = unpeg_request::<Test>(
+			&format!("{:?}", charlie.to_ss58check()),
+			1000,
+			b"EUR",
+			&charlie_iban,
+			&get_mock_beneficiary::<Test>("Charlie"),
+			&"2".to_string(),
+		)
+		.unwrap();
+
+		let unpeg_endpoint = "http://w.e36.io:8093/ebics/api-v1/unpeg";
+
+		// Requests wait for their report, so every run sends the new request
+		for request in [mock_unpeg_request, mock_unpeg_request_1, mock_unpeg_request_2] {
+			ebics_server_response(
+				&mut state.write(),
+				testing::PendingRequest {
+					uri: unpeg_endpoint.to_string(),
+					method: "POST".to_string(),
+					body: request.serialize(),
+					response: Some(request.serialize()),
+					headers: [
+						("Content-Type".to_string(), "application/json".to_string()),
+						("accept".to_string(), "*/*".to_string()),
+					]
+					.to_vec(),
+					sent: true,
+					..Default::default()
+				},
+			);
+		}
 	}
 
 	t.execute_with(|| {
+		set_local_ocw_authority();
 		// Local counter to keep track of the number of burn requests
 		fn check_burn_request(
 			initial_pallet_balance: u128,
@@ -500,6 +1606,8 @@ fn test_burn_request() {
 				burner.clone()
 			);
 			assert_eq!(burn_request.dest_iban, *dest_iban);
+			assert_eq!(burn_request.burner_account, *burner);
+			assert_eq!(burn_request.status, BurnRequestStatus::Pending);
 
 			// Burn amount should be transfered to Pallet's account
 			// Pallet's accounts serves as the treasury of unpegged funds
@@ -509,24 +1617,46 @@ fn test_burn_request() {
 				Balances::free_balance(FiatRampsExample::account_id()),
 				initial_pallet_balance + amount
 			);
+		}
 
-			// Trigger processing of burn requests
+		// Trigger processing of burn requests, the new request is claimed first and sent in
+		// the next run. Requests that were already sent are not sent again
+		let send_burn_request = |request_id: u64| {
 			assert_ok!(FiatRampsExample::process_burn_requests());
-		}
+			assert_eq!(
+				apply_unpeg_claim(pool_state.write().transactions.pop().unwrap()),
+				vec![request_id]
+			);
+			assert_eq!(
+				FiatRampsExample::burn_requests(request_id).unwrap().status,
+				BurnRequestStatus::InFlight { attempts: 0 }
+			);
+			assert_ok!(FiatRampsExample::process_burn_requests());
+		};
 
 		// map Alice iban
 		assert_ok!(FiatRampsExample::create_account(
-			Some(alice.clone()).into(),
+			RuntimeOrigin::root(),
+			alice.clone(),
 			alice_iban.clone(),
 		));
 		// map Bob iban
-		assert_ok!(FiatRampsExample::create_account(Some(bob.clone()).into(), bob_iban.clone(),));
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			bob.clone(),
+			bob_iban.clone(),
+		));
 
 		// map Charlie iban
 		assert_ok!(FiatRampsExample::create_account(
-			Some(charlie.clone()).into(),
+			RuntimeOrigin::root(),
+			charlie.clone(),
 			charlie_iban.clone(),
 		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(charlie.clone()).into(),
+			Some(get_mock_beneficiary::<Test>("Charlie"))
+		));
 
 		// Pallet's balance before unpeg request
 		let initial_pallet_balance = Balances::free_balance(FiatRampsExample::account_id());
@@ -534,10 +1664,12 @@ fn test_burn_request() {
 		assert_ok!(FiatRampsExample::transfer(
 			Some(alice.clone()).into(),
 			10000,
-			TransferDestination::Iban(bob_iban.clone())
+			*b"EUR",
+			TransferDestination::Iban(bob_iban.clone(), get_mock_beneficiary::<Test>("Bob"))
 		));
 
 		check_burn_request(initial_pallet_balance, 0, 10000, &alice, &bob_iban);
+		send_burn_request(0);
 
 		// Pallet's balance before unpeg request
 		let initial_pallet_balance = Balances::free_balance(FiatRampsExample::account_id());
@@ -545,10 +1677,12 @@ fn test_burn_request() {
 		assert_ok!(FiatRampsExample::transfer(
 			Some(bob.clone()).into(),
 			100,
+			*b"EUR",
 			TransferDestination::Address(charlie.clone())
 		));
 
 		check_burn_request(initial_pallet_balance, 1, 100, &bob, &charlie_iban);
+		send_burn_request(1);
 
 		// Pallet's balance before unpeg request
 		let initial_pallet_balance = Balances::free_balance(FiatRampsExample::account_id());
@@ -557,48 +1691,2506 @@ fn test_burn_request() {
 		assert_ok!(FiatRampsExample::transfer(
 			Some(charlie.clone()).into(),
 			1000,
+			*b"EUR",
 			TransferDestination::Withdraw
 		));
 
 		check_burn_request(initial_pallet_balance, 2, 1000, &charlie, &charlie_iban);
+		send_burn_request(2);
 	})
 }
 
 #[test]
-fn process_statements_is_permissioned() {
+fn burn_requests_are_sent_in_batches() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = sp_keystore::testing::MemoryKeystore::new();
+
+	keystore
+		.sr25519_generate_new(
+			crate::crypto::Public::ID,
+			Some(&format!(
+				"{}/alice",
+				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+			)),
+		)
+		.unwrap();
+
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let alice = get_test_accounts()[0];
+	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+	let json_request = |request_id: u64, amount: u128| {
+		unpeg_request::<Test>(
+			&format!("{:?}", alice.to_ss58check()),
+			amount,
+			b"EUR",
+			&alice_iban,
+			&get_mock_beneficiary::<Test>("Alice"),
+			&request_id.to_string(),
+		)
+		.unwrap()
+	};
+	let expect_batch = |requests: Vec<JsonValue>, response: Vec<u8>| {
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				uri: "http://w.e36.io:8093/ebics/api-v1/unpeg/batch".to_string(),
+				method: "POST".to_string(),
+				body: JsonValue::Array(requests).serialize(),
+				response: Some(response),
+				headers: [
+					("Content-Type".to_string(), "application/json".to_string()),
+					("accept".to_string(), "*/*".to_string()),
+				]
+				.to_vec(),
+				sent: true,
+				..Default::default()
+			},
+		);
+	};
+	let reported_results = || {
+		let tx = pool_state.write().transactions.pop().unwrap();
+		match Extrinsic::decode(&mut &*tx).unwrap().call {
+			RuntimeCall::FiatRampsExample(crate::Call::report_unpeg_results { results }) => results,
+			call => panic!("Unexpected call {:?}", call),
+		}
+	};
+
+	t.execute_with(|| {
+		set_local_ocw_authority();
+		MaxUnpegBatch::set(2);
+		assert_ok!(FiatRampsExample::set_unpeg_format(
+			RuntimeOrigin::root(),
+			PaymentFormat::JsonBatch
+		));
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		for amount in [1000, 2000, 3000] {
+			assert_ok!(FiatRampsExample::transfer(
+				Some(alice).into(),
+				amount,
+				*b"EUR",
+				TransferDestination::Withdraw
+			));
+		}
+
+		// Oldest requests are claimed first
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(apply_unpeg_claim(pool_state.write().transactions.pop().unwrap()), vec![0, 1]);
+
+		// Claimed requests are sent, the outcome of requests missing from the response is
+		// unknown. No more requests are claimed while a full batch is in flight
+		expect_batch(
+			vec![json_request(0, 1000), json_request(1, 2000)],
+			get_mock_unpeg_response(&[(0, true)]),
+		);
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		let results = reported_results();
+		assert_eq!(results, vec![(0, UnpegOutcome::Accepted), (1, UnpegOutcome::Unknown)]);
+		assert!(pool_state.read().transactions.is_empty());
+
+		// Sent requests are not sent again until their outcome is reported
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert!(pool_state.read().transactions.is_empty());
+
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(sp_io::crypto::sr25519_public_keys(crate::crypto::Public::ID)[0]),
+			results
+		));
+
+		// Rest is claimed and sent in the next runs
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(apply_unpeg_claim(pool_state.write().transactions.pop().unwrap()), vec![2]);
+
+		expect_batch(vec![json_request(2, 3000)], get_mock_unpeg_response(&[(2, false)]));
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(reported_results(), vec![(2, UnpegOutcome::Rejected)]);
+
+		// Nothing is due until the results are reported
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert!(pool_state.read().transactions.is_empty());
+	});
+
+	// Unexpected entries of the response are ignored
+	let response = parse_json(
+		r#"[{"ourReference":"3","success":true},{"ourReference":"x","success":true},
+		{"success":false},{"ourReference":"4"},{"ourReference":"5","success":false},true]"#,
+	)
+	.unwrap();
+	assert_eq!(parse_unpeg_results(&response), vec![(3, true), (5, false)]);
+}
+
+#[test]
+fn test_pain001_request() {
 	new_test_ext().execute_with(|| {
-		let test_accounts = get_test_accounts();
+		let alice =
+			AccountId::from_ss58check("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+		let debtor = get_mock_beneficiary::<Test>("Alice");
 
-		assert_noop!(
-			FiatRampsExample::process_statements(RuntimeOrigin::signed(test_accounts[2]),),
-			Error::<Test>::UnauthorizedCall,
+		let mut request = BurnRequest {
+			id: 7,
+			burner: string_to_bounded_vec("CH2108307000289537320"),
+			burner_account: get_test_accounts()[0],
+			dest_iban: string_to_bounded_vec("CH1230116000289537312"),
+			beneficiary: get_mock_beneficiary::<Test>("Bob & Co"),
+			// 1234.5 with 10 decimals
+			amount: 12_345_000_000_000,
+			currency: *b"CHF",
+			status: BurnRequestStatus::Pending,
+			updated_at: 1,
+		};
+		let purpose = alice.to_ss58check();
+		let transfer =
+			CreditTransfer { request: &request, debtor: Some(&debtor), purpose: &purpose };
+
+		// 2024-02-29T13:45:30
+		let message = pain001_request::<Test>("MSG-1", 1_709_214_330_000, &[transfer]).unwrap();
+
+		let document = xml::parse(&message).unwrap();
+		let initiation = document.child("CstmrCdtTrfInitn").unwrap();
+		let text = |element: &xml::Element, path: &[&str]| element.path(path).unwrap().text.clone();
+
+		assert_eq!(text(initiation, &["GrpHdr", "MsgId"]), b"MSG-1");
+		assert_eq!(text(initiation, &["GrpHdr", "CreDtTm"]), b"2024-02-29T13:45:30");
+		assert_eq!(text(initiation, &["GrpHdr", "NbOfTxs"]), b"1");
+		assert_eq!(text(initiation, &["GrpHdr", "CtrlSum"]), b"1234.5");
+
+		let payment = initiation.child("PmtInf").unwrap();
+		assert_eq!(text(payment, &["ReqdExctnDt"]), b"2024-02-29");
+		assert_eq!(text(payment, &["Dbtr", "Nm"]), b"Alice");
+		assert_eq!(text(payment, &["DbtrAcct", "Id", "IBAN"]), b"CH2108307000289537320");
+		assert_eq!(text(payment, &["DbtrAgt", "FinInstnId", "BIC"]), b"HYPLCH22");
+
+		// Request id is the end-to-end id of the transfer
+		let transaction = payment.child("CdtTrfTxInf").unwrap();
+		assert_eq!(text(transaction, &["PmtId", "EndToEndId"]), b"7");
+		assert_eq!(text(transaction, &["Amt", "InstdAmt"]), b"1234.5");
+		assert_eq!(
+			transaction.path(&["Amt", "InstdAmt"]).unwrap().attribute("Ccy"),
+			Some(&b"CHF"[..])
 		);
+		assert_eq!(text(transaction, &["Cdtr", "Nm"]), b"Bob & Co");
+		assert_eq!(text(transaction, &["Cdtr", "PstlAdr", "Ctry"]), b"CH");
+		assert_eq!(text(transaction, &["CdtrAcct", "Id", "IBAN"]), b"CH1230116000289537312");
 
-		QueuedStatements::<Test>::put(crate::QueuedStatementsInfo {
-			statements: vec![].try_into().unwrap(),
-			block_number: 0,
-			receipt_url: vec![0u8; 32].try_into().unwrap(),
-		});
+		// Remittance information matches the outgoing transaction to the burn request
+		assert_eq!(
+			ReferenceOf::<Test>::try_from(&text(transaction, &["RmtInf", "Ustrd"])[..]),
+			Ok(Reference { dest: Some(alice), burn_request_id: Some(7) })
+		);
+
+		// Banks can not transfer fractions of a cent
+		request.amount = 12_345_678_900;
+		let transfer = CreditTransfer { request: &request, debtor: None, purpose: &purpose };
+		assert_eq!(
+			pain001_request::<Test>("MSG-2", 1_709_214_330_000, &[transfer]),
+			Err(AmountError::TooPrecise)
+		);
 
-		assert_ok!(FiatRampsExample::process_statements(RuntimeOrigin::signed(
-			<Test as Config>::OcwAccount::get()
-		),));
+		// Message ids are unique per block and fit into `MsgId`
+		assert_eq!(pain001_message_id(1u64, &[7]).len(), 32);
+		assert_ne!(pain001_message_id(1u64, &[7]), pain001_message_id(2u64, &[7]));
 	});
 }
 
 #[test]
-fn set_risc0_image_id() {
+fn test_burn_request_pain001() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = sp_keystore::testing::MemoryKeystore::new();
+
+	keystore
+		.sr25519_generate_new(
+			crate::crypto::Public::ID,
+			Some(&format!(
+				"{}/alice",
+				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+			)),
+		)
+		.unwrap();
+
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let test_accounts = get_test_accounts();
+
+	let alice = test_accounts[0].clone();
+	let bob = test_accounts[1].clone();
+
+	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+	let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+
+	t.execute_with(|| {
+		set_local_ocw_authority();
+		System::set_block_number(1);
+		Timestamp::set_timestamp(1_709_214_330_000);
+
+		assert_noop!(
+			FiatRampsExample::set_unpeg_format(Some(alice).into(), PaymentFormat::Pain001),
+			BadOrigin
+		);
+		assert_ok!(FiatRampsExample::set_unpeg_format(
+			RuntimeOrigin::root(),
+			PaymentFormat::Pain001
+		));
+		assert_eq!(FiatRampsExample::unpeg_format(), PaymentFormat::Pain001);
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice.clone(),
+			alice_iban.clone(),
+		));
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			bob.clone(),
+			bob_iban.clone(),
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice.clone()).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice.clone()).into(),
+			15_000_000_000,
+			*b"EUR",
+			TransferDestination::Iban(bob_iban.clone(), get_mock_beneficiary::<Test>("Bob"))
+		));
+
+		// Burn request is sent as pain.001 message from Alice's IBAN
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		let debtor = get_mock_beneficiary::<Test>("Alice");
+		let purpose = bob.to_ss58check();
+		let transfer =
+			CreditTransfer { request: &request, debtor: Some(&debtor), purpose: &purpose };
+		let message = pain001_request::<Test>(
+			&pain001_message_id(1u64, &[0]),
+			1_709_214_330_000,
+			&[transfer],
+		)
+		.unwrap();
+
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				uri: "http://w.e36.io:8093/ebics/api-v1/pain001".to_string(),
+				method: "POST".to_string(),
+				body: message,
+				response: Some(b"OK".to_vec()),
+				headers: [
+					("Content-Type".to_string(), "application/xml".to_string()),
+					("accept".to_string(), "*/*".to_string()),
+				]
+				.to_vec(),
+				sent: true,
+				..Default::default()
+			},
+		);
+
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(apply_unpeg_claim(pool_state.write().transactions.pop().unwrap()), vec![0]);
+		assert_ok!(FiatRampsExample::process_burn_requests());
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(
+			tx.call,
+			crate::Call::report_unpeg_results { results: vec![(0, UnpegOutcome::Accepted)] }.into()
+		);
+	})
+}
+
+#[test]
+fn failed_burn_request_is_retried_and_refunded() {
 	new_test_ext().execute_with(|| {
-		let test_accounts = get_test_accounts();
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let alice = get_test_accounts()[0];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		let initial_balance = Balances::free_balance(alice);
+
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::Pending);
+		assert!(FiatRampsExample::is_unpeg_due(&request, 1));
 
 		assert_noop!(
-			FiatRampsExample::set_risc0_image_id(
-				RuntimeOrigin::signed(test_accounts[2]),
-				[0u8; 32]
+			FiatRampsExample::claim_unpeg_requests(Some(alice).into(), vec![0]),
+			Error::<Test>::UnauthorizedCall
+		);
+		assert_noop!(
+			FiatRampsExample::claim_unpeg_requests(
+				RuntimeOrigin::signed(ocw_account),
+				(0..=MaxUnpegBatch::get() as u64).collect()
 			),
-			BadOrigin
+			Error::<Test>::UnpegBatchTooLarge
+		);
+		assert_noop!(
+			FiatRampsExample::report_unpeg_results(
+				Some(alice).into(),
+				vec![(0, UnpegOutcome::Rejected)]
+			),
+			Error::<Test>::UnauthorizedCall
+		);
+
+		// Reports of requests that are not in flight are ignored
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Rejected)]
+		));
+		assert_eq!(FiatRampsExample::burn_requests(0).unwrap().status, BurnRequestStatus::Pending);
+
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::InFlight { attempts: 0 });
+		assert!(!FiatRampsExample::is_unpeg_due(&request, 100));
+
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Rejected)]
+		));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::Failed { attempts: 1 });
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestStatusChanged {
+				request_id: 0,
+				status: BurnRequestStatus::Failed { attempts: 1 },
+			}
+			.into(),
+		);
+
+		// First retry waits `UnpegRetryDelay` blocks
+		assert!(!FiatRampsExample::is_unpeg_due(&request, 2));
+		assert!(FiatRampsExample::is_unpeg_due(&request, 3));
+
+		// Requests that are not due are not claimed
+		System::set_block_number(2);
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::Failed { attempts: 1 }
+		);
+
+		System::set_block_number(3);
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::InFlight { attempts: 1 }
+		);
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Rejected)]
+		));
+
+		// Delay doubles after every failure
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::Failed { attempts: 2 });
+		assert!(!FiatRampsExample::is_unpeg_due(&request, 6));
+		assert!(FiatRampsExample::is_unpeg_due(&request, 7));
+
+		// Last attempt fails, funds are returned to the burner
+		System::set_block_number(7);
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Rejected)]
+		));
+
+		assert!(FiatRampsExample::burn_requests(0).is_none());
+		assert_eq!(Balances::free_balance(alice), initial_balance);
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestStatusChanged {
+				request_id: 0,
+				status: BurnRequestStatus::Refunded,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn failed_refund_is_not_retried() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let alice = get_test_accounts()[0];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		let initial_balance = Balances::free_balance(alice);
+
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+
+		// Last attempt is in flight
+		crate::BurnRequests::<Test>::mutate(0, |request| {
+			request.as_mut().unwrap().status =
+				BurnRequestStatus::InFlight { attempts: MaxUnpegAttempts::get() - 1 };
+		});
+
+		// Pallet account can not pay out the refund
+		let pallet_balance = Balances::free_balance(FiatRampsExample::account_id());
+		assert_ok!(Balances::force_set_balance(
+			RuntimeOrigin::root(),
+			FiatRampsExample::account_id(),
+			0
+		));
+
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Rejected)]
+		));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::RefundFailed);
+		System::assert_last_event(
+			crate::Event::<Test>::BurnRequestStatusChanged {
+				request_id: 0,
+				status: BurnRequestStatus::RefundFailed,
+			}
+			.into(),
+		);
+
+		// Request is not sent again
+		assert!(!FiatRampsExample::is_unpeg_due(&request, 1000));
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::RefundFailed
+		);
+
+		// Burner retries the refund
+		assert_ok!(Balances::force_set_balance(
+			RuntimeOrigin::root(),
+			FiatRampsExample::account_id(),
+			pallet_balance
+		));
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(alice).into(), 0));
+
+		assert!(FiatRampsExample::burn_requests(0).is_none());
+		assert_eq!(Balances::free_balance(alice), initial_balance);
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 0);
+	});
+}
+
+#[test]
+fn submitted_burn_request_is_confirmed_by_statement() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let alice = get_test_accounts()[0];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Accepted)]
+		));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::Submitted);
+		assert!(!FiatRampsExample::is_unpeg_due(&request, 100));
+
+		// Late reports of a submitted request are ignored
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Rejected), (1, UnpegOutcome::Rejected)]
+		));
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::Submitted
+		);
+
+		// Withdrawal is debited from Alice's IBAN
+		process_statement_transaction(
+			&alice_iban,
+			&alice_iban,
+			1000,
+			"EUR",
+			"Purp:none; ourRef:0",
+			TransactionType::Outgoing,
 		);
 
-		assert_ok!(FiatRampsExample::set_risc0_image_id(RuntimeOrigin::root(), [0u8; 32]));
+		assert!(FiatRampsExample::burn_requests(0).is_none());
+		assert_eq!(Balances::free_balance(FiatRampsExample::account_id()), 0);
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestStatusChanged {
+				request_id: 0,
+				status: BurnRequestStatus::Confirmed,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn burn_request_is_confirmed_only_by_matching_transaction() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let test_accounts = get_test_accounts();
+
+		let alice = test_accounts[0];
+		let bob = test_accounts[1];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+		let unknown_iban: IbanOf<Test> = string_to_bounded_vec("CH9300762011623852957");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::create_account(RuntimeOrigin::root(), bob, bob_iban.clone()));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Iban(bob_iban.clone(), get_mock_beneficiary::<Test>("Bob"))
+		));
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Accepted)]
+		));
+
+		// Debit from another IBAN does not confirm the request
+		process_statement_transaction(
+			&bob_iban,
+			&unknown_iban,
+			1000,
+			"EUR",
+			"Purp:none; ourRef:0",
+			TransactionType::Outgoing,
+		);
+		// Neither does a debit of another amount
+		process_statement_transaction(
+			&alice_iban,
+			&unknown_iban,
+			999,
+			"EUR",
+			"Purp:none; ourRef:0",
+			TransactionType::Outgoing,
+		);
+
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::Submitted
+		);
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 1000);
+		assert_eq!(Balances::free_balance(FiatRampsExample::account_id()), 1000);
+
+		let alice_balance = Balances::free_balance(alice);
+		let bob_balance = Balances::free_balance(bob);
+
+		process_statement_transaction(
+			&alice_iban,
+			&bob_iban,
+			1000,
+			"EUR",
+			"Purp:none; ourRef:0",
+			TransactionType::Outgoing,
+		);
+
+		// Escrowed funds of the request are paid to Bob, Alice does not pay again
+		assert!(FiatRampsExample::burn_requests(0).is_none());
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 0);
+		assert_eq!(Balances::free_balance(FiatRampsExample::account_id()), 0);
+		assert_eq!(Balances::free_balance(alice), alice_balance);
+		assert_eq!(Balances::free_balance(bob), bob_balance + 1000);
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestStatusChanged {
+				request_id: 0,
+				status: BurnRequestStatus::Confirmed,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn burn_request_stays_when_settlement_fails() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let alice = get_test_accounts()[0];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+		let unknown_iban: IbanOf<Test> = string_to_bounded_vec("CH9300762011623852957");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Iban(unknown_iban.clone(), get_mock_beneficiary::<Test>("Bob"))
+		));
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(0, UnpegOutcome::Accepted)]
+		));
+
+		// Pallet account can not burn the escrowed funds
+		let pallet_balance = Balances::free_balance(FiatRampsExample::account_id());
+		assert_ok!(Balances::force_set_balance(
+			RuntimeOrigin::root(),
+			FiatRampsExample::account_id(),
+			0
+		));
+
+		process_statement_transaction(
+			&alice_iban,
+			&unknown_iban,
+			1000,
+			"EUR",
+			"Purp:none; ourRef:0",
+			TransactionType::Outgoing,
+		);
+
+		// Request keeps its status and its funds stay in escrow
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::Submitted
+		);
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 1000);
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestSettlementFailed {
+				request_id: 0,
+				error: crate::Error::<Test>::InsufficientBalance.into(),
+			}
+			.into(),
+		);
+		System::assert_has_event(
+			crate::Event::<Test>::StatementProcessed {
+				holder: alice,
+				iban: alice_iban.clone(),
+				failed_transactions: vec![0],
+			}
+			.into(),
+		);
+
+		// Transaction is not marked as processed, so it confirms the request when it appears
+		// in a later statement
+		assert_ok!(Balances::force_set_balance(
+			RuntimeOrigin::root(),
+			FiatRampsExample::account_id(),
+			pallet_balance
+		));
+		process_statement_transaction(
+			&alice_iban,
+			&unknown_iban,
+			1000,
+			"EUR",
+			"Purp:none; ourRef:0",
+			TransactionType::Outgoing,
+		);
+
+		assert!(FiatRampsExample::burn_requests(0).is_none());
+		assert_eq!(EscrowedFunds::<Test>::get(alice, *b"EUR"), 0);
+		assert_eq!(Balances::free_balance(FiatRampsExample::account_id()), 0);
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestStatusChanged {
+				request_id: 0,
+				status: BurnRequestStatus::Confirmed,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn burn_request_can_be_cancelled_before_submission() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let test_accounts = get_test_accounts();
+
+		let alice = test_accounts[0];
+		let bob = test_accounts[1];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		let initial_balance = Balances::free_balance(alice);
+
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			500,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(alice).into(), 2),
+			Error::<Test>::BurnRequestNotFound
+		);
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(bob).into(), 0),
+			Error::<Test>::NotBurnRequestOwner
+		);
+
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(alice).into(), 0));
+
+		assert!(FiatRampsExample::burn_requests(0).is_none());
+		assert_eq!(Balances::free_balance(alice), initial_balance - 500);
+		System::assert_has_event(
+			crate::Event::<Test>::BurnRequestCancelled {
+				request_id: 0,
+				burner: alice,
+				amount: 1000,
+			}
+			.into(),
+		);
+
+		// Request accepted by the EBICS service can not be cancelled anymore
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![1]
+		));
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(ocw_account),
+			vec![(1, UnpegOutcome::Accepted)]
+		));
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(alice).into(), 1),
+			Error::<Test>::BurnRequestAlreadySubmitted
+		);
+	});
+}
+
+#[test]
+fn burn_request_can_not_be_cancelled_while_in_flight() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = sp_keystore::testing::MemoryKeystore::new();
+
+	keystore
+		.sr25519_generate_new(
+			crate::crypto::Public::ID,
+			Some(&format!(
+				"{}/alice",
+				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+			)),
+		)
+		.unwrap();
+
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let alice = get_test_accounts()[0];
+	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+	t.execute_with(|| {
+		set_local_ocw_authority();
+		System::set_block_number(1);
+
+		let local_key = sp_io::crypto::sr25519_public_keys(crate::crypto::Public::ID)[0];
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		let initial_balance = Balances::free_balance(alice);
+
+		for amount in [1000, 500] {
+			assert_ok!(FiatRampsExample::transfer(
+				Some(alice).into(),
+				amount,
+				*b"EUR",
+				TransferDestination::Withdraw
+			));
+		}
+
+		// Request cancelled before its claim is included is never sent
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		let claim = pool_state.write().transactions.pop().unwrap();
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(alice).into(), 0));
+		assert_eq!(apply_unpeg_claim(claim), vec![0, 1]);
+		assert_eq!(
+			FiatRampsExample::burn_requests(1).unwrap().status,
+			BurnRequestStatus::InFlight { attempts: 0 }
+		);
+
+		// Claimed request might be sent at any time
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(alice).into(), 1),
+			Error::<Test>::BurnRequestInFlight
+		);
+
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				uri: "http://w.e36.io:8093/ebics/api-v1/unpeg".to_string(),
+				method: "POST".to_string(),
+				body: unpeg_request::<Test>(
+					&format!("{:?}", alice.to_ss58check()),
+					500,
+					b"EUR",
+					&alice_iban,
+					&get_mock_beneficiary::<Test>("Alice"),
+					"1",
+				)
+				.unwrap()
+				.serialize(),
+				response: Some(vec![]),
+				headers: [
+					("Content-Type".to_string(), "application/json".to_string()),
+					("accept".to_string(), "*/*".to_string()),
+				]
+				.to_vec(),
+				sent: true,
+				..Default::default()
+			},
+		);
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		let report = pool_state.write().transactions.pop().unwrap();
+
+		// Request is sent but its outcome is not reported yet
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(alice).into(), 1),
+			Error::<Test>::BurnRequestInFlight
+		);
+
+		let results = match Extrinsic::decode(&mut &*report).unwrap().call {
+			RuntimeCall::FiatRampsExample(crate::Call::report_unpeg_results { results }) => results,
+			call => panic!("Unexpected call {:?}", call),
+		};
+		assert_eq!(results, vec![(1, UnpegOutcome::Accepted)]);
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(local_key),
+			results
+		));
+
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(alice).into(), 1),
+			Error::<Test>::BurnRequestAlreadySubmitted
+		);
+		assert_eq!(Balances::free_balance(alice), initial_balance - 500);
+	});
+}
+
+/// Offchain worker externalities that record the requests and the deadline of every wait for
+/// responses
+struct RecordingOffchainExt {
+	inner: testing::TestOffchainExt,
+	waits: Arc<Mutex<Vec<(Vec<HttpRequestId>, Option<OffchainTimestamp>)>>>,
+}
+
+impl Externalities for RecordingOffchainExt {
+	fn is_validator(&self) -> bool {
+		self.inner.is_validator()
+	}
+
+	fn network_state(&self) -> Result<OpaqueNetworkState, ()> {
+		self.inner.network_state()
+	}
+
+	fn timestamp(&mut self) -> OffchainTimestamp {
+		self.inner.timestamp()
+	}
+
+	fn sleep_until(&mut self, deadline: OffchainTimestamp) {
+		self.inner.sleep_until(deadline)
+	}
+
+	fn random_seed(&mut self) -> [u8; 32] {
+		self.inner.random_seed()
+	}
+
+	fn http_request_start(
+		&mut self,
+		method: &str,
+		uri: &str,
+		meta: &[u8],
+	) -> Result<HttpRequestId, ()> {
+		self.inner.http_request_start(method, uri, meta)
+	}
+
+	fn http_request_add_header(
+		&mut self,
+		request_id: HttpRequestId,
+		name: &str,
+		value: &str,
+	) -> Result<(), ()> {
+		self.inner.http_request_add_header(request_id, name, value)
+	}
+
+	fn http_request_write_body(
+		&mut self,
+		request_id: HttpRequestId,
+		chunk: &[u8],
+		deadline: Option<OffchainTimestamp>,
+	) -> Result<(), HttpError> {
+		self.inner.http_request_write_body(request_id, chunk, deadline)
+	}
+
+	fn http_response_wait(
+		&mut self,
+		ids: &[HttpRequestId],
+		deadline: Option<OffchainTimestamp>,
+	) -> Vec<HttpRequestStatus> {
+		self.waits.lock().unwrap().push((ids.to_vec(), deadline));
+		self.inner.http_response_wait(ids, deadline)
+	}
+
+	fn http_response_headers(&mut self, request_id: HttpRequestId) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.inner.http_response_headers(request_id)
+	}
+
+	fn http_response_read_body(
+		&mut self,
+		request_id: HttpRequestId,
+		buffer: &mut [u8],
+		deadline: Option<OffchainTimestamp>,
+	) -> Result<usize, HttpError> {
+		self.inner.http_response_read_body(request_id, buffer, deadline)
+	}
+
+	fn set_authorized_nodes(&mut self, nodes: Vec<OpaquePeerId>, authorized_only: bool) {
+		self.inner.set_authorized_nodes(nodes, authorized_only)
+	}
+}
+
+#[test]
+fn json_unpeg_requests_share_a_deadline() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = sp_keystore::testing::MemoryKeystore::new();
+	let waits = Arc::new(Mutex::new(vec![]));
+
+	keystore
+		.sr25519_generate_new(
+			crate::crypto::Public::ID,
+			Some(&format!(
+				"{}/alice",
+				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+			)),
+		)
+		.unwrap();
+
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(RecordingOffchainExt {
+		inner: offchain,
+		waits: waits.clone(),
+	}));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let alice = get_test_accounts()[0];
+	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+	let amounts = [1000, 500, 200];
+
+	t.execute_with(|| {
+		set_local_ocw_authority();
+		System::set_block_number(1);
+
+		assert_eq!(FiatRampsExample::unpeg_format(), PaymentFormat::Json);
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+		for amount in amounts {
+			assert_ok!(FiatRampsExample::transfer(
+				Some(alice).into(),
+				amount,
+				*b"EUR",
+				TransferDestination::Withdraw
+			));
+		}
+
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(
+			apply_unpeg_claim(pool_state.write().transactions.pop().unwrap()),
+			vec![0, 1, 2]
+		);
+
+		for (request_id, amount) in amounts.into_iter().enumerate() {
+			ebics_server_response(
+				&mut state.write(),
+				testing::PendingRequest {
+					uri: "http://w.e36.io:8093/ebics/api-v1/unpeg".to_string(),
+					method: "POST".to_string(),
+					body: unpeg_request::<Test>(
+						&format!("{:?}", alice.to_ss58check()),
+						amount,
+						b"EUR",
+						&alice_iban,
+						&get_mock_beneficiary::<Test>("Alice"),
+						&request_id.to_string(),
+					)
+					.unwrap()
+					.serialize(),
+					response: Some(vec![]),
+					headers: [
+						("Content-Type".to_string(), "application/json".to_string()),
+						("accept".to_string(), "*/*".to_string()),
+					]
+					.to_vec(),
+					sent: true,
+					..Default::default()
+				},
+			);
+		}
+
+		let started = sp_io::offchain::timestamp();
+		assert_ok!(FiatRampsExample::process_burn_requests());
+
+		// Every request is sent on its own, but all of them are awaited until one deadline
+		let waits = waits.lock().unwrap();
+		assert_eq!(waits.len(), 1);
+		assert_eq!(waits[0].0.len(), amounts.len());
+		assert_eq!(waits[0].1, Some(started.add(Duration::from_millis(HttpTimeout::get()))));
+
+		let report = pool_state.write().transactions.pop().unwrap();
+		let results = match Extrinsic::decode(&mut &*report).unwrap().call {
+			RuntimeCall::FiatRampsExample(crate::Call::report_unpeg_results { results }) => results,
+			call => panic!("Unexpected call {:?}", call),
+		};
+		assert_eq!(
+			results,
+			vec![
+				(0, UnpegOutcome::Accepted),
+				(1, UnpegOutcome::Accepted),
+				(2, UnpegOutcome::Accepted)
+			]
+		);
+	});
+}
+
+#[test]
+fn burn_request_with_unknown_outcome_is_not_retried() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = sp_keystore::testing::MemoryKeystore::new();
+
+	keystore
+		.sr25519_generate_new(
+			crate::crypto::Public::ID,
+			Some(&format!(
+				"{}/alice",
+				"cup swing hill dinner pioneer mom stick steel sad raven oak practice"
+			)),
+		)
+		.unwrap();
+
+	let mut t = new_test_ext();
+
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let alice = get_test_accounts()[0];
+	let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+	let reported_results = || {
+		let tx = pool_state.write().transactions.pop().unwrap();
+		match Extrinsic::decode(&mut &*tx).unwrap().call {
+			RuntimeCall::FiatRampsExample(crate::Call::report_unpeg_results { results }) => results,
+			call => panic!("Unexpected call {:?}", call),
+		}
+	};
+
+	t.execute_with(|| {
+		set_local_ocw_authority();
+		System::set_block_number(1);
+		assert_ok!(FiatRampsExample::set_unpeg_format(
+			RuntimeOrigin::root(),
+			PaymentFormat::JsonBatch
+		));
+
+		let local_key = sp_io::crypto::sr25519_public_keys(crate::crypto::Public::ID)[0];
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(apply_unpeg_claim(pool_state.write().transactions.pop().unwrap()), vec![0]);
+
+		// Response of a gateway can not tell whether the batch was executed
+		ebics_server_response(
+			&mut state.write(),
+			testing::PendingRequest {
+				uri: "http://w.e36.io:8093/ebics/api-v1/unpeg/batch".to_string(),
+				method: "POST".to_string(),
+				body: JsonValue::Array(vec![unpeg_request::<Test>(
+					&format!("{:?}", alice.to_ss58check()),
+					1000,
+					b"EUR",
+					&alice_iban,
+					&get_mock_beneficiary::<Test>("Alice"),
+					"0",
+				)
+				.unwrap()])
+				.serialize(),
+				response: Some(b"<html>Bad Gateway</html>".to_vec()),
+				headers: [
+					("Content-Type".to_string(), "application/json".to_string()),
+					("accept".to_string(), "*/*".to_string()),
+				]
+				.to_vec(),
+				sent: true,
+				..Default::default()
+			},
+		);
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert_eq!(reported_results(), vec![(0, UnpegOutcome::Unknown)]);
+
+		// Lost report is sent again without sending the request again
+		System::set_block_number(3);
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		let results = reported_results();
+		assert_eq!(results, vec![(0, UnpegOutcome::Unknown)]);
+
+		assert_ok!(FiatRampsExample::report_unpeg_results(
+			RuntimeOrigin::signed(local_key),
+			results
+		));
+
+		let request = FiatRampsExample::burn_requests(0).unwrap();
+		assert_eq!(request.status, BurnRequestStatus::Unknown { attempts: 0 });
+		assert!(!FiatRampsExample::is_unpeg_due(&request, 100));
+		assert_noop!(
+			FiatRampsExample::cancel_transfer(Some(alice).into(), 0),
+			Error::<Test>::BurnRequestInFlight
+		);
+
+		System::set_block_number(100);
+		assert_ok!(FiatRampsExample::process_burn_requests());
+		assert!(pool_state.read().transactions.is_empty());
+
+		// Root resolves the outcome after checking with the bank
+		assert_noop!(
+			FiatRampsExample::resolve_burn_request(Some(alice).into(), 0, false),
+			BadOrigin
+		);
+		assert_ok!(FiatRampsExample::resolve_burn_request(RuntimeOrigin::root(), 0, false));
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::Failed { attempts: 1 }
+		);
+		assert_noop!(
+			FiatRampsExample::resolve_burn_request(RuntimeOrigin::root(), 0, true),
+			Error::<Test>::UnpegOutcomeKnown
+		);
+		assert_noop!(
+			FiatRampsExample::resolve_burn_request(RuntimeOrigin::root(), 1, true),
+			Error::<Test>::BurnRequestNotFound
+		);
+
+		// Rejected request can be cancelled
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(alice).into(), 0));
+	});
+}
+
+#[test]
+fn beneficiary_is_required_for_transfers() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let test_accounts = get_test_accounts();
+
+		let alice = test_accounts[0];
+		let bob = test_accounts[1];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+
+		let beneficiary = get_mock_beneficiary::<Test>("Alice");
+
+		assert_noop!(
+			FiatRampsExample::set_beneficiary(Some(alice).into(), Some(beneficiary.clone())),
+			Error::<Test>::AccountNotMapped
+		);
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::create_account(RuntimeOrigin::root(), bob, bob_iban.clone()));
+
+		assert_noop!(
+			FiatRampsExample::transfer(
+				Some(alice).into(),
+				1000,
+				*b"EUR",
+				TransferDestination::Withdraw
+			),
+			Error::<Test>::BeneficiaryNotSet
+		);
+
+		// Beneficiary without name, with lowercase country, without BIC for SEPA payment or with
+		// a name that is not UTF-8
+		for invalid in [
+			Beneficiary { name: Default::default(), ..beneficiary.clone() },
+			Beneficiary { name: vec![0xc3, 0x28].try_into().unwrap(), ..beneficiary.clone() },
+			Beneficiary { country: *b"ch", ..beneficiary.clone() },
+			Beneficiary { bic: string_to_bounded_vec("HYPL"), ..beneficiary.clone() },
+			Beneficiary { bic: Default::default(), national: false, ..beneficiary.clone() },
+		] {
+			assert_noop!(
+				FiatRampsExample::set_beneficiary(Some(alice).into(), Some(invalid.clone())),
+				Error::<Test>::InvalidBeneficiary
+			);
+			assert_noop!(
+				FiatRampsExample::transfer(
+					Some(alice).into(),
+					1000,
+					*b"EUR",
+					TransferDestination::Iban(bob_iban.clone(), invalid)
+				),
+				Error::<Test>::InvalidBeneficiary
+			);
+		}
+
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(beneficiary.clone())
+		));
+		System::assert_last_event(crate::Event::<Test>::BeneficiaryUpdated { who: alice }.into());
+
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+		assert_eq!(FiatRampsExample::burn_requests(0).unwrap().beneficiary, beneficiary);
+
+		// Beneficiary details are sent in the unpeg request
+		let body = unpeg_request::<Test>(
+			&format!("{:?}", alice.to_ss58check()),
+			1000,
+			b"EUR",
+			&alice_iban,
+			&beneficiary,
+			"0",
+		)
+		.unwrap()
+		.serialize();
+		let body = String::from_utf8(body).unwrap();
+
+		assert!(body.contains(r#""clearingSystemMemberId":"HYPLCH22""#));
+		assert!(body.contains(r#""receipientName":"Alice""#));
+		assert!(body.contains(r#""receipientBankName":"Hypothekarbank Lenzburg""#));
+		assert!(body.contains(r#""receipientCountry":"CH""#));
+		assert!(body.contains(r#""nationalPayment":true"#));
+
+		// Text is decoded as UTF-8
+		let zurich = Beneficiary { city: string_to_bounded_vec("Zürich"), ..beneficiary.clone() };
+		let request = unpeg_request::<Test>("", 1000, b"EUR", &alice_iban, &zurich, "0").unwrap();
+		let city = match request {
+			JsonValue::Object(fields) => fields
+				.into_iter()
+				.find(|(key, _)| key.iter().collect::<String>() == "receipientCity")
+				.map(|(_, value)| value),
+			_ => None,
+		};
+		assert_eq!(city, Some(JsonValue::String("Zürich".chars().collect())));
+
+		// Default beneficiary can be removed
+		assert_ok!(FiatRampsExample::set_beneficiary(Some(alice).into(), None));
+		assert_eq!(FiatRampsExample::account_of(alice).unwrap().beneficiary, None);
+		assert_noop!(
+			FiatRampsExample::transfer(
+				Some(bob).into(),
+				1000,
+				*b"EUR",
+				TransferDestination::Address(alice)
+			),
+			Error::<Test>::BeneficiaryNotSet
+		);
+	})
+}
+
+#[test]
+fn process_statements_is_permissioned() {
+	new_test_ext().execute_with(|| {
+		let test_accounts = get_test_accounts();
+
+		assert_noop!(
+			FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(test_accounts[2]),
+				0,
+				IMAGE_ID,
+				vec![],
+				false
+			),
+			Error::<Test>::UnauthorizedCall,
+		);
+
+		QueuedStatements::<Test>::insert(
+			0,
+			crate::QueuedStatementsInfo {
+				statements: vec![].try_into().unwrap(),
+				block_number: 0,
+				receipt_url: vec![0u8; 32].try_into().unwrap(),
+			},
+		);
+
+		let process = || {
+			FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(OcwAccount::get()),
+				0,
+				IMAGE_ID,
+				queued_journal(0),
+				false,
+			)
+		};
+
+		// Receipt has to be verified against an active image id
+		assert_noop!(process(), Error::<Test>::ImageIdNotActive);
+		add_image_id();
+		assert_ok!(process());
+	});
+}
+
+#[test]
+fn ocw_authorities_are_managed_by_root() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let test_accounts = get_test_accounts();
+		let (alice, bob) = (test_accounts[0], test_accounts[1]);
+
+		assert_noop!(
+			FiatRampsExample::add_ocw_authority(RuntimeOrigin::signed(alice), alice),
+			BadOrigin
+		);
+		assert_noop!(
+			FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), OcwAccount::get()),
+			Error::<Test>::OcwAuthorityAlreadyAdded
+		);
+		assert_ok!(FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), alice));
+		assert_ok!(FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), bob));
+		System::assert_last_event(crate::Event::<Test>::OcwAuthorityAdded { who: bob }.into());
+		assert_noop!(
+			FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), test_accounts[2]),
+			Error::<Test>::TooManyOcwAuthorities
+		);
+
+		assert_noop!(
+			FiatRampsExample::set_attestation_threshold(RuntimeOrigin::root(), 0),
+			Error::<Test>::InvalidAttestationThreshold
+		);
+		assert_noop!(
+			FiatRampsExample::set_attestation_threshold(RuntimeOrigin::root(), 4),
+			Error::<Test>::InvalidAttestationThreshold
+		);
+		assert_ok!(FiatRampsExample::set_attestation_threshold(RuntimeOrigin::root(), 3));
+		System::assert_last_event(
+			crate::Event::<Test>::AttestationThresholdSet { threshold: 3 }.into(),
+		);
+
+		// Threshold has to stay reachable
+		assert_noop!(
+			FiatRampsExample::remove_ocw_authority(RuntimeOrigin::root(), bob),
+			Error::<Test>::InvalidAttestationThreshold
+		);
+		assert_ok!(FiatRampsExample::set_attestation_threshold(RuntimeOrigin::root(), 2));
+		assert_ok!(FiatRampsExample::remove_ocw_authority(RuntimeOrigin::root(), bob));
+		System::assert_last_event(crate::Event::<Test>::OcwAuthorityRemoved { who: bob }.into());
+		assert_noop!(
+			FiatRampsExample::remove_ocw_authority(RuntimeOrigin::root(), bob),
+			Error::<Test>::NotOcwAuthority
+		);
+
+		assert_eq!(FiatRampsExample::ocw_authorities().to_vec(), vec![OcwAccount::get(), alice]);
+	});
+}
+
+#[test]
+fn batches_need_threshold_of_agreeing_authorities() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let test_accounts = get_test_accounts();
+		let (ocw_account, alice, bob) = (OcwAccount::get(), test_accounts[0], test_accounts[1]);
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::SingleStatement,
+			StatementTypes::CompleteTransactions,
+		);
+		let statements_info = batches[0].clone();
+		let queue = |who, statements: StatementsOf<Test>| {
+			FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(who),
+				statements_info.receipt_url.clone(),
+				statements,
+			)
+		};
+
+		assert_ok!(FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), alice));
+		assert_ok!(FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), bob));
+		assert_ok!(FiatRampsExample::set_attestation_threshold(RuntimeOrigin::root(), 2));
+		add_image_id();
+
+		assert_noop!(
+			queue(test_accounts[2], statements_info.statements.clone()),
+			Error::<Test>::UnauthorizedCall
+		);
+
+		// Authorities fetched different statements, neither reaches the threshold
+		assert_ok!(queue(ocw_account, statements_info.statements.clone()));
+		assert_ok!(queue(alice, Default::default()));
+		assert_eq!(FiatRampsExample::statement_batch_count(), 0);
+
+		// Bob agrees with the OCW account, Alice is reported
+		assert_ok!(queue(bob, statements_info.statements.clone()));
+		assert_eq!(
+			FiatRampsExample::queued_statements(0).unwrap().statements,
+			statements_info.statements
+		);
+		let subject = AttestationSubject::Queue(statements_info.receipt_url.clone());
+		let agreed =
+			(&statements_info.receipt_url, &statements_info.statements).using_encoded(blake2_256);
+		System::assert_has_event(
+			crate::Event::<Test>::AttestationDisagreement {
+				subject,
+				agreed,
+				disagreeing: vec![(
+					alice,
+					(&statements_info.receipt_url, StatementsOf::<Test>::default())
+						.using_encoded(blake2_256),
+				)],
+			}
+			.into(),
+		);
+
+		// Single attestation does not process the batch, attesting again does not count twice
+		let process = |who| {
+			FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(who),
+				0,
+				IMAGE_ID,
+				queued_journal(0),
+				true,
+			)
+		};
+		assert_ok!(process(ocw_account));
+		assert_ok!(process(ocw_account));
+		assert_ok!(FiatRampsExample::reject_statements(RuntimeOrigin::signed(alice), 0));
+		assert!(FiatRampsExample::queued_statements(0).is_some());
+
+		assert_ok!(process(bob));
+		assert!(FiatRampsExample::queued_statements(0).is_none());
+		System::assert_has_event(
+			crate::Event::<Test>::ProofVerified {
+				batch_id: 0,
+				image_id: IMAGE_ID,
+				statements_info,
+			}
+			.into(),
+		);
+		assert_eq!(
+			System::events()
+				.into_iter()
+				.filter(|record| matches!(
+					record.event,
+					RuntimeEvent::FiatRampsExample(crate::Event::AttestationDisagreement {
+						subject: AttestationSubject::Verification(0),
+						..
+					})
+				))
+				.count(),
+			1
+		);
+	});
+}
+
+#[test]
+fn pending_attestations_are_bounded_and_expire() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let (ocw_account, alice) = (OcwAccount::get(), get_test_accounts()[0]);
+		let queue = |who, receipt_url: &str| {
+			FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(who),
+				string_to_bounded_vec(receipt_url),
+				Default::default(),
+			)
+		};
+
+		assert_ok!(FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), alice));
+		assert_ok!(FiatRampsExample::set_attestation_threshold(RuntimeOrigin::root(), 2));
+
+		let max = <Test as Config>::MaxPendingAttestations::get();
+		for i in 0..max {
+			assert_ok!(queue(ocw_account, &format!("receipt-{}", i)));
+		}
+		assert_eq!(PendingAttestations::<Test>::get(ocw_account), max);
+
+		// Attesting a subject again does not count, a new subject exceeds the bound
+		assert_ok!(queue(ocw_account, "receipt-0"));
+		assert_noop!(queue(ocw_account, "receipt-new"), Error::<Test>::TooManyPendingAttestations);
+
+		// Subject that reaches the threshold is released for every authority
+		assert_ok!(queue(alice, "receipt-0"));
+		assert_eq!(FiatRampsExample::statement_batch_count(), 1);
+		assert_eq!(PendingAttestations::<Test>::get(ocw_account), max - 1);
+		assert_eq!(PendingAttestations::<Test>::get(alice), 0);
+		assert_ok!(queue(ocw_account, "receipt-new"));
+
+		// Attestations expire with the statements, indexed by the block they expire in
+		let expiry = <Test as Config>::StatementsExpiry::get();
+		assert_eq!(AttestationsExpiring::<Test>::get(expiry + 2).len(), max as usize + 1);
+		FiatRampsExample::on_initialize(expiry + 2);
+		assert_eq!(Attestations::<Test>::iter().count(), 0);
+		assert!(!PendingAttestations::<Test>::contains_key(ocw_account));
+		assert!(!AttestationsExpiring::<Test>::contains_key(expiry + 2));
+	});
+}
+
+#[test]
+fn unpeg_authority_is_set_by_root() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let test_accounts = get_test_accounts();
+		let (ocw_account, alice, bob) = (OcwAccount::get(), test_accounts[0], test_accounts[1]);
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_eq!(FiatRampsExample::unpeg_authority(), Some(ocw_account));
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(alice).into(),
+			1000,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+		assert_ok!(FiatRampsExample::add_ocw_authority(RuntimeOrigin::root(), alice));
+
+		// Other authorities can not claim or report, whatever the threshold
+		assert_noop!(
+			FiatRampsExample::claim_unpeg_requests(RuntimeOrigin::signed(alice), vec![0]),
+			Error::<Test>::UnauthorizedCall
+		);
+		assert_ok!(FiatRampsExample::claim_unpeg_requests(
+			RuntimeOrigin::signed(ocw_account),
+			vec![0]
+		));
+
+		assert_noop!(
+			FiatRampsExample::set_unpeg_authority(RuntimeOrigin::signed(ocw_account), alice),
+			BadOrigin
+		);
+		assert_noop!(
+			FiatRampsExample::set_unpeg_authority(RuntimeOrigin::root(), bob),
+			Error::<Test>::NotOcwAuthority
+		);
+		assert_noop!(
+			FiatRampsExample::remove_ocw_authority(RuntimeOrigin::root(), ocw_account),
+			Error::<Test>::UnpegAuthorityInUse
+		);
+
+		// Previous authority might have sent the request in flight
+		assert_ok!(FiatRampsExample::set_unpeg_authority(RuntimeOrigin::root(), alice));
+		System::assert_last_event(crate::Event::<Test>::UnpegAuthoritySet { who: alice }.into());
+		assert_eq!(
+			FiatRampsExample::burn_requests(0).unwrap().status,
+			BurnRequestStatus::Unknown { attempts: 0 }
+		);
+		assert!(InFlightBurnRequests::<Test>::get().is_empty());
+
+		assert_noop!(
+			FiatRampsExample::report_unpeg_results(
+				RuntimeOrigin::signed(ocw_account),
+				vec![(0, UnpegOutcome::Accepted)]
+			),
+			Error::<Test>::UnauthorizedCall
+		);
+		assert_ok!(FiatRampsExample::remove_ocw_authority(RuntimeOrigin::root(), ocw_account));
+	});
+}
+
+#[test]
+fn journal_must_commit_to_queued_statements() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::SingleStatement,
+			StatementTypes::CompleteTransactions,
+		);
+		let statements = batches[0].statements.clone();
+
+		assert_ok!(journal::check::<Test>(&get_mock_journal::<Test>(&statements), &statements));
+		assert_eq!(journal::check::<Test>(b"stmts", &statements), Err(JournalError::Malformed));
+		assert_eq!(
+			journal::check::<Test>(&get_mock_journal::<Test>(&Default::default()), &statements),
+			Err(JournalError::StatementCount)
+		);
+
+		// Every field of the statement is committed to
+		let mut other = statements.clone();
+		other[0].0.iban = string_to_bounded_vec("CH9300762011623852957");
+		assert_eq!(
+			journal::check::<Test>(&get_mock_journal::<Test>(&other), &statements),
+			Err(JournalError::Iban(0))
+		);
+
+		let mut other = statements.clone();
+		other[0].0.balance += 1;
+		assert_eq!(
+			journal::check::<Test>(&get_mock_journal::<Test>(&other), &statements),
+			Err(JournalError::ClosingBalance(0))
+		);
+
+		let mut other = statements.clone();
+		other[0].1[0].amount += 1;
+		assert_eq!(
+			journal::check::<Test>(&get_mock_journal::<Test>(&other), &statements),
+			Err(JournalError::Transactions(0))
+		);
+
+		// Batch is dropped without processing its transactions
+		assert_ok!(FiatRampsExample::queue_statements(
+			RuntimeOrigin::signed(ocw_account),
+			batches[0].receipt_url.clone(),
+			statements,
+		));
+		add_image_id();
+		assert_ok!(FiatRampsExample::process_statements(
+			RuntimeOrigin::signed(ocw_account),
+			0,
+			IMAGE_ID,
+			get_mock_journal::<Test>(&other),
+			false
+		));
+
+		assert!(FiatRampsExample::queued_statements(0).is_none());
+		System::assert_last_event(
+			crate::Event::<Test>::JournalMismatch {
+				batch_id: 0,
+				error: JournalError::Transactions(0),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn transactions_hash_commits_to_canonical_form() {
+	let transaction = TransactionOf::<Test> {
+		iban: string_to_bounded_vec("CH9300762011623852957"),
+		name: string_to_bounded_vec("Zürich AG"),
+		currency: string_to_bounded_vec("CHF"),
+		amount: 125_000_000_000,
+		reference: string_to_bounded_vec("RF18539007547034"),
+		tx_type: TransactionType::Incoming,
+		entry_reference: None,
+		booking_date: 1_700_000_000_000,
+		validation_date: 1_700_006_400_000,
+	};
+
+	assert_eq!(journal::canonical_transactions::<Test>(&[]), b"fiat-ramps/transactions/v1\n");
+	assert_eq!(
+		journal::canonical_transactions::<Test>(&[transaction.clone()]),
+		"fiat-ramps/transactions/v1\n21:CH9300762011623852957,10:Zürich AG,3:CHF,\
+		13:12.5000000000,16:RF18539007547034,4:CRDT,0:,19:2023-11-14T22:13:20,\
+		19:2023-11-15T00:00:00,"
+			.as_bytes()
+	);
+	assert_eq!(
+		journal::transactions_hash::<Test>(&[transaction.clone()]),
+		b"30830d5ba4d40bd432dd8b8b4528c7bf22103ca0576996098b833ef0406c2bdd"
+	);
+
+	// Entry reference and direction are committed to
+	let outgoing = TransactionOf::<Test> {
+		tx_type: TransactionType::Outgoing,
+		entry_reference: Some(string_to_bounded_vec("2023111400001")),
+		..transaction
+	};
+	assert!(journal::canonical_transactions::<Test>(&[outgoing])
+		.ends_with(b",4:DBIT,13:2023111400001,19:2023-11-14T22:13:20,19:2023-11-15T00:00:00,"));
+}
+
+#[test]
+fn queued_statement_batches_are_kept_separately() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::MultipleStatements,
+			StatementTypes::CompleteTransactions,
+		);
+
+		for batch in batches.iter() {
+			assert_ok!(FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(ocw_account),
+				batch.receipt_url.clone(),
+				batch.statements.clone(),
+			));
+		}
+
+		// Queueing the same receipt twice is not allowed
+		assert_noop!(
+			FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(ocw_account),
+				batches[0].receipt_url.clone(),
+				batches[0].statements.clone(),
+			),
+			Error::<Test>::StatementsAlreadyQueued,
+		);
+
+		assert_eq!(FiatRampsExample::statement_batch_count(), 3);
+		assert_eq!(QueuedStatements::<Test>::iter().count(), 3);
+
+		// Batches are processed and rejected independently
+		add_image_id();
+		assert_ok!(FiatRampsExample::process_statements(
+			RuntimeOrigin::signed(ocw_account),
+			1,
+			IMAGE_ID,
+			queued_journal(1),
+			false
+		));
+		assert_ok!(FiatRampsExample::reject_statements(RuntimeOrigin::signed(ocw_account), 2));
+
+		assert_eq!(
+			FiatRampsExample::queued_statements(0).unwrap().statements,
+			batches[0].statements
+		);
+		assert!(FiatRampsExample::queued_statements(1).is_none());
+		assert!(FiatRampsExample::queued_statements(2).is_none());
+
+		System::assert_has_event(crate::Event::<Test>::StatementsRejected { batch_id: 2 }.into());
+
+		assert_noop!(
+			FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(ocw_account),
+				1,
+				IMAGE_ID,
+				vec![],
+				false
+			),
+			Error::<Test>::StatementsNotQueued,
+		);
+		assert_noop!(
+			FiatRampsExample::reject_statements(RuntimeOrigin::signed(get_test_accounts()[0]), 0),
+			Error::<Test>::UnauthorizedCall,
+		);
+	});
+}
+
+#[test]
+fn queued_statements_expire() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::SingleStatement,
+			StatementTypes::IncomingTransactions,
+		);
+
+		assert_ok!(FiatRampsExample::queue_statements(
+			RuntimeOrigin::signed(ocw_account),
+			batches[0].receipt_url.clone(),
+			batches[0].statements.clone(),
+		));
+
+		let expiry_block = 1 + <Test as Config>::StatementsExpiry::get();
+
+		// Batch is indexed by the block it is removed in and by its receipt URL
+		assert_eq!(StatementsExpiring::<Test>::get(expiry_block + 1), vec![0]);
+		assert_eq!(QueuedReceipts::<Test>::get(&batches[0].receipt_url), Some(0));
+		assert_noop!(
+			FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(ocw_account),
+				batches[0].receipt_url.clone(),
+				batches[0].statements.clone(),
+			),
+			Error::<Test>::StatementsAlreadyQueued
+		);
+
+		// Batch is kept until the expiry block has passed
+		FiatRampsExample::on_initialize(expiry_block);
+		assert!(FiatRampsExample::queued_statements(0).is_some());
+
+		System::set_block_number(expiry_block + 1);
+		FiatRampsExample::on_initialize(expiry_block + 1);
+		assert!(FiatRampsExample::queued_statements(0).is_none());
+		assert!(StatementsExpiring::<Test>::get(expiry_block + 1).is_empty());
+		assert!(QueuedReceipts::<Test>::get(&batches[0].receipt_url).is_none());
+
+		System::assert_has_event(crate::Event::<Test>::StatementsExpired { batch_id: 0 }.into());
+
+		// Receipt of an expired batch can be queued again
+		assert_ok!(FiatRampsExample::queue_statements(
+			RuntimeOrigin::signed(ocw_account),
+			batches[0].receipt_url.clone(),
+			batches[0].statements.clone(),
+		));
+		assert_eq!(QueuedReceipts::<Test>::get(&batches[0].receipt_url), Some(1));
+	});
+}
+
+#[test]
+fn image_ids_are_registered_and_retired_by_root() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+
+		let test_accounts = get_test_accounts();
+		let (old, new) = ([1u8; 32], [2u8; 32]);
+		let add = |image_id: [u8; 32], activation: u64| {
+			FiatRampsExample::add_risc0_image_id(
+				RuntimeOrigin::root(),
+				image_id,
+				activation,
+				string_to_bounded_vec("v1"),
+			)
+		};
+
+		assert_noop!(
+			FiatRampsExample::add_risc0_image_id(
+				RuntimeOrigin::signed(test_accounts[2]),
+				old,
+				0,
+				string_to_bounded_vec("v1")
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			FiatRampsExample::retire_risc0_image_id(
+				RuntimeOrigin::signed(test_accounts[2]),
+				old,
+				0
+			),
+			BadOrigin
+		);
+		assert_eq!(FiatRampsExample::risc0_image_id(), None);
+
+		// New image is registered ahead of its activation
+		assert_ok!(add(old, 0));
+		assert_ok!(add(new, 20));
+		System::assert_last_event(
+			crate::Event::<Test>::ImageIdAdded {
+				image_id: new,
+				activation: 20,
+				label: string_to_bounded_vec("v1"),
+			}
+			.into(),
+		);
+		assert_noop!(add(new, 20), Error::<Test>::ImageIdAlreadyRegistered);
+		assert_noop!(add(IMAGE_ID, 0), Error::<Test>::TooManyImageIds);
+		assert_eq!(FiatRampsExample::risc0_image_id(), Some(old));
+		assert!(!FiatRampsExample::is_image_id_active(&new));
+
+		// Old image is still accepted until its sunset, while both are active
+		assert_ok!(FiatRampsExample::retire_risc0_image_id(RuntimeOrigin::root(), old, 30));
+		System::assert_last_event(
+			crate::Event::<Test>::ImageIdRetired { image_id: old, sunset: 30 }.into(),
+		);
+
+		System::set_block_number(20);
+		assert!(FiatRampsExample::is_image_id_active(&old));
+		assert_eq!(FiatRampsExample::risc0_image_id(), Some(new));
+
+		System::set_block_number(30);
+		assert!(!FiatRampsExample::is_image_id_active(&old));
+		assert_eq!(FiatRampsExample::risc0_image_id(), Some(new));
+
+		// Image retired right away is removed and frees its slot
+		assert_ok!(FiatRampsExample::retire_risc0_image_id(RuntimeOrigin::root(), old, 30));
+		assert!(!Risc0ImageIds::<Test>::contains_key(old));
+		assert_noop!(
+			FiatRampsExample::retire_risc0_image_id(RuntimeOrigin::root(), old, 30),
+			Error::<Test>::ImageIdNotFound
+		);
+		assert_ok!(add(IMAGE_ID, 0));
+	});
+}
+
+#[test]
+fn receipt_weight_scales_with_its_length() {
+	let weight = |length: usize| {
+		crate::Call::<Test>::submit_receipt {
+			batch_id: 0,
+			image_id: IMAGE_ID,
+			receipt: vec![0; length].try_into().unwrap(),
+		}
+		.get_dispatch_info()
+		.weight
+	};
+
+	assert_eq!(
+		weight(1024).saturating_sub(weight(0)),
+		ReceiptByteWeight::get().saturating_mul(1024)
+	);
+	assert_eq!(weight(0).proof_size(), ReceiptVerificationWeight::get().proof_size());
+	assert!(weight(MaxReceiptLength::get() as usize).all_gt(weight(0)));
+}
+
+/// Verifies a succinct receipt produced by hyperfridge
+///
+/// No succinct receipt of the risc0 version of the pallet is checked in, proving one needs the
+/// hyperfridge guest and prover. Generate it with `hyperfridge` and store it in
+/// `fixtures/succinct_receipt.json` with the hex encoded image id of the guest in
+/// `fixtures/succinct_image_id.hex`, then run the test with `--ignored`.
+#[test]
+#[ignore]
+fn succinct_receipt_fixture_verifies() {
+	let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+	let bytes = std::fs::read(fixtures.join("succinct_receipt.json"))
+		.expect("Succinct receipt fixture should be generated with hyperfridge");
+	let image_id: [u8; 32] = sp_core::bytes::from_hex(
+		std::fs::read_to_string(fixtures.join("succinct_image_id.hex")).unwrap().trim(),
+	)
+	.unwrap()
+	.try_into()
+	.unwrap();
+
+	new_test_ext().execute_with(|| {
+		let (receipt, _): (Receipt, _) = serde_json_core::from_slice(&bytes).unwrap();
+		assert!(matches!(receipt.inner, InnerReceipt::Succinct(_)));
+
+		assert_eq!(FiatRampsExample::check_receipt(&receipt, &[image_id]), Some((image_id, false)));
+
+		// Receipt does not verify against another image id
+		assert_eq!(FiatRampsExample::check_receipt(&receipt, &[IMAGE_ID]), None);
+	});
+}
+
+#[test]
+fn anyone_can_submit_receipt_of_queued_batch() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let alice = get_test_accounts()[0];
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::SingleStatement,
+			StatementTypes::CompleteTransactions,
+		);
+		let statements_info = batches[0].clone();
+		let submit = |journal: &[u8]| {
+			FiatRampsExample::submit_receipt(
+				RuntimeOrigin::signed(alice),
+				0,
+				IMAGE_ID,
+				get_mock_receipt(journal).0.try_into().unwrap(),
+			)
+		};
+
+		assert_ok!(FiatRampsExample::queue_statements(
+			RuntimeOrigin::signed(OcwAccount::get()),
+			statements_info.receipt_url.clone(),
+			statements_info.statements.clone(),
+		));
+		let journal = queued_journal(0);
+
+		assert_noop!(submit(&journal), Error::<Test>::ImageIdNotActive);
+		add_image_id();
+
+		assert_noop!(
+			FiatRampsExample::submit_receipt(
+				RuntimeOrigin::signed(alice),
+				0,
+				IMAGE_ID,
+				b"{}".to_vec().try_into().unwrap()
+			),
+			Error::<Test>::InvalidReceipt
+		);
+		assert_noop!(
+			submit(&get_mock_journal::<Test>(&Default::default())),
+			Error::<Test>::JournalMismatch
+		);
+
+		// Receipt is verified on chain, the batch is processed like by the offchain worker
+		let receipt_length = get_mock_receipt(&journal).0.len() as u64;
+		let post_info = submit(&journal).unwrap();
+		assert!(post_info.actual_weight.unwrap().all_gte(
+			<Test as Config>::ReceiptVerificationWeight::get()
+				.saturating_add(ReceiptByteWeight::get().saturating_mul(receipt_length))
+		));
+
+		assert!(FiatRampsExample::queued_statements(0).is_none());
+		System::assert_has_event(
+			crate::Event::<Test>::ProofVerified {
+				batch_id: 0,
+				image_id: IMAGE_ID,
+				statements_info,
+			}
+			.into(),
+		);
+
+		assert_noop!(submit(&journal), Error::<Test>::StatementsNotQueued);
+	});
+}
+
+#[test]
+fn fake_receipts_are_refused_unless_allowed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let alice = get_test_accounts()[0];
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::SingleStatement,
+			StatementTypes::CompleteTransactions,
+		);
+		add_image_id();
+		assert_ok!(FiatRampsExample::queue_statements(
+			RuntimeOrigin::signed(ocw_account),
+			batches[0].receipt_url.clone(),
+			batches[0].statements.clone(),
+		));
+		let journal = queued_journal(0);
+		let submit = || {
+			FiatRampsExample::submit_receipt(
+				RuntimeOrigin::signed(alice),
+				0,
+				IMAGE_ID,
+				get_mock_receipt(&journal).0.try_into().unwrap(),
+			)
+		};
+
+		assert_noop!(
+			FiatRampsExample::set_fake_receipts_allowed(RuntimeOrigin::signed(alice), false),
+			BadOrigin
+		);
+		assert_ok!(FiatRampsExample::set_fake_receipts_allowed(RuntimeOrigin::root(), false));
+		System::assert_last_event(
+			crate::Event::<Test>::FakeReceiptsAllowedSet { allowed: false }.into(),
+		);
+
+		// Neither the offchain worker nor anyone else can process the batch with a fake receipt
+		assert_noop!(submit(), Error::<Test>::InvalidReceipt);
+		assert_noop!(
+			FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(ocw_account),
+				0,
+				IMAGE_ID,
+				journal.clone(),
+				true
+			),
+			Error::<Test>::FakeReceiptsNotAllowed
+		);
+
+		// Chain that does not permit fake receipts can not allow them
+		FakeReceiptsPermitted::<Test>::put(false);
+		assert_noop!(
+			FiatRampsExample::set_fake_receipts_allowed(RuntimeOrigin::root(), true),
+			Error::<Test>::FakeReceiptsNotPermitted
+		);
+
+		FakeReceiptsPermitted::<Test>::put(true);
+		assert_ok!(FiatRampsExample::set_fake_receipts_allowed(RuntimeOrigin::root(), true));
+		assert_ok!(submit());
+		System::assert_has_event(
+			crate::Event::<Test>::FakeReceiptAccepted { batch_id: 0, image_id: IMAGE_ID }.into(),
+		);
+	});
+}
+
+#[test]
+fn runtime_api_queries_return_pallet_state() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let ocw_account = OcwAccount::get();
+		let test_accounts = get_test_accounts();
+
+		let alice = test_accounts[0];
+		let bob = test_accounts[1];
+		let alice_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537320");
+
+		assert_ok!(FiatRampsExample::create_account(
+			RuntimeOrigin::root(),
+			alice,
+			alice_iban.clone()
+		));
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(alice).into(),
+			Some(get_mock_beneficiary::<Test>("Alice"))
+		));
+
+		assert_eq!(FiatRampsExample::iban_of(&alice), Some(alice_iban));
+		assert_eq!(FiatRampsExample::iban_of(&bob), None);
+
+		for amount in [1000, 500, 250] {
+			assert_ok!(FiatRampsExample::transfer(
+				Some(alice).into(),
+				amount,
+				*b"EUR",
+				TransferDestination::Withdraw
+			));
+		}
+		assert_ok!(FiatRampsExample::cancel_transfer(Some(alice).into(), 1));
+
+		let requests = FiatRampsExample::pending_burn_requests_of(&alice);
+		assert_eq!(requests.iter().map(|request| request.id).collect::<Vec<_>>(), vec![0, 2]);
+		assert_eq!(requests[1].amount, 250);
+		assert!(FiatRampsExample::pending_burn_requests_of(&bob).is_empty());
+
+		let (_, batches) = get_mock_response::<Test>(
+			ResponseTypes::MultipleStatements,
+			StatementTypes::CompleteTransactions,
+		);
+
+		for batch in batches.iter() {
+			assert_ok!(FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(ocw_account),
+				batch.receipt_url.clone(),
+				batch.statements.clone(),
+			));
+		}
+
+		let status = FiatRampsExample::queued_statements_status();
+		let expiry = <Test as Config>::StatementsExpiry::get();
+
+		assert_eq!(status.len(), batches.len());
+		for (batch_id, (status, batch)) in status.iter().zip(batches.iter()).enumerate() {
+			assert_eq!(status.batch_id, batch_id as u64);
+			assert_eq!(status.receipt_url, batch.receipt_url.to_vec());
+			assert_eq!(status.statements, batch.statements.len() as u32);
+			assert_eq!(
+				status.transactions,
+				batch.statements.iter().map(|(_, txs)| txs.len() as u32).sum::<u32>()
+			);
+			assert_eq!(status.queued_at, 1);
+			assert_eq!(status.expires_at, 1 + expiry);
+		}
+	});
+}
+
+#[test]
+fn balance_mismatch_pauses_minting() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		PauseMintingOnMismatch::set(true);
+
+		let ocw_account = OcwAccount::get();
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+		let owner_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537313");
+
+		// Processes a statement of the owner with one incoming transaction, if `amount` is set
+		let process_statement = |balance: u128, amount: Option<u128>| {
+			let batch_id = FiatRampsExample::statement_batch_count();
+			let transactions = amount
+				.map(|amount| TransactionOf::<Test> {
+					iban: bob_iban.clone(),
+					name: string_to_bounded_vec("Bob"),
+					currency: string_to_bounded_vec("EUR"),
+					amount,
+					reference: string_to_bounded_vec("Purp:none; ourRef:none"),
+					tx_type: TransactionType::Incoming,
+					entry_reference: Some(string_to_bounded_vec(&format!("entry-{}", batch_id))),
+					booking_date: 0,
+					validation_date: 0,
+				})
+				.into_iter()
+				.collect::<Vec<_>>();
+			let statements = vec![(
+				BankAccountOf::<Test> {
+					iban: owner_iban.clone(),
+					opening_balance: 0,
+					balance,
+					currency: None,
+					last_updated: 0,
+					beneficiary: None,
+				},
+				transactions.try_into().unwrap(),
+			)];
+
+			assert_ok!(FiatRampsExample::queue_statements(
+				RuntimeOrigin::signed(ocw_account),
+				string_to_bounded_vec(&format!("receipt-{}.json", batch_id)),
+				statements.try_into().unwrap(),
+			));
+			assert_ok!(FiatRampsExample::process_statements(
+				RuntimeOrigin::signed(ocw_account),
+				batch_id,
+				IMAGE_ID,
+				queued_journal(batch_id),
+				false
+			));
+		};
+
+		// Closing balance matches the minted funds
+		process_statement(1000, Some(1000));
+
+		let owner = FiatRampsExample::get_account_id(&owner_iban).unwrap();
+
+		assert_eq!(Balances::free_balance(owner), 1000);
+		assert_eq!(FiatRampsExample::account_of(owner).unwrap().balance, 1000);
+		assert!(FiatRampsExample::minting_paused_at(&owner_iban).is_none());
+
+		// Bank reports more funds than were minted
+		process_statement(3000, Some(1000));
+
+		assert_eq!(Balances::free_balance(owner), 2000);
+		assert_eq!(FiatRampsExample::minting_paused_at(&owner_iban), Some(1));
+		System::assert_has_event(
+			crate::Event::<Test>::ReconciliationMismatch {
+				iban: owner_iban.clone(),
+				bank: 3000,
+				chain: 2000,
+			}
+			.into(),
+		);
+
+		// Minting is paused until an admin resumes it
+		process_statement(4000, Some(1000));
+
+		assert_eq!(Balances::free_balance(owner), 2000);
+		System::assert_has_event(
+			crate::Event::<Test>::StatementProcessed {
+				holder: owner,
+				iban: owner_iban.clone(),
+				failed_transactions: vec![0],
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			FiatRampsExample::resume_minting(Some(owner).into(), owner_iban.clone()),
+			BadOrigin
+		);
+		assert_ok!(FiatRampsExample::resume_minting(RuntimeOrigin::root(), owner_iban.clone()));
+		assert_noop!(
+			FiatRampsExample::resume_minting(RuntimeOrigin::root(), owner_iban.clone()),
+			Error::<Test>::MintingNotPaused
+		);
+		System::assert_has_event(
+			crate::Event::<Test>::MintingResumed { iban: owner_iban.clone() }.into(),
+		);
+
+		// Funds held in escrow by burn requests are counted as on-chain funds
+		assert_ok!(FiatRampsExample::set_beneficiary(
+			Some(owner).into(),
+			Some(get_mock_beneficiary::<Test>("Owner"))
+		));
+		assert_ok!(FiatRampsExample::transfer(
+			Some(owner).into(),
+			500,
+			*b"EUR",
+			TransferDestination::Withdraw
+		));
+
+		process_statement(2000, None);
+
+		assert_eq!(Balances::free_balance(owner), 1500);
+		assert!(FiatRampsExample::minting_paused_at(&owner_iban).is_none());
+	});
+}
+
+#[test]
+fn duplicate_transactions_are_skipped() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let bob_iban: IbanOf<Test> = string_to_bounded_vec("CH1230116000289537312");
+		let owner_iban: IbanOf<Test> = string_to_bounded_vec("CH2108307000289537313");
+
+		process_incoming_transaction(&bob_iban, 1000, "Purp:none; ourRef:none");
+
+		let owner = FiatRampsExample::get_account_id(&owner_iban).unwrap();
+		assert_eq!(Balances::free_balance(owner), 1000);
+
+		// Overlapping statement contains the same transaction again
+		process_incoming_transaction(&bob_iban, 1000, "Purp:none; ourRef:none");
+
+		assert_eq!(Balances::free_balance(owner), 1000);
+
+		let transaction = TransactionOf::<Test> {
+			iban: bob_iban.clone(),
+			name: string_to_bounded_vec("Alice"),
+			currency: string_to_bounded_vec("EUR"),
+			amount: 1000,
+			reference: string_to_bounded_vec("Purp:none; ourRef:none"),
+			tx_type: TransactionType::Incoming,
+			entry_reference: None,
+			booking_date: 0,
+			validation_date: 0,
+		};
+		let expires_at = 1 + <Test as Config>::ProcessedTransactionsExpiry::get();
+
+		assert_eq!(
+			FiatRampsExample::processed_transactions(&owner_iban, transaction.id()),
+			Some(expires_at)
+		);
+		System::assert_has_event(
+			crate::Event::<Test>::DuplicateTransactionSkipped {
+				iban: owner_iban.clone(),
+				transaction_id: transaction.id(),
+			}
+			.into(),
+		);
+
+		// Entry reference of the bank identifies the transaction, if it is provided
+		let entry_reference = Some(string_to_bounded_vec("ZV20231012/000123"));
+		let with_entry_reference = TransactionOf::<Test> { entry_reference, ..transaction.clone() };
+
+		assert_ne!(with_entry_reference.id(), transaction.id());
+		assert_eq!(
+			with_entry_reference.id(),
+			TransactionOf::<Test> { amount: 2000, ..with_entry_reference.clone() }.id()
+		);
+
+		// Processed transactions are forgotten after the expiry
+		FiatRampsExample::on_initialize(expires_at);
+		assert!(FiatRampsExample::processed_transactions(&owner_iban, transaction.id()).is_none());
+
+		process_incoming_transaction(&bob_iban, 1000, "Purp:none; ourRef:none");
+		assert_eq!(Balances::free_balance(owner), 2000);
 	});
 }
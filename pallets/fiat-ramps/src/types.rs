@@ -17,12 +17,36 @@ pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"ramp");
 /// Account id will be derived from this pallet id.
 pub const PALLET_ID: PalletId = PalletId(*b"FiatRamp");
 
+/// Length of the reference code of an IBAN link
+pub const LINK_CODE_LENGTH: usize = 8;
+
+/// Characters used in the reference code of an IBAN link, without ambiguous `I` and `O`
+pub const LINK_CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// Offchain storage key of the statements that could not be parsed in the last response
+pub const STATEMENT_ERRORS_KEY: &[u8] = b"fiat_ramps::statement_errors";
+
 /// Account id of
 pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
 
 /// Balance type
 pub type BalanceOf<T> = <<T as Config>::Currency as Currency<AccountIdOf<T>>>::Balance;
 
+/// Asset id of `Config::Assets`
+pub type AssetIdOf<T> = <<T as Config>::Assets as fungibles::Inspect<AccountIdOf<T>>>::AssetId;
+
+/// ISO 4217 currency code, e.g `EUR`
+pub type CurrencyCode = [u8; 3];
+
+/// On-chain representation of a fiat currency
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum CurrencyId<AssetId> {
+	/// Currency of `Config::Currency`
+	Native,
+	/// Asset of `Config::Assets`
+	Asset(AssetId),
+}
+
 /// String vector
 pub type BoundedString<MaxLength> = BoundedVec<u8, MaxLength>;
 
@@ -40,11 +64,18 @@ pub type TransactionOf<T> =
 	Transaction<<T as Config>::MaxIbanLength, <T as Config>::MaxStringLength>;
 
 /// Bank account type
-pub type BankAccountOf<T> = BankAccount<<T as Config>::MaxIbanLength>;
+pub type BankAccountOf<T> =
+	BankAccount<<T as Config>::MaxIbanLength, <T as Config>::MaxStringLength>;
+
+/// Beneficiary of `Config`
+pub type BeneficiaryOf<T> = Beneficiary<<T as Config>::MaxStringLength>;
 
 /// Transfer destination of `Config`
-pub type TransferDestinationOf<T> =
-	TransferDestination<<T as Config>::MaxIbanLength, <T as frame_system::Config>::AccountId>;
+pub type TransferDestinationOf<T> = TransferDestination<
+	<T as Config>::MaxIbanLength,
+	<T as Config>::MaxStringLength,
+	<T as frame_system::Config>::AccountId,
+>;
 
 /// Explicit `BoundedVec<Statement>` type alias
 pub type StatementsOf<T> = BoundedVec<
@@ -62,16 +93,95 @@ pub enum OcwActivity {
 	None,
 }
 
+/// Status of a burn request
+#[derive(
+	Clone, Copy, Encode, Decode, Eq, PartialEq, Default, RuntimeDebug, MaxEncodedLen, TypeInfo,
+)]
+pub enum BurnRequestStatus {
+	/// Unpeg request has not been sent to the EBICS service yet
+	#[default]
+	Pending,
+	/// Unpeg request has been accepted by the EBICS service
+	Submitted,
+	/// Outgoing transaction of the burn request appeared in a bank statement
+	Confirmed,
+	/// Unpeg request failed `attempts` times and will be retried
+	Failed { attempts: u32 },
+	/// Unpeg request failed too many times and the funds were returned to the burner
+	Refunded,
+	/// Unpeg request is claimed by the offchain worker and might have been sent, the request
+	/// can not be cancelled until its outcome is reported. `attempts` counts earlier failures
+	InFlight { attempts: u32 },
+	/// Unpeg request was sent, but it is unknown whether the EBICS service executed it. It is
+	/// not retried until the outgoing transaction appears or root resolves the outcome
+	Unknown { attempts: u32 },
+	/// Unpeg request failed too many times and returning the funds to the burner failed. It is
+	/// not retried, the burner can retry the refund by cancelling the request
+	RefundFailed,
+}
+
+/// Outcome of an unpeg request reported by the offchain worker
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum UnpegOutcome {
+	/// EBICS service accepted the request
+	Accepted,
+	/// EBICS service rejected the request or it was not sent, it can be retried
+	Rejected,
+	/// Request timed out or the response could not be read, it might have been executed
+	Unknown,
+}
+
 /// Type that represents a burn request
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
-pub struct BurnRequest<MaxLength: Get<u32>, Balance: MaxEncodedLen> {
+pub struct BurnRequest<
+	MaxLength: Get<u32>,
+	StringMaxLength: Get<u32>,
+	AccountId,
+	Balance: MaxEncodedLen,
+	BlockNumber,
+> {
 	pub id: u64,
 	pub burner: Iban<MaxLength>,
+	/// On-chain account that made the request and whose funds are in escrow
+	pub burner_account: AccountId,
 	pub dest_iban: Iban<MaxLength>,
+	/// Holder of `dest_iban`
+	pub beneficiary: Beneficiary<StringMaxLength>,
 	pub amount: Balance,
+	/// Currency of the amount
+	pub currency: CurrencyCode,
+	/// Current status of the request
+	pub status: BurnRequestStatus,
+	/// Block of the last status change
+	pub updated_at: BlockNumber,
+}
+
+/// Burn request of `Config`
+pub type BurnRequestOf<T> = BurnRequest<
+	<T as Config>::MaxIbanLength,
+	<T as Config>::MaxStringLength,
+	AccountIdOf<T>,
+	BalanceOf<T>,
+	BlockNumberFor<T>,
+>;
+
+/// IBAN link waiting for the micro-deposit that proves ownership of the IBAN
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PendingLink<AccountId, BlockNumber> {
+	/// Account that requested the link
+	pub who: AccountId,
+	/// Code that has to be included in the reference of the deposit
+	pub code: [u8; LINK_CODE_LENGTH],
+	/// Amount of the deposit
+	pub amount: u128,
+	/// Block after which the link expires
+	pub expires_at: BlockNumber,
 }
 
+/// Pending IBAN link of `Config`
+pub type PendingLinkOf<T> = PendingLink<AccountIdOf<T>, BlockNumberFor<T>>;
+
 /// Trait for deseralizing a value from a JsonValue type
 pub trait Deserialize<T> {
 	fn deserialize(value: &JsonValue) -> Option<T>;
@@ -106,24 +216,215 @@ pub struct Transaction<MaxLength: Get<u32>, StringMaxLength: Get<u32>> {
 	pub reference: BoundedString<StringMaxLength>,
 	/// Type of the transaction: incoming or outgoing
 	pub tx_type: TransactionType,
+	/// Bank's reference of the entry, e.g `AcctSvcrRef`, if the bank provides one
+	pub entry_reference: Option<BoundedString<StringMaxLength>>,
+	/// Booking date, unix timestamp in milliseconds
+	pub booking_date: u64,
+	/// Value date, unix timestamp in milliseconds
+	pub validation_date: u64,
 }
 
 /// Representation of a Bank Account
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug, TypeInfo, MaxEncodedLen)]
-pub struct BankAccount<MaxLength: Get<u32>> {
+pub struct BankAccount<MaxLength: Get<u32>, StringMaxLength: Get<u32>> {
 	/// IBAN number of the account
 	pub iban: Iban<MaxLength>,
+	/// Opening balance of the account
+	pub opening_balance: u128,
 	/// Closing balance of the account
 	pub balance: u128,
-	/// Last block the statement was updated
+	/// Currency of the account, if the statement reports it
+	pub currency: Option<CurrencyCode>,
+	/// Closing date of the last statement, unix timestamp in milliseconds
 	pub last_updated: u64,
+	/// Holder of the account, used as beneficiary of withdrawals and transfers to the account
+	pub beneficiary: Option<Beneficiary<StringMaxLength>>,
+}
+
+/// Beneficiary of a payment made by the EBICS service
+///
+/// Banks reject SEPA and SIC payments without the name and address of the beneficiary
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Beneficiary<StringMaxLength: Get<u32>> {
+	/// Name of the account holder
+	pub name: BoundedString<StringMaxLength>,
+	/// Street of the address
+	pub street: BoundedString<StringMaxLength>,
+	/// House number of the address
+	pub street_nr: BoundedString<StringMaxLength>,
+	/// Postal code of the address
+	pub zip: BoundedString<StringMaxLength>,
+	/// City of the address
+	pub city: BoundedString<StringMaxLength>,
+	/// ISO 3166-1 alpha-2 code of the country, e.g `CH`
+	pub country: [u8; 2],
+	/// BIC of the beneficiary's bank, can be empty for national payments
+	pub bic: BoundedString<StringMaxLength>,
+	/// Name of the beneficiary's bank
+	pub bank_name: BoundedString<StringMaxLength>,
+	/// Whether the payment is made through the national clearing system instead of SEPA
+	pub national: bool,
+}
+
+/// Reference field of a transaction, parsed with `TryFrom<&[u8]>`
+///
+/// Accepted formats are:
+/// - `Purp:<SS58 address>; ourRef:<burn request id>`, where both values can be `none`
+/// - ISO 20022 structured creditor reference, e.g `RF3442`, numeric payload is the burn
+///   request id
+/// - plain SS58 address of the recipient
+///
+/// Any other free text is accepted as a reference without information.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Reference<AccountId> {
+	/// On-chain recipient of the transaction
+	pub dest: Option<AccountId>,
+	/// Id of the burn request that the transaction settles
+	pub burn_request_id: Option<u64>,
+}
+
+/// Reference type of `Config`
+pub type ReferenceOf<T> = Reference<AccountIdOf<T>>;
+
+/// Errors that can occur when parsing the reference field of a transaction
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum ReferenceError {
+	/// Reference is not valid UTF-8
+	InvalidUtf8,
+	/// `Purp` field does not contain a valid SS58 address
+	InvalidAddress,
+	/// `ourRef` field does not contain a valid burn request id
+	InvalidRequestId,
+	/// Structured creditor reference has an invalid format or checksum
+	InvalidCreditorReference,
+}
+
+/// Errors that can occur when converting between JSON numbers and fixed-point amounts
+#[derive(Encode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum AmountError {
+	/// Value is not a number
+	NotANumber,
+	/// Amount does not fit into the target type
+	Overflow,
+	/// Amount has more decimal places than `Config::FiatDecimals`
+	TooPrecise,
+}
+
+/// Errors that can occur when parsing a statement received from the EBICS service
+#[derive(Encode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum ParseError {
+	/// Statement or transaction is not a JSON object
+	NotAnObject,
+	/// Field is missing
+	MissingField(&'static str),
+	/// Field does not have the expected type
+	InvalidField(&'static str),
+	/// Field is longer than its maximum length
+	FieldTooLong(&'static str),
+	/// Field is not a valid amount
+	InvalidAmount(&'static str, AmountError),
+	/// Statement has more than `Config::MaxStatements` transactions
+	TooManyTransactions(u32),
+	/// Message is not a well-formed XML document
+	InvalidXml(XmlError),
+}
+
+/// Statement parsed from the response of the EBICS service
+///
+/// Consists of the receipt url, bank account and transactions of the statement, or the type
+/// and index of the transaction and the reason the statement could not be parsed.
+pub type ParsedStatement<T> = Result<
+	(StringOf<T>, BankAccountOf<T>, Vec<TransactionOf<T>>),
+	(Option<(TransactionType, u32)>, ParseError),
+>;
+
+/// Errors of the offchain worker's requests to the EBICS service
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum OcwError {
+	/// Request could not be sent or the connection failed
+	Transport,
+	/// No response before the deadline
+	Timeout,
+	/// Response has a status other than `200`
+	HttpStatus(u16),
+	/// URL or response is not valid UTF-8
+	InvalidUtf8,
+	/// Response is not valid JSON
+	InvalidJson,
+	/// Response does not have the expected structure
+	Schema,
+}
+
+/// Errors that can occur when parsing an XML document
+#[derive(Encode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum XmlError {
+	/// Document ends before the root element is closed
+	UnexpectedEnd,
+	/// Document is malformed at the given byte offset
+	Malformed(u32),
+	/// Elements are nested deeper than supported
+	TooDeep,
+}
+
+/// Reasons why the journal of a receipt does not commit to the queued statements
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum JournalError {
+	/// Journal is not a serialized JSON document with a list of statements
+	Malformed,
+	/// Journal has a different number of statements
+	StatementCount,
+	/// IBAN of the statement at the index differs
+	Iban(u32),
+	/// Closing balance of the statement at the index differs
+	ClosingBalance(u32),
+	/// Transactions of the statement at the index differ
+	Transactions(u32),
+}
+
+/// Format of the bank statements fetched by the offchain worker
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum StatementFormat {
+	/// JSON statements of the EBICS service
+	Json,
+	/// ISO 20022 camt.053 end of day statements
+	Camt053,
+	/// ISO 20022 camt.054 intraday debit and credit notifications
+	Camt054,
+}
+
+/// Format of the payment initiations sent by the offchain worker for burn requests
+#[derive(
+	Encode, Decode, Clone, Copy, PartialEq, Eq, Default, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub enum PaymentFormat {
+	/// JSON unpeg request of the EBICS service, one request per call
+	#[default]
+	Json,
+	/// ISO 20022 pain.001 customer credit transfer initiation
+	Pain001,
+	/// JSON array of unpeg requests, sent to the batch endpoint of the EBICS service
+	JsonBatch,
+}
+
+/// Statement that could not be parsed
+///
+/// Kept SCALE encoded in the offchain storage under `STATEMENT_ERRORS_KEY`, field names are
+/// encoded as strings
+#[derive(Encode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct StatementError {
+	/// Index of the statement in the response
+	pub statement: u32,
+	/// Type and index of the transaction, if the error is in one of the transactions
+	pub transaction: Option<(TransactionType, u32)>,
+	/// Reason why the statement was skipped
+	pub error: ParseError,
 }
 
 /// Burn destination
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
-pub enum TransferDestination<MaxLength: Get<u32>, AccountId> {
-	/// Burn to a specific IBAN
-	Iban(Iban<MaxLength>),
+pub enum TransferDestination<MaxLength: Get<u32>, StringMaxLength: Get<u32>, AccountId> {
+	/// Burn to a specific IBAN, held by the beneficiary
+	Iban(Iban<MaxLength>, Beneficiary<StringMaxLength>),
 	/// Burn to another account, i.e transfer on-chain
 	Address(AccountId),
 	/// Withdraw
@@ -146,3 +447,59 @@ pub type QueuedStatementsInfoOf<T> = QueuedStatementsInfo<
 	StatementsOf<T>,
 	BoundedString<<T as Config>::MaxStringLength>,
 >;
+
+/// Image ID of the risc0 module that receipts are verified against
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ImageIdInfo<BlockNumber, Label> {
+	/// Block from which receipts of the image are accepted
+	pub activation: BlockNumber,
+	/// Block from which receipts of the image are no longer accepted
+	pub sunset: Option<BlockNumber>,
+	/// Label or version of the guest program
+	pub label: Label,
+}
+
+pub type ImageIdInfoOf<T> =
+	ImageIdInfo<BlockNumberFor<T>, BoundedString<<T as Config>::MaxStringLength>>;
+
+/// What the offchain worker authorities attest
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum AttestationSubject<ReceiptUrl> {
+	/// Statements fetched for the receipt, before they are queued
+	Queue(ReceiptUrl),
+	/// Outcome of verifying the receipt of a queued batch
+	Verification(u64),
+}
+
+pub type AttestationSubjectOf<T> =
+	AttestationSubject<BoundedString<<T as Config>::MaxStringLength>>;
+
+/// Attestations of a subject that have not reached the threshold yet
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct AttestationsInfo<AccountId, BlockNumber> {
+	/// Block of the first attestation
+	pub block_number: BlockNumber,
+	/// Authorities and the hash of the content they attested
+	pub votes: Vec<(AccountId, [u8; 32])>,
+}
+
+pub type AttestationsInfoOf<T> = AttestationsInfo<AccountIdOf<T>, BlockNumberFor<T>>;
+
+/// Summary of a queued statement batch, returned by the runtime API
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct QueuedStatementsStatus<BlockNumber> {
+	/// Id of the batch
+	pub batch_id: u64,
+	/// URL for the receipt of the statements
+	pub receipt_url: Vec<u8>,
+	/// Number of statements in the batch
+	pub statements: u32,
+	/// Number of transactions in all statements of the batch
+	pub transactions: u32,
+	/// Block number when the statements were queued
+	pub queued_at: BlockNumber,
+	/// Last block in which the batch can be verified
+	pub expires_at: BlockNumber,
+}
+
+pub type QueuedStatementsStatusOf<T> = QueuedStatementsStatus<BlockNumberFor<T>>;
@@ -1,9 +1,20 @@
 use crate::{
-	types::{Deserialize, IbanOf},
+	amount::{from_json_number, to_json_number},
+	types::{
+		AmountError, BeneficiaryOf, BurnRequestOf, CurrencyCode, Deserialize, IbanOf, ParseError,
+	},
 	Config,
 };
+use alloc::{
+	format,
+	string::{String, ToString},
+};
+use codec::Encode;
+use frame_support::{traits::Get, BoundedVec};
 /// Utility functions
-use lite_json::{JsonValue, NumberValue};
+use lite_json::JsonValue;
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::traits::SaturatedConversion;
 use sp_std::{vec, vec::Vec};
 
 /// Utility function for parsing value from json object
@@ -17,16 +28,118 @@ pub fn parse_object(key: &str, obj: &[(Vec<char>, lite_json::JsonValue)]) -> Jso
 
 /// Utility function for extracting value from json object
 ///
-/// parse value of a given key from json object
-pub fn extract_value<T: Deserialize<T> + Default>(
+/// parse value of a given key from json object, fails if the key is missing or the value
+/// has an unexpected type
+pub fn extract_value<T: Deserialize<T>>(
+	key: &'static str,
+	obj: &[(Vec<char>, lite_json::JsonValue)],
+) -> Result<T, ParseError> {
+	let (_, v) = obj
+		.iter()
+		.find(|(k, _)| k.iter().copied().eq(key.chars()))
+		.ok_or(ParseError::MissingField(key))?;
+	T::deserialize(v).ok_or(ParseError::InvalidField(key))
+}
+
+/// Utility function for extracting a string from json object
+///
+/// Fails if the string does not fit into `MaxLength` bytes
+pub fn extract_bounded<MaxLength: Get<u32>>(
+	key: &'static str,
+	obj: &[(Vec<char>, lite_json::JsonValue)],
+) -> Result<BoundedVec<u8, MaxLength>, ParseError> {
+	extract_value::<Vec<u8>>(key, obj)?
+		.try_into()
+		.map_err(|_| ParseError::FieldTooLong(key))
+}
+
+/// Utility function for extracting an amount from json object
+///
+/// The amount is converted to a fixed-point value with `decimals` decimal places
+pub fn extract_amount(
 	key: &str,
 	obj: &[(Vec<char>, lite_json::JsonValue)],
-) -> T {
-	let (_, v) = obj.iter().find(|(k, _)| k.iter().copied().eq(key.chars())).unwrap();
-	if let Some(value) = T::deserialize(v) {
-		return value
+	decimals: u32,
+) -> Result<i128, AmountError> {
+	match parse_object(key, obj) {
+		JsonValue::Number(number) => from_json_number(&number, decimals),
+		_ => Err(AmountError::NotANumber),
+	}
+}
+
+/// Utility function for extracting a date from json object
+///
+/// Date is expected in `YYYY-MM-DD` format, a time following the date is ignored. Returns
+/// the unix timestamp of the start of the day in milliseconds, `None` if the key is missing
+/// or the date is invalid.
+pub fn extract_date(key: &str, obj: &[(Vec<char>, lite_json::JsonValue)]) -> Option<u64> {
+	match parse_object(key, obj) {
+		JsonValue::String(date) => parse_date(&date),
+		_ => None,
+	}
+}
+
+/// Parses a `YYYY-MM-DD` date to a unix timestamp in milliseconds
+pub fn parse_date(date: &[char]) -> Option<u64> {
+	let number = |start: usize, end: usize| {
+		date.get(start..end)?
+			.iter()
+			.try_fold(0u64, |n, c| Some(n * 10 + c.to_digit(10)? as u64))
+	};
+
+	if date.get(4) != Some(&'-') || date.get(7) != Some(&'-') {
+		return None
+	}
+
+	let (year, month, day) = (number(0, 4)?, number(5, 7)?, number(8, 10)?);
+
+	let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+	let days_in_month = match month {
+		2 if is_leap_year => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31,
+	};
+
+	if year < 1970 || !(1..=12).contains(&month) || day == 0 || day > days_in_month {
+		return None
 	}
-	Default::default()
+
+	// Days since the unix epoch, years start in March so that leap days are at their end
+	let (year, month) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+	let day_of_year = (153 * month + 2) / 5 + day - 1;
+	let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year;
+
+	// 719468 days between 0000-03-01 and 1970-01-01
+	Some((days - 719_468) * 86_400_000)
+}
+
+/// Formats a unix timestamp in milliseconds as `YYYY-MM-DDThh:mm:ss` in UTC
+pub fn format_date_time(timestamp: u64) -> String {
+	let seconds = timestamp / 1000 % 86_400;
+
+	// Inverse of `parse_date`, days are split into 400 year eras of 146097 days
+	let days = timestamp / 86_400_000 + 719_468;
+	let (era, day_of_era) = (days / 146_097, days % 146_097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month + 2) / 5 + 1;
+
+	// Years start in March
+	let year = era * 400 + year_of_era;
+	let (year, month) = if month < 10 { (year, month + 3) } else { (year + 1, month - 9) };
+
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+		year,
+		month,
+		day,
+		seconds / 3600,
+		seconds / 60 % 60,
+		seconds % 60
+	)
 }
 
 /// Unpeq request template
@@ -35,95 +148,257 @@ pub fn extract_value<T: Deserialize<T> + Default>(
 ///
 /// `account_id` - Sender of the unpeq request
 /// `amount` - Amount of the unpeq request
+/// `currency` - ISO 4217 code of the amount's currency
 /// `iban` - IBAN of the receiver
+/// `beneficiary` - Holder of the receiver's IBAN
 /// `reference` - Reference of the unpeq request, we save request id in this field
 pub fn unpeg_request<T: Config>(
 	dest: &str,
 	amount: u128,
+	currency: &CurrencyCode,
 	iban: &IbanOf<T>,
+	beneficiary: &BeneficiaryOf<T>,
 	reference: &str,
-) -> JsonValue {
+) -> Result<JsonValue, AmountError> {
 	log::info!("params {:?} {:?} {:?} {:?}", dest, amount, iban, reference);
 
-	// First step is to convert amount to NumberValue type
-	let integer = amount / 10_000_000_000;
-	let fraction = amount % 10_000_000_000;
+	// UTF-8 bytes as JSON string
+	let string_json =
+		|bytes: &[u8]| JsonValue::String(String::from_utf8_lossy(bytes).chars().collect());
 
-	// Mutable copy of `fraction` that will be used to calculate length of the fraction
-	let mut fraction_copy = fraction;
+	// First step is to convert amount to NumberValue type
+	let amount = i128::try_from(amount).map_err(|_| AmountError::Overflow)?;
+	let amount_json = JsonValue::Number(to_json_number(amount, T::FiatDecimals::get())?);
 
-	let fraction_length = {
-		let mut len = 0;
+	Ok(JsonValue::Object(vec![
+		("amount".chars().collect(), amount_json),
+		("clearingSystemMemberId".chars().collect(), string_json(&beneficiary.bic)),
+		("currency".chars().collect(), string_json(currency)),
+		("nationalPayment".chars().collect(), JsonValue::Boolean(beneficiary.national)),
+		("ourReference".chars().collect(), JsonValue::String(reference.chars().collect())),
+		("purpose".chars().collect(), JsonValue::String(dest.chars().collect())),
+		("receipientBankName".chars().collect(), string_json(&beneficiary.bank_name)),
+		("receipientCity".chars().collect(), string_json(&beneficiary.city)),
+		("receipientCountry".chars().collect(), string_json(&beneficiary.country)),
+		("receipientName".chars().collect(), string_json(&beneficiary.name)),
+		("receipientIban".chars().collect(), string_json(iban)),
+		("receipientStreet".chars().collect(), string_json(&beneficiary.street)),
+		("receipientStreetNr".chars().collect(), string_json(&beneficiary.street_nr)),
+		("receipientZip".chars().collect(), string_json(&beneficiary.zip)),
+	]))
+}
 
-		while fraction_copy > 0 {
-			fraction_copy /= 10;
-			len += 1;
-		}
-		len
+/// Results of a batch of unpeg requests
+///
+/// The EBICS service responds with a list of `{"ourReference": "<request id>", "success": bool}`
+/// objects, entries with another shape are ignored. Requests without an entry have an unknown
+/// outcome, only an explicit `false` rejects a request
+pub fn parse_unpeg_results(response: &JsonValue) -> Vec<(u64, bool)> {
+	let results = match response {
+		JsonValue::Array(results) => results,
+		_ => return Vec::new(),
 	};
 
-	let amount_json = JsonValue::Number(NumberValue {
-		integer: integer as u64,
-		fraction: fraction as u64,
-		fraction_length,
-		exponent: 0,
-		negative: false,
-	});
+	results
+		.iter()
+		.filter_map(|result| {
+			let obj = result.as_object()?;
+			let reference = extract_value::<Vec<u8>>("ourReference", obj).ok()?;
+			let request_id = core::str::from_utf8(&reference).ok()?.trim().parse().ok()?;
+			match parse_object("success", obj) {
+				JsonValue::Boolean(success) => Some((request_id, success)),
+				_ => None,
+			}
+		})
+		.collect()
+}
 
-	let iban_json = JsonValue::String(iban[..].iter().map(|b| *b as char).collect::<Vec<char>>());
+/// Namespace of pain.001 messages
+const PAIN001_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:pain.001.001.03";
 
-	JsonValue::Object(vec![
-		("amount".chars().collect(), amount_json),
-		(
-			"clearingSystemMemberId".chars().collect(),
-			JsonValue::String(vec!['H', 'Y', 'P', 'L', 'C', 'H', '2', '2']),
-		),
-		("currency".chars().collect(), JsonValue::String(vec!['E', 'U', 'R'])),
-		("nationalPayment".chars().collect(), JsonValue::Boolean(true)),
-		("ourReference".chars().collect(), JsonValue::String(reference.chars().collect())),
-		("purpose".chars().collect(), JsonValue::String(dest.chars().collect())),
-		("receipientBankName".chars().collect(), JsonValue::String(vec!['H', 'y', 'p'])),
-		("receipientCity".chars().collect(), JsonValue::String(vec!['e'])),
-		("receipientCountry".chars().collect(), JsonValue::String(vec!['C', 'H'])),
-		("receipientName".chars().collect(), JsonValue::String(vec!['e'])),
-		("receipientIban".chars().collect(), iban_json),
-		("receipientStreet".chars().collect(), JsonValue::String(vec!['e'])),
-		("receipientStreetNr".chars().collect(), JsonValue::String(vec!['2', '5'])),
-		("receipientZip".chars().collect(), JsonValue::String(vec!['6', '3', '4', '0'])),
-	])
+/// Name of the party that initiates pain.001 messages
+const INITIATING_PARTY: &str = "Fiat Ramps";
+
+/// Placeholder of mandatory elements whose value is unknown
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+/// Maximum number of decimal places of ISO 20022 amounts
+const MAX_ISO_DECIMALS: u32 = 5;
+
+/// Credit transfer of a burn request in a pain.001 message
+pub struct CreditTransfer<'a, T: Config> {
+	/// Burn request to transfer, its id is the end-to-end id of the transfer
+	pub request: &'a BurnRequestOf<T>,
+	/// Holder of the burner's IBAN, if known
+	pub debtor: Option<&'a BeneficiaryOf<T>>,
+	/// SS58 address of the receiver, used as purpose of the transfer
+	pub purpose: &'a str,
 }
 
-#[test]
-fn test_unpeg_request() {
-	use crate::mock::new_test_ext;
+/// Unique id of a pain.001 message with the given burn requests
+///
+/// Messages sent in different blocks have different ids, so that a retried request is not
+/// rejected as a duplicate of the failed one
+pub fn pain001_message_id<BlockNumber: Encode>(
+	block_number: BlockNumber,
+	request_ids: &[u64],
+) -> String {
+	let hash = (block_number, request_ids).using_encoded(sp_io::hashing::blake2_128);
+	format!("{}", HexDisplay::from(&hash))
+}
 
-	new_test_ext().execute_with(|| {
-		let amount = 1_000_000_000_000_u128;
-		// First step is to convert amount to NumberValue type
-		let integer = amount / 10_000_000_000;
-		let fraction = amount % 10_000_000_000;
+/// pain.001 customer credit transfer initiation template
+///
+/// Every transfer is a payment information block (`PmtInf`) of its own, since the burn
+/// requests are paid from the IBANs of different burners. The request id is used as
+/// end-to-end id and as `ourRef` of the remittance information, so that the outgoing
+/// transaction can be matched to the burn request in the bank statement.
+///
+/// # Arguments
+///
+/// `message_id` - Unique id of the message, at most 35 characters
+/// `created_at` - Creation time of the message as unix timestamp in milliseconds, the
+/// transfers are requested to be executed on the same day
+/// `transfers` - Burn requests to transfer
+pub fn pain001_request<T: Config>(
+	message_id: &str,
+	created_at: u64,
+	transfers: &[CreditTransfer<T>],
+) -> Result<Vec<u8>, AmountError> {
+	let decimals = T::FiatDecimals::get();
+	let created_at = format_date_time(created_at);
+	let execution_date = &created_at[..10];
 
-		// Mutable copy of `fraction` that will be used to calculate length of the fraction
-		let mut fraction_copy = fraction;
+	let mut total = 0u128;
+	let mut payments = String::new();
 
-		let fraction_length = {
-			let mut len = 0;
+	for transfer in transfers {
+		let request = transfer.request;
+		let beneficiary = &request.beneficiary;
+		let id = request.id.to_string();
 
-			while fraction_copy > 0 {
-				fraction_copy /= 10;
-				len += 1;
-			}
-			len
+		let amount = request.amount.saturated_into::<u128>();
+		total = total.checked_add(amount).ok_or(AmountError::Overflow)?;
+		let amount = iso_amount(amount, decimals)?;
+
+		// Debtor agent is mandatory, banks identify it by the IBAN if the BIC is unknown
+		let (debtor_name, debtor_agent) = match transfer.debtor {
+			Some(debtor) if !debtor.bic.is_empty() =>
+				(escape(&debtor.name), element("BIC", &escape(&debtor.bic))),
+			Some(debtor) => (escape(&debtor.name), element("Othr", &element("Id", NOT_PROVIDED))),
+			None => (NOT_PROVIDED.to_string(), element("Othr", &element("Id", NOT_PROVIDED))),
 		};
 
-		let amount_json = NumberValue {
-			integer: integer as u64,
-			fraction: fraction as u64,
-			fraction_length,
-			exponent: 0,
-			negative: false,
+		// National payments are routed by the IBAN of the creditor
+		let creditor_agent = if beneficiary.bic.is_empty() {
+			String::new()
+		} else {
+			element("CdtrAgt", &element("FinInstnId", &element("BIC", &escape(&beneficiary.bic))))
 		};
 
-		println!("{:?}", amount_json.to_f64());
+		let address = [
+			("StrtNm", &beneficiary.street[..]),
+			("BldgNb", &beneficiary.street_nr[..]),
+			("PstCd", &beneficiary.zip[..]),
+			("TwnNm", &beneficiary.city[..]),
+			("Ctry", &beneficiary.country[..]),
+		]
+		.iter()
+		.filter(|(_, value)| value.iter().any(u8::is_ascii_alphanumeric))
+		.map(|(name, value)| element(name, &escape(value)))
+		.collect::<String>();
+		let address = if address.is_empty() { address } else { element("PstlAdr", &address) };
+		let reference = format!("Purp:{}; ourRef:{}", transfer.purpose, id);
+
+		let transaction = [
+			element("PmtId", &[element("InstrId", &id), element("EndToEndId", &id)].concat()),
+			element(
+				"Amt",
+				&format!("<InstdAmt Ccy=\"{}\">{}</InstdAmt>", escape(&request.currency), amount),
+			),
+			creditor_agent,
+			element("Cdtr", &[element("Nm", &escape(&beneficiary.name)), address].concat()),
+			element("CdtrAcct", &element("Id", &element("IBAN", &escape(&request.dest_iban)))),
+			element("RmtInf", &element("Ustrd", &escape(reference.as_bytes()))),
+		];
+
+		payments.push_str(&element(
+			"PmtInf",
+			&[
+				element("PmtInfId", &id),
+				element("PmtMtd", "TRF"),
+				element("NbOfTxs", "1"),
+				element("CtrlSum", &amount),
+				element("ReqdExctnDt", execution_date),
+				element("Dbtr", &element("Nm", &debtor_name)),
+				element("DbtrAcct", &element("Id", &element("IBAN", &escape(&request.burner)))),
+				element("DbtrAgt", &element("FinInstnId", &debtor_agent)),
+				element("CdtTrfTxInf", &transaction.concat()),
+			]
+			.concat(),
+		));
+	}
+
+	let header = [
+		element("MsgId", &escape(message_id.as_bytes())),
+		element("CreDtTm", &created_at),
+		element("NbOfTxs", &transfers.len().to_string()),
+		element("CtrlSum", &iso_amount(total, decimals)?),
+		element("InitgPty", &element("Nm", INITIATING_PARTY)),
+	];
+
+	Ok(format!(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?><Document xmlns=\"{}\">{}</Document>",
+		PAIN001_NAMESPACE,
+		element("CstmrCdtTrfInitn", &[element("GrpHdr", &header.concat()), payments].concat()),
+	)
+	.into_bytes())
+}
+
+/// Amount with `decimals` decimal places as ISO 20022 decimal, e.g `1234.5`
+///
+/// Fails if the amount has more decimal places than ISO 20022 amounts allow
+pub fn iso_amount(amount: u128, decimals: u32) -> Result<String, AmountError> {
+	let amount = i128::try_from(amount).map_err(|_| AmountError::Overflow)?;
+	let number = to_json_number(amount, decimals)?;
+
+	match number.fraction_length {
+		0 => Ok(number.integer.to_string()),
+		length if length <= MAX_ISO_DECIMALS =>
+			Ok(format!("{}.{:0width$}", number.integer, number.fraction, width = length as usize)),
+		_ => Err(AmountError::TooPrecise),
+	}
+}
+
+/// XML element with the given content
+fn element(name: &str, content: &str) -> String {
+	format!("<{0}>{1}</{0}>", name, content)
+}
+
+/// Escapes text for XML character data and attribute values
+fn escape(text: &[u8]) -> String {
+	String::from_utf8_lossy(text).chars().fold(String::new(), |mut escaped, c| {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c),
+		}
+		escaped
+	})
+}
+
+#[test]
+fn test_unpeg_request() {
+	use crate::mock::new_test_ext;
+
+	new_test_ext().execute_with(|| {
+		let amount = 1_000_000_000_000_i128;
+		// First step is to convert amount to NumberValue type
+		let amount_json = to_json_number(amount, 10).unwrap();
+
+		assert_eq!(amount_json.to_f64(), 100.0);
 	});
 }
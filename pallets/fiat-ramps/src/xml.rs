@@ -0,0 +1,285 @@
+//! Minimal XML parser for ISO 20022 messages
+//!
+//! Supports elements, attributes, character data, CDATA sections, comments, processing
+//! instructions and predefined or numeric character references. Document type declarations
+//! are not supported. Namespace prefixes are removed from element and attribute names, since
+//! a message uses a single namespace.
+use crate::types::XmlError;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// Maximum nesting depth of elements
+const MAX_DEPTH: usize = 32;
+
+/// Element of an XML document
+#[derive(Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Element {
+	/// Local name of the element
+	pub name: Vec<u8>,
+	/// Attributes of the element by their local names
+	pub attributes: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Child elements in document order
+	pub children: Vec<Element>,
+	/// Character data of the element without leading and trailing whitespace
+	pub text: Vec<u8>,
+}
+
+impl Element {
+	/// First child with the given name
+	pub fn child(&self, name: &str) -> Option<&Element> {
+		self.children.iter().find(|child| child.name == name.as_bytes())
+	}
+
+	/// All children with the given name
+	pub fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+		self.children.iter().filter(move |child| child.name == name.as_bytes())
+	}
+
+	/// Element at the path of child names
+	pub fn path(&self, path: &[&str]) -> Option<&Element> {
+		path.iter().try_fold(self, |element, name| element.child(name))
+	}
+
+	/// First descendant with the given name, in document order
+	pub fn find(&self, name: &str) -> Option<&Element> {
+		self.children.iter().find_map(|child| {
+			if child.name == name.as_bytes() {
+				Some(child)
+			} else {
+				child.find(name)
+			}
+		})
+	}
+
+	/// Value of the attribute with the given name
+	pub fn attribute(&self, name: &str) -> Option<&[u8]> {
+		self.attributes
+			.iter()
+			.find(|(key, _)| key == name.as_bytes())
+			.map(|(_, value)| &value[..])
+	}
+}
+
+/// Parses the root element of an XML document
+pub fn parse(xml: &[u8]) -> Result<Element, XmlError> {
+	let mut parser = Parser { xml, position: 0 };
+
+	// Byte order mark
+	if parser.starts_with(b"\xEF\xBB\xBF") {
+		parser.position += 3;
+	}
+
+	parser.skip_misc()?;
+	let root = parser.element(0)?;
+	parser.skip_misc()?;
+
+	if parser.position != xml.len() {
+		return Err(parser.malformed())
+	}
+
+	Ok(root)
+}
+
+/// Cursor over the bytes of a document
+struct Parser<'a> {
+	xml: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn peek(&self) -> Option<u8> {
+		self.xml.get(self.position).copied()
+	}
+
+	fn starts_with(&self, prefix: &[u8]) -> bool {
+		self.xml[self.position..].starts_with(prefix)
+	}
+
+	fn malformed(&self) -> XmlError {
+		XmlError::Malformed(self.position as u32)
+	}
+
+	fn expect(&mut self, c: u8) -> Result<(), XmlError> {
+		match self.peek() {
+			Some(next) if next == c => {
+				self.position += 1;
+				Ok(())
+			},
+			Some(_) => Err(self.malformed()),
+			None => Err(XmlError::UnexpectedEnd),
+		}
+	}
+
+	fn skip_whitespace(&mut self) {
+		while self.peek().map_or(false, |c| c.is_ascii_whitespace()) {
+			self.position += 1;
+		}
+	}
+
+	/// Returns the bytes before `end` and moves past `end`
+	fn take_until(&mut self, end: &[u8]) -> Result<&'a [u8], XmlError> {
+		let rest = &self.xml[self.position..];
+		let length =
+			rest.windows(end.len()).position(|w| w == end).ok_or(XmlError::UnexpectedEnd)?;
+
+		self.position += length + end.len();
+		Ok(&rest[..length])
+	}
+
+	/// Skips whitespace, comments and processing instructions
+	fn skip_misc(&mut self) -> Result<(), XmlError> {
+		loop {
+			self.skip_whitespace();
+
+			if self.starts_with(b"<?") {
+				self.take_until(b"?>")?;
+			} else if self.starts_with(b"<!--") {
+				self.take_until(b"-->")?;
+			} else {
+				return Ok(())
+			}
+		}
+	}
+
+	/// Local name of an element or attribute
+	fn name(&mut self) -> Result<Vec<u8>, XmlError> {
+		let start = self.position;
+
+		while let Some(c) = self.peek() {
+			if c.is_ascii_whitespace() || matches!(c, b'/' | b'>' | b'=' | b'<') {
+				break
+			}
+			self.position += 1;
+		}
+
+		let name = &self.xml[start..self.position];
+		match name.rsplit(|c| *c == b':').next() {
+			Some(local) if !local.is_empty() && !local.starts_with(b"!") => Ok(local.to_vec()),
+			_ => Err(XmlError::Malformed(start as u32)),
+		}
+	}
+
+	fn element(&mut self, depth: usize) -> Result<Element, XmlError> {
+		if depth >= MAX_DEPTH {
+			return Err(XmlError::TooDeep)
+		}
+
+		self.expect(b'<')?;
+		let mut element = Element { name: self.name()?, ..Default::default() };
+
+		loop {
+			self.skip_whitespace();
+
+			match self.peek() {
+				Some(b'/') => {
+					self.position += 1;
+					self.expect(b'>')?;
+					return Ok(element)
+				},
+				Some(b'>') => {
+					self.position += 1;
+					break
+				},
+				Some(_) => {
+					let name = self.name()?;
+					self.skip_whitespace();
+					self.expect(b'=')?;
+					self.skip_whitespace();
+
+					let quote = match self.peek() {
+						Some(quote @ (b'"' | b'\'')) => quote,
+						Some(_) => return Err(self.malformed()),
+						None => return Err(XmlError::UnexpectedEnd),
+					};
+					self.position += 1;
+
+					let start = self.position;
+					let value = self.take_until(&[quote])?;
+					element.attributes.push((name, unescape(value, start)?));
+				},
+				None => return Err(XmlError::UnexpectedEnd),
+			}
+		}
+
+		let mut text = Vec::new();
+
+		loop {
+			if self.starts_with(b"</") {
+				let start = self.position;
+				self.position += 2;
+
+				if self.name()? != element.name {
+					return Err(XmlError::Malformed(start as u32))
+				}
+
+				self.skip_whitespace();
+				self.expect(b'>')?;
+				break
+			} else if self.starts_with(b"<!--") {
+				self.take_until(b"-->")?;
+			} else if self.starts_with(b"<![CDATA[") {
+				self.position += 9;
+				text.extend_from_slice(self.take_until(b"]]>")?);
+			} else if self.starts_with(b"<?") {
+				self.take_until(b"?>")?;
+			} else if self.starts_with(b"<") {
+				element.children.push(self.element(depth + 1)?);
+			} else if self.peek().is_none() {
+				return Err(XmlError::UnexpectedEnd)
+			} else {
+				let start = self.position;
+				while self.peek().map_or(false, |c| c != b'<') {
+					self.position += 1;
+				}
+				text.extend(unescape(&self.xml[start..self.position], start)?);
+			}
+		}
+
+		let start = text.iter().position(|c| !c.is_ascii_whitespace()).unwrap_or(text.len());
+		let end = text.iter().rposition(|c| !c.is_ascii_whitespace()).map_or(start, |end| end + 1);
+		element.text = text[start..end].to_vec();
+
+		Ok(element)
+	}
+}
+
+/// Replaces character references of the text at `position` of the document
+fn unescape(text: &[u8], position: usize) -> Result<Vec<u8>, XmlError> {
+	let mut unescaped = Vec::with_capacity(text.len());
+	let mut rest = text;
+
+	while let Some(start) = rest.iter().position(|c| *c == b'&') {
+		let malformed = XmlError::Malformed((position + text.len() - rest.len() + start) as u32);
+
+		unescaped.extend_from_slice(&rest[..start]);
+
+		let length = rest[start..].iter().position(|c| *c == b';').ok_or(malformed)?;
+		let reference = &rest[start + 1..start + length];
+
+		let numeric = |digits: &[u8], radix: u32| {
+			core::str::from_utf8(digits)
+				.ok()
+				.filter(|digits| digits.chars().all(|c| c.is_digit(radix)))
+				.and_then(|digits| u32::from_str_radix(digits, radix).ok())
+				.and_then(char::from_u32)
+		};
+
+		let c = match reference {
+			b"amp" => '&',
+			b"lt" => '<',
+			b"gt" => '>',
+			b"quot" => '"',
+			b"apos" => '\'',
+			[b'#', b'x', hex @ ..] => numeric(hex, 16).ok_or(malformed)?,
+			[b'#', decimal @ ..] => numeric(decimal, 10).ok_or(malformed)?,
+			_ => return Err(malformed),
+		};
+
+		unescaped.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+		rest = &rest[start + length + 1..];
+	}
+
+	unescaped.extend_from_slice(rest);
+
+	Ok(unescaped)
+}
@@ -15,7 +15,8 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
+		AccountIdLookup, BlakeTwo256, Block as BlockT, Convert, IdentifyAccount, NumberFor, One,
+		Verify,
 	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	AccountId32, ApplyExtrinsicResult, MultiAddress, MultiSignature, SaturatedConversion,
@@ -29,18 +30,20 @@ use sp_version::RuntimeVersion;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness,
-		StorageInfo,
+		AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU32, ConstU64, ConstU8,
+		KeyOwnerProofSystem, Randomness, StorageInfo,
 	},
 	weights::{
 		constants::{
-			BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_REF_TIME_PER_SECOND,
+			BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_REF_TIME_PER_NANOS,
+			WEIGHT_REF_TIME_PER_SECOND,
 		},
 		IdentityFee, Weight,
 	},
 	StorageValue,
 };
 pub use frame_system::Call as SystemCall;
+use frame_system::{EnsureRoot, EnsureSigned};
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, CurrencyAdapter, Multiplier};
@@ -106,10 +109,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -264,6 +267,35 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ConstFeeMultiplier<FeeMultiplier>;
 }
 
+/// Identifier of an asset.
+pub type AssetId = u32;
+
+/// Asset representing Swiss francs.
+pub const CHF_ASSET_ID: AssetId = 1;
+
+impl pallet_assets::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type RemoveItemsLimit = ConstU32<1000>;
+	type AssetId = AssetId;
+	type AssetIdParameter = codec::Compact<AssetId>;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
+	type AssetAccountDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
+	type MetadataDepositBase = ConstU128<EXISTENTIAL_DEPOSIT>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type CallbackHandle = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
 impl pallet_sudo::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type RuntimeCall = RuntimeCall;
@@ -282,13 +314,72 @@ parameter_types! {
 	pub const MaxIbanLength: u32 = 64;
 	#[derive(TypeInfo, Encode, Decode, Clone, PartialEq, Eq , MaxEncodedLen, Debug)]
 	pub const MaxStringLength: u32 = 256;
-	/// OCW account
+	/// OCW account, the only OCW authority before authorities were managed on chain
 	pub OcwAccount: AccountId = AccountId::from(AccountId32::from(
 		hex_literal::hex!("004771ae35f923e82e77fafd1f4b1878cd4b372a7406c7b88125119f5ffbdc29")
 	));
 	/// Bound for statements
 	#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo)]
 	pub const MaxStatements: u32 = 255;
+	/// Number of blocks queued statements wait for their receipt
+	pub const StatementsExpiry: BlockNumber = HOURS;
+	/// Number of blocks an IBAN link waits for its micro-deposit
+	pub const LinkExpiry: BlockNumber = 7 * DAYS;
+	/// Number of accounts that can wait for a micro-deposit from the same IBAN
+	pub const MaxPendingLinks: u32 = 8;
+	/// Number of failed unpeg attempts before a burn request is refunded
+	pub const MaxUnpegAttempts: u32 = 5;
+	/// Number of blocks before a failed unpeg request is retried
+	pub const UnpegRetryDelay: BlockNumber = 10 * MINUTES;
+	/// Maximum number of burn requests sent in one unpeg batch
+	pub const MaxUnpegBatch: u32 = 50;
+	/// Milliseconds to wait for the response of the EBICS service
+	pub const HttpTimeout: u64 = 2_000;
+	/// Number of retries of requests that timed out or failed with a server error
+	pub const HttpRetries: u32 = 2;
+	/// Number of decimal places of fiat amounts
+	pub const FiatDecimals: u32 = 10;
+	/// Development accounts are endowed at genesis, so their balances never match the bank
+	pub const PauseMintingOnMismatch: bool = false;
+	/// Number of blocks a processed bank transaction is remembered
+	pub const ProcessedTransactionsExpiry: BlockNumber = 30 * DAYS;
+	/// EBICS service of the demo serves JSON statements
+	pub const FetchedStatementFormat: fiat_ramps::types::StatementFormat =
+		fiat_ramps::types::StatementFormat::Json;
+	/// Succinct receipts are a few hundred kilobytes when JSON encoded
+	pub const MaxReceiptLength: u32 = 1024 * 1024;
+	/// Estimate of verifying the seal of a succinct receipt in the runtime, until it is
+	/// benchmarked. The proof covers the image ids and a queued batch of up to 256 KiB.
+	pub const ReceiptVerificationWeight: Weight =
+		Weight::from_parts(WEIGHT_REF_TIME_PER_SECOND / 4, 256 * 1024);
+	/// Estimate of decoding and hashing a receipt, 100 ns per byte, the receipt is part of
+	/// the extrinsic and adds nothing to the proof
+	pub const ReceiptByteWeight: Weight = Weight::from_parts(100 * WEIGHT_REF_TIME_PER_NANOS, 0);
+	/// Image ids of the current and previous guest programs
+	pub const MaxImageIds: u32 = 8;
+	/// Maximum number of offchain worker authorities
+	pub const MaxOcwAuthorities: u32 = 16;
+	/// Statement batches an authority attests before the others catch up or they expire
+	pub const MaxPendingAttestations: u32 = 32;
+}
+
+/// Fiat currencies supported by the runtime
+///
+/// Euro is the native currency, Swiss francs are held in an asset
+pub struct FiatCurrencies;
+
+impl Convert<fiat_ramps::types::CurrencyCode, Option<fiat_ramps::types::CurrencyId<AssetId>>>
+	for FiatCurrencies
+{
+	fn convert(
+		code: fiat_ramps::types::CurrencyCode,
+	) -> Option<fiat_ramps::types::CurrencyId<AssetId>> {
+		match &code {
+			b"EUR" => Some(fiat_ramps::types::CurrencyId::Native),
+			b"CHF" => Some(fiat_ramps::types::CurrencyId::Asset(CHF_ASSET_ID)),
+			_ => None,
+		}
+	}
 }
 
 impl fiat_ramps::Config for Runtime {
@@ -300,8 +391,27 @@ impl fiat_ramps::Config for Runtime {
 	type UnsignedPriority = UnsignedPriority;
 	type MaxIbanLength = MaxIbanLength;
 	type MaxStringLength = MaxStringLength;
-	type OcwAccount = OcwAccount;
+	type MaxOcwAuthorities = MaxOcwAuthorities;
+	type MaxPendingAttestations = MaxPendingAttestations;
 	type MaxStatements = MaxStatements;
+	type StatementsExpiry = StatementsExpiry;
+	type LinkExpiry = LinkExpiry;
+	type MaxPendingLinks = MaxPendingLinks;
+	type MaxUnpegAttempts = MaxUnpegAttempts;
+	type UnpegRetryDelay = UnpegRetryDelay;
+	type MaxUnpegBatch = MaxUnpegBatch;
+	type HttpTimeout = HttpTimeout;
+	type HttpRetries = HttpRetries;
+	type FiatDecimals = FiatDecimals;
+	type PauseMintingOnMismatch = PauseMintingOnMismatch;
+	type ProcessedTransactionsExpiry = ProcessedTransactionsExpiry;
+	type StatementFormat = FetchedStatementFormat;
+	type MaxReceiptLength = MaxReceiptLength;
+	type ReceiptVerificationWeight = ReceiptVerificationWeight;
+	type ReceiptByteWeight = ReceiptByteWeight;
+	type MaxImageIds = MaxImageIds;
+	type Assets = Assets;
+	type CurrencyFor = FiatCurrencies;
 }
 
 impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
@@ -370,6 +480,7 @@ construct_runtime!(
 		TransactionPayment: pallet_transaction_payment,
 		Sudo: pallet_sudo,
 		FiatRamps: fiat_ramps,
+		Assets: pallet_assets,
 	}
 );
 
@@ -394,6 +505,8 @@ pub type SignedExtra = (
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
+/// Storage migrations to apply on runtime upgrade.
+pub type Migrations = (fiat_ramps::migrations::v1::MigrateToV1<Runtime, OcwAccount>,);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -401,8 +514,30 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
+/// Burn request of the fiat-ramps pallet
+pub type FiatRampsBurnRequest = fiat_ramps::types::BurnRequestOf<Runtime>;
+/// Summary of a statement batch queued in the fiat-ramps pallet
+pub type FiatRampsQueuedStatements = fiat_ramps::types::QueuedStatementsStatusOf<Runtime>;
+
+sp_api::decl_runtime_apis! {
+	/// API to query the state of the fiat-ramps pallet
+	pub trait FiatRampsApi {
+		/// Account linked to the IBAN
+		fn account_by_iban(iban: Vec<u8>) -> Option<AccountId>;
+		/// IBAN linked to the account
+		fn iban_by_account(account: AccountId) -> Option<Vec<u8>>;
+		/// Burn requests of the account whose funds are still held in escrow
+		fn pending_burn_requests(account: AccountId) -> Vec<FiatRampsBurnRequest>;
+		/// Statement batches that are waiting for verification
+		fn queued_statements() -> Vec<FiatRampsQueuedStatements>;
+		/// Most recently activated image id of the risc0 module that verifies the statements
+		fn risc0_image_id() -> Option<[u8; 32]>;
+	}
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -536,6 +671,28 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl FiatRampsApi<Block> for Runtime {
+		fn account_by_iban(iban: Vec<u8>) -> Option<AccountId> {
+			FiatRamps::iban_to_account(fiat_ramps::types::IbanOf::<Runtime>::try_from(iban).ok()?)
+		}
+
+		fn iban_by_account(account: AccountId) -> Option<Vec<u8>> {
+			FiatRamps::iban_of(&account).map(|iban| iban.into_inner())
+		}
+
+		fn pending_burn_requests(account: AccountId) -> Vec<FiatRampsBurnRequest> {
+			FiatRamps::pending_burn_requests_of(&account)
+		}
+
+		fn queued_statements() -> Vec<FiatRampsQueuedStatements> {
+			FiatRamps::queued_statements_status()
+		}
+
+		fn risc0_image_id() -> Option<[u8; 32]> {
+			FiatRamps::risc0_image_id()
+		}
+	}
+
 	impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Nonce> for Runtime {
 		fn account_nonce(account: AccountId) -> Nonce {
 			System::account_nonce(account)